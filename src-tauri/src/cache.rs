@@ -1,193 +1,467 @@
-use crate::db::{Database, DataSource, MetadataCache};
-use crate::metadata::{MetadataFetcher, TableInfo, KafkaTopicInfo, SchemaInfo};
-use anyhow::{Context, Result};
+use crate::connection::DataSourceKind;
+use crate::db::{DataSource, Database, DbPool, MetadataCache};
+use crate::metadata::{
+    KafkaTopicInfo, MetadataFetcher, PostgresObjects, RedisInfoSection, SchemaInfo, TableInfo,
+};
+use crate::offline;
+use crate::snapshot::SnapshotManager;
+use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
-use serde_json::Value;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+pub const METADATA_REVALIDATED_EVENT: &str = "metadata-revalidated";
+
+static APP_HANDLE: OnceCell<AppHandle> = OnceCell::new();
+
+// Cache entries with a background revalidation in flight
+static REVALIDATING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedMetadata<T> {
+    pub data: T,
+    pub stale: bool,
+    pub cached_at: DateTime<Utc>,
+    pub revalidating: bool,
+    pub error: Option<String>, // Why fresh data could not be fetched
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataRevalidatedEvent {
+    pub data_source_id: i64,
+    pub cache_type: String,
+    pub cache_key: String,
+    pub error: Option<String>,
+}
 
 pub struct CacheManager;
 
 impl CacheManager {
-    const CACHE_EXPIRY_HOURS: i64 = 24;
+    // Used when no cache policy matches
+    const DEFAULT_CACHE_EXPIRY_HOURS: i64 = 24;
+    // Expired entries are kept this long so they can still be served stale or offline
+    const STALE_RETENTION_DAYS: i64 = 30;
+
+    /// Registers the app handle used to notify the UI when a revalidation finishes.
+    pub fn init(app: AppHandle) {
+        let _ = APP_HANDLE.set(app);
+    }
 
     pub async fn get_tables_cached(
+        db: &DbPool,
         data_source: &DataSource,
         force_refresh: bool,
-    ) -> Result<Vec<TableInfo>> {
-        let cache_key = format!("tables:{}", data_source.database.as_deref().unwrap_or("default"));
-        
-        if !force_refresh {
-            if let Some(cached) = Self::get_cache(data_source.id, "tables", &cache_key)? {
-                if let Ok(tables) = serde_json::from_value::<Vec<TableInfo>>(cached.cache_data.clone()) {
-                    return Ok(tables);
-                }
-            }
-        }
-        
-        // Fetch fresh data
-        let tables = MetadataFetcher::get_tables(data_source).await?;
-        
-        // Cache the result
-        let cache_data = serde_json::to_value(&tables)?;
-        let expires_at = Utc::now() + Duration::hours(Self::CACHE_EXPIRY_HOURS);
-        
-        let cache = MetadataCache {
-            id: 0,
-            data_source_id: data_source.id,
-            cache_type: "tables".to_string(),
-            cache_key: cache_key.clone(),
-            cache_data,
-            cached_at: Utc::now(),
-            expires_at: Some(expires_at),
-        };
-        
-        Self::save_cache(&cache)?;
-        Ok(tables)
+    ) -> Result<CachedMetadata<Vec<TableInfo>>> {
+        let cache_key = Self::tables_cache_key(data_source);
+        Self::get_or_fetch(
+            db,
+            data_source,
+            "tables",
+            &cache_key,
+            force_refresh,
+            |ds| async move { MetadataFetcher::get_tables(&ds).await },
+        )
+        .await
     }
 
     pub async fn get_table_structure_cached(
+        db: &DbPool,
         data_source: &DataSource,
         schema: Option<&str>,
         table_name: &str,
         force_refresh: bool,
-    ) -> Result<TableInfo> {
-        let cache_key = format!("table_structure:{}:{}", 
-            schema.unwrap_or("default"), 
+    ) -> Result<CachedMetadata<TableInfo>> {
+        let cache_key = format!(
+            "table_structure:{}:{}",
+            schema.unwrap_or("default"),
             table_name
         );
-        
-        if !force_refresh {
-            if let Some(cached) = Self::get_cache(data_source.id, "table_structure", &cache_key)? {
-                if let Ok(table) = serde_json::from_value::<TableInfo>(cached.cache_data.clone()) {
-                    return Ok(table);
+        let schema = schema.map(|s| s.to_string());
+        let table_name = table_name.to_string();
+        Self::get_or_fetch(
+            db,
+            data_source,
+            "table_structure",
+            &cache_key,
+            force_refresh,
+            move |ds| {
+                let schema = schema.clone();
+                let table_name = table_name.clone();
+                async move {
+                    MetadataFetcher::get_table_structure(&ds, schema.as_deref(), &table_name).await
                 }
-            }
-        }
-        
-        // Fetch fresh data
-        let table = MetadataFetcher::get_table_structure(data_source, schema, table_name).await?;
-        
-        // Cache the result
-        let cache_data = serde_json::to_value(&table)?;
-        let expires_at = Utc::now() + Duration::hours(Self::CACHE_EXPIRY_HOURS);
-        
-        let cache = MetadataCache {
-            id: 0,
-            data_source_id: data_source.id,
-            cache_type: "table_structure".to_string(),
-            cache_key: cache_key.clone(),
-            cache_data,
-            cached_at: Utc::now(),
-            expires_at: Some(expires_at),
-        };
-        
-        Self::save_cache(&cache)?;
-        Ok(table)
+            },
+        )
+        .await
     }
 
     pub async fn get_kafka_topics_cached(
+        db: &DbPool,
         data_source: &DataSource,
         force_refresh: bool,
-    ) -> Result<Vec<KafkaTopicInfo>> {
-        let cache_key = "topics".to_string();
-        
-        if !force_refresh {
-            if let Some(cached) = Self::get_cache(data_source.id, "topics", &cache_key)? {
-                if let Ok(topics) = serde_json::from_value::<Vec<KafkaTopicInfo>>(cached.cache_data.clone()) {
-                    return Ok(topics);
-                }
-            }
-        }
-        
-        // Fetch fresh data
-        let topics = MetadataFetcher::get_kafka_topics(data_source).await?;
-        
-        // Cache the result
-        let cache_data = serde_json::to_value(&topics)?;
-        let expires_at = Utc::now() + Duration::hours(Self::CACHE_EXPIRY_HOURS);
-        
-        let cache = MetadataCache {
-            id: 0,
-            data_source_id: data_source.id,
-            cache_type: "topics".to_string(),
-            cache_key: cache_key.clone(),
-            cache_data,
-            cached_at: Utc::now(),
-            expires_at: Some(expires_at),
-        };
-        
-        Self::save_cache(&cache)?;
-        Ok(topics)
+    ) -> Result<CachedMetadata<Vec<KafkaTopicInfo>>> {
+        Self::get_or_fetch(
+            db,
+            data_source,
+            "topics",
+            "topics",
+            force_refresh,
+            |ds| async move { MetadataFetcher::get_kafka_topics(&ds).await },
+        )
+        .await
+    }
+
+    pub async fn list_databases_cached(
+        db: &DbPool,
+        data_source: &DataSource,
+        force_refresh: bool,
+    ) -> Result<CachedMetadata<Vec<String>>> {
+        Self::get_or_fetch(
+            db,
+            data_source,
+            "databases",
+            "databases",
+            force_refresh,
+            |ds| async move { MetadataFetcher::list_databases(&ds).await },
+        )
+        .await
+    }
+
+    // "schemas" already holds Schema Registry subjects, hence the longer cache type
+    pub async fn list_schemas_cached(
+        db: &DbPool,
+        data_source: &DataSource,
+        database: Option<&str>,
+        force_refresh: bool,
+    ) -> Result<CachedMetadata<Vec<String>>> {
+        let cache_key = format!("database_schemas:{}", database.unwrap_or("default"));
+        let database = database.map(|s| s.to_string());
+        Self::get_or_fetch(
+            db,
+            data_source,
+            "database_schemas",
+            &cache_key,
+            force_refresh,
+            move |ds| {
+                let database = database.clone();
+                async move { MetadataFetcher::list_schemas(&ds, database.as_deref()).await }
+            },
+        )
+        .await
+    }
+
+    pub async fn get_postgres_objects_cached(
+        db: &DbPool,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        force_refresh: bool,
+    ) -> Result<CachedMetadata<PostgresObjects>> {
+        let cache_key = format!("postgres_objects:{}", schema.unwrap_or("default"));
+        let schema = schema.map(|s| s.to_string());
+        Self::get_or_fetch(
+            db,
+            data_source,
+            "postgres_objects",
+            &cache_key,
+            force_refresh,
+            move |ds| {
+                let schema = schema.clone();
+                async move { MetadataFetcher::get_postgres_objects(&ds, schema.as_deref()).await }
+            },
+        )
+        .await
+    }
+
+    pub async fn get_redis_info_cached(
+        db: &DbPool,
+        data_source: &DataSource,
+        force_refresh: bool,
+    ) -> Result<CachedMetadata<Vec<RedisInfoSection>>> {
+        Self::get_or_fetch(
+            db,
+            data_source,
+            "redis_info",
+            "redis_info",
+            force_refresh,
+            |ds| async move { MetadataFetcher::get_redis_info(&ds).await },
+        )
+        .await
     }
 
     pub async fn get_schema_registry_schemas_cached(
+        db: &DbPool,
         data_source: &DataSource,
         force_refresh: bool,
-    ) -> Result<Vec<SchemaInfo>> {
-        let cache_key = "schemas".to_string();
-        
+    ) -> Result<CachedMetadata<Vec<SchemaInfo>>> {
+        Self::get_or_fetch(
+            db,
+            data_source,
+            "schemas",
+            "schemas",
+            force_refresh,
+            |ds| async move { MetadataFetcher::get_schema_registry_schemas(&ds).await },
+        )
+        .await
+    }
+
+    pub async fn get_schema_versions_cached(
+        db: &DbPool,
+        data_source: &DataSource,
+        subject: &str,
+        force_refresh: bool,
+    ) -> Result<CachedMetadata<Vec<SchemaInfo>>> {
+        let cache_key = format!("schema_versions:{}", subject);
+        let subject = subject.to_string();
+        Self::get_or_fetch(
+            db,
+            data_source,
+            "schema_versions",
+            &cache_key,
+            force_refresh,
+            move |ds| {
+                let subject = subject.clone();
+                async move { MetadataFetcher::get_schema_versions(&ds, &subject).await }
+            },
+        )
+        .await
+    }
+
+    /// Stale-while-revalidate lookup shared by every cached metadata type:
+    /// - offline: whatever is cached, expired or not, and never the network
+    /// - fresh cache hit: returned as is
+    /// - expired hit: returned with `stale: true` while a background task re-fetches it
+    /// - miss or forced refresh: fetched; if that fails, any stale copy is returned with the error
+    async fn get_or_fetch<T, F, Fut>(
+        db: &DbPool,
+        data_source: &DataSource,
+        cache_type: &str,
+        cache_key: &str,
+        force_refresh: bool,
+        fetch: F,
+    ) -> Result<CachedMetadata<T>>
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+        F: Fn(DataSource) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let mut cached = Self::get_cache(db, data_source.id, cache_type, cache_key)
+            .await?
+            .and_then(|entry| {
+                serde_json::from_value::<T>(entry.cache_data.clone())
+                    .ok()
+                    .map(|data| (data, entry))
+            });
+
+        if offline::is_enabled() {
+            return match cached {
+                Some((data, entry)) => Ok(CachedMetadata {
+                    data,
+                    stale: entry.is_expired(),
+                    cached_at: entry.cached_at,
+                    revalidating: false,
+                    error: None,
+                }),
+                None => Err(anyhow::anyhow!(
+                    "Offline mode is enabled and no {} metadata is cached for {}",
+                    cache_type,
+                    data_source.name
+                )),
+            };
+        }
+
         if !force_refresh {
-            if let Some(cached) = Self::get_cache(data_source.id, "schemas", &cache_key)? {
-                if let Ok(schemas) = serde_json::from_value::<Vec<SchemaInfo>>(cached.cache_data.clone()) {
-                    return Ok(schemas);
+            if let Some((data, entry)) = cached.take() {
+                let stale = entry.is_expired();
+                if stale {
+                    Self::revalidate_in_background(
+                        db.clone(),
+                        data_source.clone(),
+                        cache_type,
+                        cache_key,
+                        fetch,
+                    );
                 }
+                return Ok(CachedMetadata {
+                    data,
+                    stale,
+                    cached_at: entry.cached_at,
+                    revalidating: stale,
+                    error: None,
+                });
             }
         }
-        
-        // Fetch fresh data
-        let schemas = MetadataFetcher::get_schema_registry_schemas(data_source).await?;
-        
-        // Cache the result
-        let cache_data = serde_json::to_value(&schemas)?;
-        let expires_at = Utc::now() + Duration::hours(Self::CACHE_EXPIRY_HOURS);
-        
-        let cache = MetadataCache {
-            id: 0,
-            data_source_id: data_source.id,
-            cache_type: "schemas".to_string(),
-            cache_key: cache_key.clone(),
-            cache_data,
-            cached_at: Utc::now(),
-            expires_at: Some(expires_at),
-        };
-        
-        Self::save_cache(&cache)?;
-        Ok(schemas)
-    }
-
-    pub fn clear_cache(data_source_id: i64, cache_type: Option<&str>) -> Result<()> {
-        use crate::db::get_db;
-        let db_guard = get_db()?;
-        if let Some(ref db) = *db_guard {
-            db.delete_metadata_cache(data_source_id, cache_type)?;
+
+        match fetch(data_source.clone()).await {
+            Ok(data) => {
+                Self::store(
+                    db,
+                    data_source.id,
+                    cache_type,
+                    cache_key,
+                    serde_json::to_value(&data)?,
+                )
+                .await?;
+                Ok(CachedMetadata {
+                    data,
+                    stale: false,
+                    cached_at: Utc::now(),
+                    revalidating: false,
+                    error: None,
+                })
+            }
+            Err(e) => match cached {
+                Some((data, entry)) => Ok(CachedMetadata {
+                    data,
+                    stale: true,
+                    cached_at: entry.cached_at,
+                    revalidating: false,
+                    error: Some(e.to_string()),
+                }),
+                None => Err(e),
+            },
         }
-        Ok(())
     }
 
-    fn get_cache(
-        data_source_id: i64,
+    fn revalidate_in_background<T, F, Fut>(
+        db: DbPool,
+        data_source: DataSource,
         cache_type: &str,
         cache_key: &str,
-    ) -> Result<Option<MetadataCache>> {
-        use crate::db::get_db;
-        // Get cache data before any async operations
-        let cached_data = {
-            let db_guard = get_db()?;
-            if let Some(ref db) = *db_guard {
-                db.get_metadata_cache(data_source_id, cache_type, cache_key)?
-            } else {
-                None
+        fetch: F,
+    ) where
+        T: Serialize + Send + 'static,
+        F: Fn(DataSource) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let in_flight_key = format!("{}:{}:{}", data_source.id, cache_type, cache_key);
+        if !REVALIDATING.lock().unwrap().insert(in_flight_key.clone()) {
+            return;
+        }
+
+        let cache_type = cache_type.to_string();
+        let cache_key = cache_key.to_string();
+        tauri::async_runtime::spawn(async move {
+            let data_source_id = data_source.id;
+            let result = match fetch(data_source).await {
+                // Serialized before awaiting so the task does not hold on to `data`
+                Ok(data) => match serde_json::to_value(&data) {
+                    Ok(cache_data) => {
+                        Self::store(&db, data_source_id, &cache_type, &cache_key, cache_data).await
+                    }
+                    Err(e) => Err(e.into()),
+                },
+                Err(e) => Err(e),
+            };
+            REVALIDATING.lock().unwrap().remove(&in_flight_key);
+
+            let error = result.err().map(|e| e.to_string());
+            if let Some(ref e) = error {
+                eprintln!(
+                    "Failed to revalidate {} for data source {}: {}",
+                    cache_key, data_source_id, e
+                );
+            }
+
+            if let Some(app) = APP_HANDLE.get() {
+                let event = MetadataRevalidatedEvent {
+                    data_source_id,
+                    cache_type,
+                    cache_key,
+                    error,
+                };
+                if let Err(e) = app.emit(METADATA_REVALIDATED_EVENT, event) {
+                    eprintln!("Failed to emit {} event: {}", METADATA_REVALIDATED_EVENT, e);
+                }
             }
-        };
-        Ok(cached_data)
-    }
-    
-    fn save_cache(cache: &MetadataCache) -> Result<()> {
-        use crate::db::get_db;
-        let db_guard = get_db()?;
-        if let Some(ref db) = *db_guard {
-            db.save_metadata_cache(cache)?;
+        });
+    }
+
+    pub async fn clear_cache(
+        db: &DbPool,
+        data_source_id: i64,
+        cache_type: Option<&str>,
+    ) -> Result<()> {
+        let cache_type = cache_type.map(|s| s.to_string());
+        db.run(move |db| db.delete_metadata_cache(data_source_id, cache_type.as_deref()))
+            .await
+    }
+
+    /// The browsed schema is part of the key so changing it in the options never
+    /// serves the table list of the previous one.
+    pub fn tables_cache_key(data_source: &DataSource) -> String {
+        let database = data_source.database.as_deref().unwrap_or("default");
+        match data_source.data_type {
+            DataSourceKind::Sqlserver => match data_source.sqlserver_options().schema {
+                Some(schema) => format!("tables:{}:{}", database, schema),
+                None => format!("tables:{}", database),
+            },
+            DataSourceKind::Postgresql => match data_source.postgres_options().schema {
+                Some(schema) => format!("tables:{}:{}", database, schema),
+                None => format!("tables:{}", database),
+            },
+            _ => format!("tables:{}", database),
         }
-        Ok(())
     }
-}
 
+    /// Replaces a cache entry with fresh data, applying the matching cache policy.
+    pub async fn store(
+        db: &DbPool,
+        data_source_id: i64,
+        cache_type: &str,
+        cache_key: &str,
+        cache_data: serde_json::Value,
+    ) -> Result<()> {
+        let cache_type = cache_type.to_string();
+        let cache_key = cache_key.to_string();
+        db.run(move |db| {
+            let cache = MetadataCache {
+                id: 0,
+                data_source_id,
+                expires_at: Self::expires_at(db, data_source_id, &cache_type)?,
+                cache_type,
+                cache_key,
+                cache_data,
+                cached_at: Utc::now(),
+            };
+            db.save_metadata_cache(&cache)?;
+            SnapshotManager::record_if_changed(db, &cache)
+        })
+        .await
+    }
+
+    /// Expiry for a new cache entry; `None` when the matching policy never expires.
+    fn expires_at(
+        db: &Database,
+        data_source_id: i64,
+        cache_type: &str,
+    ) -> Result<Option<DateTime<Utc>>> {
+        Ok(match db.resolve_cache_ttl(data_source_id, cache_type)? {
+            Some(Some(seconds)) => Some(Utc::now() + Duration::seconds(seconds)),
+            Some(None) => None,
+            None => Some(Utc::now() + Duration::hours(Self::DEFAULT_CACHE_EXPIRY_HOURS)),
+        })
+    }
+
+    /// Deletes entries that expired longer ago than the stale retention window.
+    pub fn purge_expired(db: &Database) -> Result<usize> {
+        let cutoff = Utc::now() - Duration::days(Self::STALE_RETENTION_DAYS);
+        db.purge_expired_metadata_cache(cutoff)
+    }
+
+    async fn get_cache(
+        db: &DbPool,
+        data_source_id: i64,
+        cache_type: &str,
+        cache_key: &str,
+    ) -> Result<Option<MetadataCache>> {
+        let cache_type = cache_type.to_string();
+        let cache_key = cache_key.to_string();
+        db.run(move |db| db.get_metadata_cache(data_source_id, &cache_type, &cache_key))
+            .await
+    }
+}
@@ -1,78 +1,84 @@
-use crate::cache::CacheManager;
-use crate::connection::ConnectionManager;
-use crate::db::{get_db, DataSource};
+use crate::cache::{CacheManager, CachedMetadata};
+use crate::connection::{
+    ConnectionDiagnostics, ConnectionManager, DataSourceKind, DataSourceOptions, ProxyConfig,
+    ProxyType, SshConfig,
+};
+use crate::connector::{Capability, ConnectorRegistry, QueryResult};
+use crate::db::{DataSource, DatabaseLocation, DbPool};
 use crate::metadata::{MetadataFetcher, TableComparison};
+use crate::refresh::{MetadataChangedEvent, RefreshScheduler};
+use crate::snapshot::{SnapshotDiff, SnapshotManager};
 use anyhow::{Context, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use tauri::State;
 
 // Context commands
 #[tauri::command]
-pub async fn create_context(name: String, description: Option<String>) -> Result<i64, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    if let Some(ref db) = *db {
-        db.create_context(&name, description.as_deref())
-            .map_err(|e| e.to_string())
-    } else {
-        Err("Database not initialized".to_string())
-    }
+pub async fn create_context(
+    db: State<'_, DbPool>,
+    name: String,
+    description: Option<String>,
+) -> Result<i64, String> {
+    db.run(move |db| db.create_context(&name, description.as_deref()))
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn list_contexts() -> Result<Vec<crate::db::Context>, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    if let Some(ref db) = *db {
-        db.list_contexts().map_err(|e| e.to_string())
-    } else {
-        Err("Database not initialized".to_string())
-    }
+pub async fn list_contexts(db: State<'_, DbPool>) -> Result<Vec<crate::db::Context>, String> {
+    db.run(move |db| db.list_contexts())
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn update_context(
+    db: State<'_, DbPool>,
     id: i64,
     name: String,
     description: Option<String>,
 ) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    if let Some(ref db) = *db {
-        db.update_context(id, &name, description.as_deref())
-            .map_err(|e| e.to_string())
-    } else {
-        Err("Database not initialized".to_string())
-    }
+    db.run(move |db| db.update_context(id, &name, description.as_deref()))
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn delete_context(id: i64) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    if let Some(ref db) = *db {
-        db.delete_context(id).map_err(|e| e.to_string())
-    } else {
-        Err("Database not initialized".to_string())
-    }
+pub async fn delete_context(db: State<'_, DbPool>, id: i64) -> Result<(), String> {
+    db.run(move |db| db.delete_context(id))
+        .await
+        .map_err(|e| e.to_string())
 }
 
 // DataSource commands
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CreateDataSourceRequest {
     pub context_id: i64,
     pub name: String,
-    pub data_type: String,
+    pub data_type: DataSourceKind,
     pub host: String,
     pub port: u16,
     pub database: Option<String>,
     pub username: String,
     pub password: String,
-    pub proxy_type: Option<String>,
-    pub proxy_config: Option<serde_json::Value>,
-    pub ssh_config: Option<serde_json::Value>,
+    #[serde(default, deserialize_with = "ProxyType::deserialize_optional")]
+    pub proxy_type: Option<ProxyType>,
+    pub proxy_config: Option<ProxyConfig>,
+    pub ssh_config: Option<SshConfig>,
     pub schema_registry_url: Option<String>,
+    #[serde(default)]
+    pub schema_registry_config: Option<serde_json::Value>,
+    #[serde(default)]
+    pub options: Option<DataSourceOptions>,
 }
 
 #[tauri::command]
-pub async fn create_data_source(req: CreateDataSourceRequest) -> Result<i64, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    if let Some(ref db) = *db {
+pub async fn create_data_source(
+    db: State<'_, DbPool>,
+    req: CreateDataSourceRequest,
+) -> Result<i64, String> {
+    db.run(move |db| {
         let data_source = DataSource {
             id: 0,
             context_id: req.context_id,
@@ -87,97 +93,145 @@ pub async fn create_data_source(req: CreateDataSourceRequest) -> Result<i64, Str
             proxy_config: req.proxy_config,
             ssh_config: req.ssh_config,
             schema_registry_url: req.schema_registry_url,
+            schema_registry_config: req.schema_registry_config,
+            options: req.options,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
-        db.create_data_source(&data_source).map_err(|e| e.to_string())
-    } else {
-        Err("Database not initialized".to_string())
-    }
+        data_source.validate()?;
+        db.create_data_source(&data_source)
+    })
+    .await
+    .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn list_data_sources(context_id: Option<i64>) -> Result<Vec<DataSource>, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    if let Some(ref db) = *db {
-        db.list_data_sources(context_id).map_err(|e| e.to_string())
-    } else {
-        Err("Database not initialized".to_string())
-    }
+pub async fn list_data_sources(
+    db: State<'_, DbPool>,
+    context_id: Option<i64>,
+) -> Result<Vec<DataSource>, String> {
+    db.run(move |db| db.list_data_sources(context_id))
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_data_source(id: i64) -> Result<DataSource, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    if let Some(ref db) = *db {
-        db.get_data_source(id).map_err(|e| e.to_string())
-    } else {
-        Err("Database not initialized".to_string())
-    }
+pub async fn get_data_source(db: State<'_, DbPool>, id: i64) -> Result<DataSource, String> {
+    db.run(move |db| db.get_data_source(id))
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn update_data_source(data_source: DataSource) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    if let Some(ref db) = *db {
-        db.update_data_source(&data_source).map_err(|e| e.to_string())
-    } else {
-        Err("Database not initialized".to_string())
-    }
+pub async fn update_data_source(
+    db: State<'_, DbPool>,
+    data_source: DataSource,
+) -> Result<(), String> {
+    data_source.validate().map_err(|e| e.to_string())?;
+    db.run(move |db| {
+        db.transaction(|db| {
+            let current = db.get_data_source(data_source.id)?;
+            db.update_data_source(&data_source)?;
+            // Cached tables and database/schema lists describe what was connected to before
+            if connection_changed(&current, &data_source) {
+                db.delete_metadata_cache(data_source.id, None)?;
+            }
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+fn connection_changed(current: &DataSource, updated: &DataSource) -> bool {
+    current.data_type != updated.data_type
+        || current.host != updated.host
+        || current.port != updated.port
+        || current.database != updated.database
+        || current.username != updated.username
+        || current.options != updated.options
 }
 
+/// JSON schemas of the data source settings, per kind, for rendering forms.
 #[tauri::command]
-pub async fn delete_data_source(id: i64) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    if let Some(ref db) = *db {
-        db.delete_data_source(id).map_err(|e| e.to_string())
-    } else {
-        Err("Database not initialized".to_string())
-    }
+pub async fn get_data_source_schemas() -> Result<serde_json::Value, String> {
+    Ok(crate::connection::options::json_schemas())
 }
 
 #[tauri::command]
-pub async fn test_connection(data_source: DataSource) -> Result<(), String> {
+pub async fn delete_data_source(db: State<'_, DbPool>, id: i64) -> Result<(), String> {
+    db.run(move |db| db.delete_data_source(id))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn test_connection(data_source: DataSource) -> Result<ConnectionDiagnostics, String> {
     ConnectionManager::test_connection(&data_source)
         .await
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_connector_capabilities(data_type: DataSourceKind) -> Result<Vec<Capability>, String> {
+    Ok(ConnectorRegistry::global().capabilities(data_type))
+}
+
+// Query commands
+const DEFAULT_MAX_ROWS: usize = 1000;
+
+#[tauri::command]
+pub async fn execute_query(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    sql: String,
+    max_rows: Option<usize>,
+) -> Result<QueryResult, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    ConnectionManager::execute_query(&data_source, &sql, max_rows.unwrap_or(DEFAULT_MAX_ROWS))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Local store commands
+#[tauri::command]
+pub async fn get_database_location(db: State<'_, DbPool>) -> Result<DatabaseLocation, String> {
+    Ok(db.location().clone())
+}
+
 // Metadata commands
 #[tauri::command]
 pub async fn get_tables(
+    db: State<'_, DbPool>,
     data_source_id: i64,
     force_refresh: bool,
-) -> Result<Vec<crate::metadata::TableInfo>, String> {
-    let data_source = {
-        let db = get_db().map_err(|e| e.to_string())?;
-        if let Some(ref db) = *db {
-            db.get_data_source(data_source_id).map_err(|e| e.to_string())?
-        } else {
-            return Err("Database not initialized".to_string());
-        }
-    };
-    CacheManager::get_tables_cached(&data_source, force_refresh)
+) -> Result<CachedMetadata<Vec<crate::metadata::TableInfo>>, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    CacheManager::get_tables_cached(&db, &data_source, force_refresh)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_table_structure(
+    db: State<'_, DbPool>,
     data_source_id: i64,
     schema: Option<String>,
     table_name: String,
     force_refresh: bool,
-) -> Result<crate::metadata::TableInfo, String> {
-    let data_source = {
-        let db = get_db().map_err(|e| e.to_string())?;
-        if let Some(ref db) = *db {
-            db.get_data_source(data_source_id).map_err(|e| e.to_string())?
-        } else {
-            return Err("Database not initialized".to_string());
-        }
-    };
+) -> Result<CachedMetadata<crate::metadata::TableInfo>, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
     CacheManager::get_table_structure_cached(
+        &db,
         &data_source,
         schema.as_deref(),
         &table_name,
@@ -189,71 +243,422 @@ pub async fn get_table_structure(
 
 #[tauri::command]
 pub async fn get_kafka_topics(
+    db: State<'_, DbPool>,
     data_source_id: i64,
     force_refresh: bool,
-) -> Result<Vec<crate::metadata::KafkaTopicInfo>, String> {
-    let data_source = {
-        let db = get_db().map_err(|e| e.to_string())?;
-        if let Some(ref db) = *db {
-            db.get_data_source(data_source_id).map_err(|e| e.to_string())?
-        } else {
-            return Err("Database not initialized".to_string());
-        }
-    };
-    CacheManager::get_kafka_topics_cached(&data_source, force_refresh)
+) -> Result<CachedMetadata<Vec<crate::metadata::KafkaTopicInfo>>, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    CacheManager::get_kafka_topics_cached(&db, &data_source, force_refresh)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_databases(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    force_refresh: bool,
+) -> Result<CachedMetadata<Vec<String>>, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    CacheManager::list_databases_cached(&db, &data_source, force_refresh)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_schemas(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    database: Option<String>,
+    force_refresh: bool,
+) -> Result<CachedMetadata<Vec<String>>, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    CacheManager::list_schemas_cached(&db, &data_source, database.as_deref(), force_refresh)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_postgres_objects(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    schema: Option<String>,
+    force_refresh: bool,
+) -> Result<CachedMetadata<crate::metadata::PostgresObjects>, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    CacheManager::get_postgres_objects_cached(&db, &data_source, schema.as_deref(), force_refresh)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+const DEFAULT_REDIS_VALUE_LIMIT: usize = 1000;
+
+#[tauri::command]
+pub async fn scan_redis_keys(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    pattern: Option<String>,
+    cursor: Option<u64>,
+) -> Result<crate::metadata::RedisKeyPage, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    MetadataFetcher::scan_redis_keys(&data_source, pattern.as_deref(), cursor.unwrap_or(0))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_redis_value(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    key: String,
+    limit: Option<usize>,
+) -> Result<crate::metadata::RedisValue, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    MetadataFetcher::get_redis_value(
+        &data_source,
+        &key,
+        limit.unwrap_or(DEFAULT_REDIS_VALUE_LIMIT),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_redis_info(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    force_refresh: bool,
+) -> Result<CachedMetadata<Vec<crate::metadata::RedisInfoSection>>, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    CacheManager::get_redis_info_cached(&db, &data_source, force_refresh)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_schema_registry_schemas(
+    db: State<'_, DbPool>,
     data_source_id: i64,
     force_refresh: bool,
-) -> Result<Vec<crate::metadata::SchemaInfo>, String> {
-    let data_source = {
-        let db = get_db().map_err(|e| e.to_string())?;
-        if let Some(ref db) = *db {
-            db.get_data_source(data_source_id).map_err(|e| e.to_string())?
-        } else {
-            return Err("Database not initialized".to_string());
-        }
-    };
-    CacheManager::get_schema_registry_schemas_cached(&data_source, force_refresh)
+) -> Result<CachedMetadata<Vec<crate::metadata::SchemaInfo>>, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    CacheManager::get_schema_registry_schemas_cached(&db, &data_source, force_refresh)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_schema_versions(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    subject: String,
+    force_refresh: bool,
+) -> Result<CachedMetadata<Vec<crate::metadata::SchemaInfo>>, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    CacheManager::get_schema_versions_cached(&db, &data_source, &subject, force_refresh)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn diff_schema_versions(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    subject: String,
+    version1: i32,
+    version2: i32,
+) -> Result<crate::metadata::SchemaDiff, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    MetadataFetcher::diff_schema_versions(&data_source, &subject, version1, version2)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_schema_compatibility(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    subject: Option<String>,
+) -> Result<crate::metadata::CompatibilityLevel, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    MetadataFetcher::get_schema_compatibility(&data_source, subject.as_deref())
         .await
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn test_schema_compatibility(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    subject: String,
+    schema: String,
+    schema_type: Option<String>,
+    version: Option<i32>,
+) -> Result<crate::metadata::CompatibilityCheckResult, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    MetadataFetcher::test_schema_compatibility(
+        &data_source,
+        &subject,
+        &schema,
+        schema_type.as_deref(),
+        version,
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn refresh_metadata(
+    db: State<'_, DbPool>,
     data_source_id: i64,
     cache_type: Option<String>,
 ) -> Result<(), String> {
-    CacheManager::clear_cache(data_source_id, cache_type.as_deref())
+    CacheManager::clear_cache(&db, data_source_id, cache_type.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Change detection commands
+#[tauri::command]
+pub async fn detect_metadata_changes(
+    db: State<'_, DbPool>,
+    app: tauri::AppHandle,
+    data_source_id: i64,
+) -> Result<Option<MetadataChangedEvent>, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let event = RefreshScheduler::detect_changes(&db, &data_source)
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(event) = &event {
+        RefreshScheduler::emit(&app, event);
+    }
+    Ok(event)
+}
+
+#[tauri::command]
+pub async fn list_metadata_changes(
+    db: State<'_, DbPool>,
+    data_source_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<crate::db::MetadataChangeRecord>, String> {
+    db.run(move |db| db.list_metadata_changes(data_source_id, limit.unwrap_or(200)))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn clear_metadata_changes(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+) -> Result<(), String> {
+    db.run(move |db| db.clear_metadata_changes(data_source_id))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Snapshot commands
+#[tauri::command]
+pub async fn take_metadata_snapshot(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    label: Option<String>,
+) -> Result<i64, String> {
+    let data_source = db
+        .use_data_source(data_source_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    SnapshotManager::take_snapshot(&db, &data_source, label.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_metadata_snapshots(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    cache_type: Option<String>,
+) -> Result<Vec<crate::db::MetadataSnapshotInfo>, String> {
+    db.run(move |db| db.list_metadata_snapshots(data_source_id, cache_type.as_deref()))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_metadata_snapshot(db: State<'_, DbPool>, id: i64) -> Result<(), String> {
+    db.run(move |db| db.delete_metadata_snapshot(id))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn diff_metadata_snapshots(
+    db: State<'_, DbPool>,
+    from_snapshot_id: i64,
+    to_snapshot_id: i64,
+) -> Result<SnapshotDiff, String> {
+    SnapshotManager::diff(&db, from_snapshot_id, to_snapshot_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn diff_metadata_since(
+    db: State<'_, DbPool>,
+    data_source_id: i64,
+    since: String,
+) -> Result<SnapshotDiff, String> {
+    let since = chrono::DateTime::parse_from_rfc3339(&since)
+        .map_err(|e| format!("Invalid timestamp {}: {}", since, e))?
+        .with_timezone(&chrono::Utc);
+    let data_source = db
+        .run(move |db| db.get_data_source(data_source_id))
+        .await
+        .map_err(|e| e.to_string())?;
+    SnapshotManager::diff_since(&db, &data_source, since)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Offline mode commands
+#[tauri::command]
+pub async fn get_offline_mode() -> Result<bool, String> {
+    Ok(crate::offline::is_enabled())
+}
+
+#[tauri::command]
+pub async fn set_offline_mode(db: State<'_, DbPool>, enabled: bool) -> Result<(), String> {
+    crate::offline::set_enabled(&db, enabled)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Cache policy commands
+#[tauri::command]
+pub async fn set_cache_policy(
+    db: State<'_, DbPool>,
+    data_source_id: Option<i64>,
+    cache_type: String,
+    ttl_seconds: Option<i64>,
+) -> Result<i64, String> {
+    if let Some(ttl) = ttl_seconds {
+        if ttl <= 0 {
+            return Err("ttl_seconds must be positive; omit it to never expire".to_string());
+        }
+    }
+    db.run(move |db| db.set_cache_policy(data_source_id, &cache_type, ttl_seconds))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_cache_policies(
+    db: State<'_, DbPool>,
+    data_source_id: Option<i64>,
+) -> Result<Vec<crate::db::CachePolicy>, String> {
+    db.run(move |db| db.list_cache_policies(data_source_id))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_cache_policy(db: State<'_, DbPool>, id: i64) -> Result<(), String> {
+    db.run(move |db| db.delete_cache_policy(id))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn purge_expired_cache(db: State<'_, DbPool>) -> Result<usize, String> {
+    db.run(CacheManager::purge_expired)
+        .await
         .map_err(|e| e.to_string())
 }
 
 // Comparison command
 #[tauri::command]
 pub async fn compare_tables(
+    db: State<'_, DbPool>,
     source1_id: i64,
     source2_id: i64,
     schema1: Option<String>,
     schema2: Option<String>,
     table_name: String,
 ) -> Result<TableComparison, String> {
-    // Get data sources before await
-    let (source1, source2) = {
-        let db = get_db().map_err(|e| e.to_string())?;
-        if let Some(ref db) = *db {
-            let s1 = db.get_data_source(source1_id).map_err(|e| e.to_string())?;
-            let s2 = db.get_data_source(source2_id).map_err(|e| e.to_string())?;
-            (s1, s2)
-        } else {
-            return Err("Database not initialized".to_string());
-        }
-    };
-    
-    // Now we can await without holding the guard
+    let source1 = db
+        .use_data_source(source1_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let source2 = db
+        .use_data_source(source2_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Offline: compare cached structures; row counts need a live connection
+    if crate::offline::is_enabled() {
+        let table1 = CacheManager::get_table_structure_cached(
+            &db,
+            &source1,
+            schema1.as_deref(),
+            &table_name,
+            false,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        let table2 = CacheManager::get_table_structure_cached(
+            &db,
+            &source2,
+            schema2.as_deref(),
+            &table_name,
+            false,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        return Ok(MetadataFetcher::compare_table_infos(
+            &table_name,
+            table1.data,
+            table2.data,
+        ));
+    }
+
     MetadataFetcher::compare_tables(
         &source1,
         &source2,
@@ -264,4 +669,3 @@ pub async fn compare_tables(
     .await
     .map_err(|e| e.to_string())
 }
-
@@ -0,0 +1,97 @@
+use crate::db::DataSource;
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+// RDS IAM tokens are valid for 15 minutes; Azure AD tokens for longer
+const DEFAULT_TOKEN_LIFETIME_SECS: u64 = 900;
+// A token is replaced this long before it expires so new connections never use a stale one
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Tokens by data source id and command, with when they expire
+type TokenCache = HashMap<(i64, String), (String, Instant)>;
+
+static TOKENS: Lazy<Mutex<TokenCache>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Where the password of a connection comes from: the data source itself, or a
+/// local command printing a short lived token such as
+/// `aws rds generate-db-auth-token ...` or
+/// `az account get-access-token --resource https://database.windows.net/ --query accessToken -o tsv`.
+pub struct CredentialProvider;
+
+impl CredentialProvider {
+    pub async fn password(
+        data_source: &DataSource,
+        password_command: Option<&str>,
+        token_lifetime_secs: Option<u64>,
+    ) -> Result<String> {
+        let Some(command) = password_command.filter(|c| !c.trim().is_empty()) else {
+            return Ok(data_source.password.clone());
+        };
+
+        let key = (data_source.id, command.to_string());
+        if let Some((token, expires_at)) = TOKENS.lock().unwrap().get(&key) {
+            if Instant::now() + REFRESH_MARGIN < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+
+        let lifetime =
+            Duration::from_secs(token_lifetime_secs.unwrap_or(DEFAULT_TOKEN_LIFETIME_SECS));
+        let token = Self::run(command).await?;
+        TOKENS
+            .lock()
+            .unwrap()
+            .insert(key, (token.clone(), Instant::now() + lifetime));
+        Ok(token)
+    }
+
+    /// Forgets cached tokens, e.g. after the server rejected one.
+    pub fn invalidate(data_source_id: i64) {
+        TOKENS
+            .lock()
+            .unwrap()
+            .retain(|(id, _), _| *id != data_source_id);
+    }
+
+    async fn run(command: &str) -> Result<String> {
+        let mut process = if cfg!(windows) {
+            let mut process = Command::new("cmd");
+            process.arg("/C").arg(command);
+            process
+        } else {
+            let mut process = Command::new("sh");
+            process.arg("-c").arg(command);
+            process
+        };
+        process.kill_on_drop(true);
+
+        let output = tokio::time::timeout(COMMAND_TIMEOUT, process.output())
+            .await
+            .context(format!(
+                "Password command timed out after {} seconds",
+                COMMAND_TIMEOUT.as_secs()
+            ))?
+            .context("Failed to run the password command")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Password command failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let token = String::from_utf8(output.stdout)
+            .context("Password command printed invalid UTF-8")?
+            .trim()
+            .to_string();
+        if token.is_empty() {
+            anyhow::bail!("Password command printed nothing");
+        }
+        Ok(token)
+    }
+}
@@ -0,0 +1,654 @@
+use crate::connection::{DataSourceKind, ProxyType, TlsInfo};
+use crate::connector::Connector;
+use crate::db::DataSource;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// One step of reaching a server, e.g. resolving its name or logging in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticStage {
+    pub name: String, // dns, tcp, proxy, ssh, tls, authentication, server
+    pub status: StageStatus,
+    pub duration_ms: Option<u64>,
+    pub detail: Option<String>,
+    pub hint: Option<String>, // Likely cause of a failure
+}
+
+impl DiagnosticStage {
+    fn passed(name: &str, started: Instant, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: StageStatus::Passed,
+            duration_ms: Some(started.elapsed().as_millis() as u64),
+            detail: Some(detail.into()),
+            hint: None,
+        }
+    }
+
+    /// Read over an established connection, so it is not timed on its own.
+    fn reported(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: StageStatus::Passed,
+            duration_ms: None,
+            detail: Some(detail.into()),
+            hint: None,
+        }
+    }
+
+    fn failed(name: &str, started: Instant, error: &anyhow::Error, hint: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: StageStatus::Failed,
+            duration_ms: Some(started.elapsed().as_millis() as u64),
+            detail: Some(format!("{:#}", error)),
+            hint,
+        }
+    }
+
+    /// A setting this version cannot honour; nothing was attempted for it.
+    fn unsupported(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: StageStatus::Failed,
+            duration_ms: None,
+            detail: Some(detail.into()),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn skipped(name: &str, reason: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: StageStatus::Skipped,
+            duration_ms: None,
+            detail: Some(reason.into()),
+            hint: None,
+        }
+    }
+}
+
+/// Who and where the connection ended up as, as the server reports it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerDetails {
+    pub version: Option<String>,
+    pub current_user: Option<String>,
+    pub current_database: Option<String>,
+}
+
+/// The outcome of a connection test, stage by stage, so a failure can be traced
+/// to the name lookup, the network, the proxy, TLS or the login.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionDiagnostics {
+    pub success: bool,
+    pub stages: Vec<DiagnosticStage>,
+    pub tls: Option<TlsInfo>,
+    pub server: Option<ServerDetails>,
+}
+
+impl ConnectionDiagnostics {
+    pub async fn run(connector: &dyn Connector, data_source: &DataSource) -> Self {
+        let mut stages = Vec::new();
+        let file_based = data_source.data_type.is_file_based();
+
+        // Why the stages after the network ones cannot run, once one of those failed
+        let unreachable = if file_based {
+            None
+        } else if data_source.proxy_type == Some(ProxyType::Ssh) {
+            // No tunnel is opened yet, so connectors that accept the setting dial the
+            // server directly; check what is actually reached
+            stages.push(DiagnosticStage::unsupported(
+                "ssh",
+                format!(
+                    "SSH tunnels are not supported yet; the connection goes directly to {}:{}",
+                    data_source.host.trim(),
+                    data_source.port
+                ),
+                "Forward a local port with ssh -L and point the data source at it, \
+                 or use a SOCKS5 proxy",
+            ));
+            if Self::check_target(data_source, &mut stages).await {
+                None
+            } else {
+                Some("The server was not reached".to_string())
+            }
+        } else if let Some(proxy_type) = data_source.proxy_type {
+            if Self::check_hop(data_source, proxy_type, &mut stages).await {
+                let reason = format!(
+                    "Left to the proxy; the login shows whether it reached {}",
+                    data_source.host.trim()
+                );
+                stages.push(DiagnosticStage::skipped("dns", reason.clone()));
+                stages.push(DiagnosticStage::skipped("tcp", reason));
+                None
+            } else {
+                let reason = "The proxy was not reached".to_string();
+                stages.push(DiagnosticStage::skipped("dns", reason.clone()));
+                stages.push(DiagnosticStage::skipped("tcp", reason.clone()));
+                Some(reason)
+            }
+        } else if data_source.data_type == DataSourceKind::Mongodb
+            && data_source.mongodb_options().srv.unwrap_or(false)
+        {
+            let reason = "The driver looks up the SRV records";
+            stages.push(DiagnosticStage::skipped("dns", reason));
+            stages.push(DiagnosticStage::skipped("tcp", reason));
+            None
+        } else if Self::check_target(data_source, &mut stages).await {
+            None
+        } else {
+            Some("The server was not reached".to_string())
+        };
+
+        let login = if file_based { "open" } else { "authentication" };
+        if let Some(reason) = unreachable {
+            for name in ["tls", login, "server"] {
+                stages.push(DiagnosticStage::skipped(name, reason.clone()));
+            }
+            return Self::finish(stages, None, None);
+        }
+
+        let started = Instant::now();
+        let session = match connector.inspect_connection(data_source).await {
+            Ok(session) => session,
+            Err(e) => {
+                if !file_based && is_tls_error(&e) {
+                    stages.push(DiagnosticStage::failed(
+                        "tls",
+                        started,
+                        &e,
+                        Some(tls_hint(data_source.data_type)),
+                    ));
+                    stages.push(DiagnosticStage::skipped(login, "The TLS handshake failed"));
+                    stages.push(DiagnosticStage::skipped(
+                        "server",
+                        "The TLS handshake failed",
+                    ));
+                } else {
+                    if !file_based {
+                        stages.push(DiagnosticStage::skipped(
+                            "tls",
+                            "Unknown; the connection failed before it could be checked",
+                        ));
+                    }
+                    let message = format!("{:#}", e).to_lowercase();
+                    stages.push(DiagnosticStage::failed(
+                        login,
+                        started,
+                        &e,
+                        login_hint(data_source.data_type, &message),
+                    ));
+                    let reason = if file_based {
+                        "The file could not be opened"
+                    } else {
+                        "The login failed"
+                    };
+                    stages.push(DiagnosticStage::skipped("server", reason));
+                }
+                return Self::finish(stages, None, None);
+            }
+        };
+        let login_stage = DiagnosticStage::passed(login, started, "Connected");
+
+        let tls = if file_based {
+            None
+        } else {
+            Self::check_tls(session.tls, data_source, &mut stages)
+        };
+        stages.push(login_stage);
+
+        let server = match session.server {
+            Ok(Some(server)) => {
+                let mut detail = server.version.clone().unwrap_or_default();
+                if let Some(user) = &server.current_user {
+                    detail.push_str(&format!(" as {}", user));
+                }
+                if let Some(database) = &server.current_database {
+                    detail.push_str(&format!(" in {}", database));
+                }
+                stages.push(DiagnosticStage::reported("server", detail));
+                Some(server)
+            }
+            Ok(None) => {
+                stages.push(DiagnosticStage::skipped(
+                    "server",
+                    format!("Not reported for {} data sources", data_source.data_type),
+                ));
+                None
+            }
+            // The connection works; only the details are out of reach
+            Err(e) => {
+                stages.push(DiagnosticStage::skipped(
+                    "server",
+                    format!("Could not be read: {:#}", e),
+                ));
+                None
+            }
+        };
+
+        Self::finish(stages, tls, server)
+    }
+
+    /// Encryption is only known once connected, so this stage is checked after the login.
+    fn check_tls(
+        tls: Result<Option<TlsInfo>>,
+        data_source: &DataSource,
+        stages: &mut Vec<DiagnosticStage>,
+    ) -> Option<TlsInfo> {
+        match tls {
+            Ok(Some(tls)) if tls.encrypted => {
+                let mut detail = tls
+                    .version
+                    .clone()
+                    .unwrap_or_else(|| "Encrypted".to_string());
+                if let Some(cipher) = &tls.cipher {
+                    detail.push_str(&format!(", {}", cipher));
+                }
+                if let Some(subject) = &tls.certificate_subject {
+                    detail.push_str(&format!("; certificate {}", subject));
+                }
+                if let Some(error) = &tls.certificate_error {
+                    detail.push_str(&format!("; the certificate could not be read: {}", error));
+                }
+                stages.push(DiagnosticStage::reported("tls", detail));
+                Some(tls)
+            }
+            Ok(Some(tls)) => {
+                stages.push(DiagnosticStage::skipped(
+                    "tls",
+                    "The connection is not encrypted",
+                ));
+                Some(tls)
+            }
+            Ok(None) => {
+                stages.push(DiagnosticStage::skipped(
+                    "tls",
+                    format!("Not reported for {} data sources", data_source.data_type),
+                ));
+                None
+            }
+            // The connection works; only the details are out of reach
+            Err(e) => {
+                stages.push(DiagnosticStage::skipped(
+                    "tls",
+                    format!("Could not be read: {:#}", e),
+                ));
+                None
+            }
+        }
+    }
+
+    fn finish(
+        stages: Vec<DiagnosticStage>,
+        tls: Option<TlsInfo>,
+        server: Option<ServerDetails>,
+    ) -> Self {
+        Self {
+            success: stages.iter().all(|s| s.status != StageStatus::Failed),
+            stages,
+            tls,
+            server,
+        }
+    }
+
+    /// Resolves and connects to the data source's own host and port.
+    async fn check_target(data_source: &DataSource, stages: &mut Vec<DiagnosticStage>) -> bool {
+        let host = data_source.host.trim();
+        let started = Instant::now();
+        let addresses = match resolve(host, data_source.port).await {
+            Ok(addresses) => {
+                stages.push(DiagnosticStage::passed(
+                    "dns",
+                    started,
+                    format!("{} resolved to {}", host, join_ips(&addresses)),
+                ));
+                addresses
+            }
+            Err(e) => {
+                stages.push(DiagnosticStage::failed(
+                    "dns",
+                    started,
+                    &e,
+                    Some(format!(
+                        "Check the host name, and any VPN or DNS settings {} depends on",
+                        host
+                    )),
+                ));
+                stages.push(DiagnosticStage::skipped(
+                    "tcp",
+                    "The host name did not resolve",
+                ));
+                return false;
+            }
+        };
+
+        let started = Instant::now();
+        match connect(&addresses).await {
+            Ok((_, address)) => {
+                stages.push(DiagnosticStage::passed(
+                    "tcp",
+                    started,
+                    format!("Connected to {}", address),
+                ));
+                true
+            }
+            Err(e) => {
+                let hint = tcp_hint(&e, data_source.port, data_source.data_type);
+                stages.push(DiagnosticStage::failed("tcp", started, &e, hint));
+                false
+            }
+        }
+    }
+
+    /// Checks the proxy the connection goes through: that it resolves, accepts
+    /// connections and speaks the expected protocol.
+    async fn check_hop(
+        data_source: &DataSource,
+        proxy_type: ProxyType,
+        stages: &mut Vec<DiagnosticStage>,
+    ) -> bool {
+        let started = Instant::now();
+        match Self::reach_hop(data_source, proxy_type).await {
+            Ok(detail) => {
+                stages.push(DiagnosticStage::passed("proxy", started, detail));
+                true
+            }
+            Err(e) => {
+                let hint = format!(
+                    "Check the {} settings and that this machine may connect to it",
+                    proxy_type
+                );
+                stages.push(DiagnosticStage::failed("proxy", started, &e, Some(hint)));
+                false
+            }
+        }
+    }
+
+    async fn reach_hop(data_source: &DataSource, proxy_type: ProxyType) -> Result<String> {
+        let config = data_source
+            .proxy_config
+            .as_ref()
+            .context("proxy_config is missing")?;
+
+        let addresses = resolve(&config.host, config.port).await?;
+        let (mut stream, address) = connect(&addresses).await?;
+        match proxy_type {
+            ProxyType::Ssh => anyhow::bail!("SSH tunnels are not supported yet"),
+            // A greeting offering no authentication and username/password
+            ProxyType::Socks5 => {
+                stream.write_all(&[0x05, 0x02, 0x00, 0x02]).await?;
+                let mut reply = [0u8; 2];
+                tokio::time::timeout(NETWORK_TIMEOUT, stream.read_exact(&mut reply))
+                    .await
+                    .context("The SOCKS5 proxy did not answer")??;
+                if reply[0] != 0x05 {
+                    anyhow::bail!("{} does not look like a SOCKS5 proxy", address);
+                }
+                if reply[1] == 0xff {
+                    anyhow::bail!(
+                        "The SOCKS5 proxy accepts none of the offered authentication methods"
+                    );
+                }
+                Ok(format!("SOCKS5 proxy at {}", address))
+            }
+            ProxyType::Http => Ok(format!("HTTP proxy at {} accepted the connection", address)),
+        }
+    }
+}
+
+async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>> {
+    let addresses: Vec<SocketAddr> =
+        tokio::time::timeout(NETWORK_TIMEOUT, tokio::net::lookup_host((host, port)))
+            .await
+            .context(format!("Resolving {} timed out", host))?
+            .context(format!("{} does not resolve", host))?
+            .collect();
+    if addresses.is_empty() {
+        anyhow::bail!("{} resolved to no addresses", host);
+    }
+    Ok(addresses)
+}
+
+/// Connects to the first address that accepts, as drivers do.
+async fn connect(addresses: &[SocketAddr]) -> Result<(TcpStream, SocketAddr)> {
+    let mut last_error = None;
+    for address in addresses {
+        match tokio::time::timeout(NETWORK_TIMEOUT, TcpStream::connect(address)).await {
+            Ok(Ok(stream)) => return Ok((stream, *address)),
+            Ok(Err(e)) => last_error = Some(anyhow::Error::from(e)),
+            Err(_) => {
+                last_error = Some(anyhow::Error::from(std::io::Error::new(
+                    ErrorKind::TimedOut,
+                    format!("Connecting to {} timed out", address),
+                )))
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No address to connect to")))
+}
+
+fn join_ips(addresses: &[SocketAddr]) -> String {
+    addresses
+        .iter()
+        .map(|a| a.ip().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn tcp_hint(error: &anyhow::Error, port: u16, kind: DataSourceKind) -> Option<String> {
+    let wrong_port = kind
+        .default_port()
+        .filter(|default| *default != port)
+        .map(|default| format!(" {} usually listens on {}.", kind, default))
+        .unwrap_or_default();
+    match error.downcast_ref::<std::io::Error>()?.kind() {
+        ErrorKind::ConnectionRefused => Some(format!(
+            "Nothing accepts connections on port {}; check the port and that the server is running.{}",
+            port, wrong_port
+        )),
+        ErrorKind::TimedOut => Some(format!(
+            "No answer on port {}; a firewall or security group is probably dropping the traffic.{}",
+            port, wrong_port
+        )),
+        ErrorKind::ConnectionReset => Some(
+            "The connection was reset; a firewall or the server's host based access rules may reject this machine"
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
+
+/// Whether connecting failed in the TLS handshake: by the driver's TLS error where it
+/// has one, otherwise by phrases only TLS failures use. A bare "ssl" is not enough;
+/// PostgreSQL names it in login errors such as "no pg_hba.conf entry ... SSL off".
+fn is_tls_error(error: &anyhow::Error) -> bool {
+    let typed = error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<sqlx::Error>(),
+            Some(sqlx::Error::Tls(_))
+        ) || matches!(
+            cause.downcast_ref::<tiberius::error::Error>(),
+            Some(tiberius::error::Error::Tls(_))
+        ) || cause.is::<native_tls::Error>()
+    });
+    let message = format!("{:#}", error).to_lowercase();
+    typed
+        || [
+            "tls handshake",
+            "ssl handshake",
+            "handshake failure",
+            "certificate verify failed",
+            "invalid certificate",
+            "invalid peer certificate",
+            "self signed certificate",
+            "self-signed certificate",
+            "unable to get local issuer certificate",
+            "certificate has expired",
+            "unknown ca",
+            "wrong version number",
+            "server does not support ssl",
+            "server does not support tls",
+            "ssl connection is required",
+        ]
+        .iter()
+        .any(|phrase| message.contains(phrase))
+}
+
+fn tls_hint(kind: DataSourceKind) -> String {
+    format!(
+        "Check the TLS mode and CA certificate; the {} server may not support TLS, or its certificate does not match the CA or host name",
+        kind
+    )
+}
+
+/// Common login failures, recognised from the driver's error message.
+fn login_hint(kind: DataSourceKind, message: &str) -> Option<String> {
+    if message.contains("plugin") || message.contains("caching_sha2") {
+        return Some(
+            "The server wants a different authentication plugin; try auth_method caching_sha2, or cleartext for token logins"
+                .to_string(),
+        );
+    }
+    if message.contains("password command") {
+        return Some("Run the password command in a terminal to see why it fails".to_string());
+    }
+    if message.contains("access denied")
+        || message.contains("password authentication failed")
+        || message.contains("login failed")
+        || message.contains("authentication failed")
+        || message.contains("noauth")
+        || message.contains("wrongpass")
+    {
+        return Some(format!(
+            "The {} server rejected the user name or password, or this user may not connect from this machine",
+            kind
+        ));
+    }
+    if message.contains("unknown database")
+        || message.contains("does not exist")
+        || message.contains("cannot open database")
+    {
+        return Some("The database does not exist or the user may not access it".to_string());
+    }
+    if message.contains("pg_hba.conf") {
+        return Some(
+            "pg_hba.conf has no entry allowing this user, database and address; ask the administrator to add one"
+                .to_string(),
+        );
+    }
+    if message.contains("timed out") {
+        return Some(
+            "The server accepted the connection but did not complete the login in time".to_string(),
+        );
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tls_errors_are_recognised_by_type() {
+        let error = anyhow::Error::from(sqlx::Error::Tls("bad record mac".into()))
+            .context("Failed to create PostgreSQL connection pool");
+        assert!(is_tls_error(&error));
+
+        let error = anyhow::Error::from(tiberius::error::Error::Tls("alert".to_string()));
+        assert!(is_tls_error(&error));
+    }
+
+    #[test]
+    fn tls_errors_are_recognised_by_message() {
+        for message in [
+            "error:1416F086:SSL routines:tls_process_server_certificate:certificate verify failed",
+            "received fatal alert: HandshakeFailure; TLS handshake failed",
+            "invalid peer certificate: UnknownIssuer",
+            "error returned from database: server does not support SSL",
+        ] {
+            assert!(is_tls_error(&anyhow::anyhow!(message)), "{}", message);
+        }
+    }
+
+    #[test]
+    fn login_errors_mentioning_ssl_are_not_tls_errors() {
+        for message in [
+            "no pg_hba.conf entry for host \"10.0.0.5\", user \"app\", database \"app\", SSL off",
+            "Access denied for user 'app'@'10.0.0.5' (using password: YES)",
+            "SSH handshake failed",
+        ] {
+            assert!(!is_tls_error(&anyhow::anyhow!(message)), "{}", message);
+        }
+    }
+
+    #[test]
+    fn tls_hint_names_the_engine() {
+        assert!(tls_hint(DataSourceKind::Postgresql).contains(DataSourceKind::Postgresql.as_str()));
+    }
+
+    #[test]
+    fn login_hints() {
+        let hint = |message: &str| login_hint(DataSourceKind::Mysql, &message.to_lowercase());
+        assert!(hint("Access denied for user 'app'@'%'")
+            .unwrap()
+            .contains("rejected the user name or password"));
+        assert!(
+            hint("authentication plugin 'caching_sha2_password' is not supported")
+                .unwrap()
+                .contains("authentication plugin")
+        );
+        assert!(hint("Password command failed (exit status: 1): expired")
+            .unwrap()
+            .contains("password command"));
+        assert!(hint("Unknown database 'shop'")
+            .unwrap()
+            .contains("does not exist"));
+        assert!(hint("no pg_hba.conf entry for host")
+            .unwrap()
+            .contains("pg_hba.conf"));
+        assert_eq!(hint("protocol error"), None);
+    }
+
+    #[test]
+    fn tcp_hints_follow_the_io_error() {
+        let io = |kind: ErrorKind| anyhow::Error::from(std::io::Error::from(kind));
+
+        let refused = tcp_hint(
+            &io(ErrorKind::ConnectionRefused),
+            3307,
+            DataSourceKind::Mysql,
+        );
+        let refused = refused.unwrap();
+        assert!(refused.contains("port 3307"));
+        assert!(refused.contains("usually listens on 3306"));
+
+        let timed_out = tcp_hint(&io(ErrorKind::TimedOut), 3306, DataSourceKind::Mysql).unwrap();
+        assert!(timed_out.contains("firewall"));
+        assert!(!timed_out.contains("usually listens"));
+
+        assert!(tcp_hint(&io(ErrorKind::ConnectionReset), 3306, DataSourceKind::Mysql).is_some());
+        assert_eq!(
+            tcp_hint(&io(ErrorKind::Other), 3306, DataSourceKind::Mysql),
+            None
+        );
+        assert_eq!(
+            tcp_hint(&anyhow::anyhow!("no route"), 3306, DataSourceKind::Mysql),
+            None
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::connection::ConnectionTester;
+use crate::connection::options::{KafkaSaslMechanism, KafkaSecurityProtocol};
 use crate::db::DataSource;
 use anyhow::{Context, Result};
 use rdkafka::config::ClientConfig;
@@ -8,15 +8,6 @@ use std::time::Duration;
 
 pub struct KafkaConnector;
 
-impl ConnectionTester for KafkaConnector {
-    async fn test_connection(data_source: &DataSource) -> Result<()> {
-        Self::create_admin_client(data_source)
-            .await
-            .context("Failed to create Kafka admin client")?;
-        Ok(())
-    }
-}
-
 impl KafkaConnector {
     pub async fn create_admin_client(data_source: &DataSource) -> Result<rdkafka::admin::AdminClient<rdkafka::client::DefaultClientContext>> {
         let mut config = ClientConfig::new();
@@ -24,13 +15,7 @@ impl KafkaConnector {
         config.set("client.id", "data-explorer");
         config.set("request.timeout.ms", "5000");
         
-        // Handle authentication if needed
-        if !data_source.username.is_empty() {
-            config.set("security.protocol", "SASL_PLAINTEXT");
-            config.set("sasl.mechanism", "PLAIN");
-            config.set("sasl.username", &data_source.username);
-            config.set("sasl.password", &data_source.password);
-        }
+        Self::apply_security(&mut config, data_source);
         
         let admin_client: rdkafka::admin::AdminClient<rdkafka::client::DefaultClientContext> = config
             .create()
@@ -53,12 +38,7 @@ impl KafkaConnector {
         config.set("session.timeout.ms", "6000");
         config.set("enable.auto.commit", "true");
         
-        if !data_source.username.is_empty() {
-            config.set("security.protocol", "SASL_PLAINTEXT");
-            config.set("sasl.mechanism", "PLAIN");
-            config.set("sasl.username", &data_source.username);
-            config.set("sasl.password", &data_source.password);
-        }
+        Self::apply_security(&mut config, data_source);
         
         let consumer: StreamConsumer = config
             .create()
@@ -72,12 +52,7 @@ impl KafkaConnector {
         config.set("bootstrap.servers", format!("{}:{}", data_source.host, data_source.port));
         config.set("client.id", "data-explorer-producer");
         
-        if !data_source.username.is_empty() {
-            config.set("security.protocol", "SASL_PLAINTEXT");
-            config.set("sasl.mechanism", "PLAIN");
-            config.set("sasl.username", &data_source.username);
-            config.set("sasl.password", &data_source.password);
-        }
+        Self::apply_security(&mut config, data_source);
         
         let producer: FutureProducer = config
             .create()
@@ -85,5 +60,26 @@ impl KafkaConnector {
         
         Ok(producer)
     }
-}
 
+    /// Applies the data source's Kafka options. Without explicit options a username
+    /// still means SASL/PLAIN over plaintext.
+    fn apply_security(config: &mut ClientConfig, data_source: &DataSource) {
+        let options = data_source.kafka_options();
+        if let Some(client_id) = &options.client_id {
+            config.set("client.id", client_id);
+        }
+
+        let protocol = options.security_protocol.or_else(|| {
+            (!data_source.username.is_empty()).then_some(KafkaSecurityProtocol::SaslPlaintext)
+        });
+        if let Some(protocol) = protocol {
+            config.set("security.protocol", protocol.as_str());
+            if protocol.uses_sasl() {
+                let mechanism = options.sasl_mechanism.unwrap_or(KafkaSaslMechanism::Plain);
+                config.set("sasl.mechanism", mechanism.as_str());
+                config.set("sasl.username", &data_source.username);
+                config.set("sasl.password", &data_source.password);
+            }
+        }
+    }
+}
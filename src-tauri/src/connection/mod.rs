@@ -1,15 +1,26 @@
+use crate::connector::{connector_for, Capability, QueryResult};
 use crate::db::DataSource;
 use anyhow::Result;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+pub mod auth;
+pub mod diagnostics;
+pub mod options;
 pub mod proxy;
 pub mod mysql;
 pub mod postgresql;
 pub mod sqlserver;
+pub mod tls;
+pub mod schema_registry;
 #[cfg(feature = "kafka")]
 pub mod kafka;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub use options::{DataSourceKind, DataSourceOptions, ProxyType, TlsMode};
+pub use diagnostics::{ConnectionDiagnostics, ServerDetails};
+pub use tls::TlsInfo;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ProxyConfig {
     pub proxy_type: String, // socks5, http
     pub host: String,
@@ -18,7 +29,7 @@ pub struct ProxyConfig {
     pub password: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct SshConfig {
     pub host: String,
     pub port: u16,
@@ -28,24 +39,40 @@ pub struct SshConfig {
     pub local_port: u16, // Local port for SSH tunnel
 }
 
-pub trait ConnectionTester {
-    async fn test_connection(data_source: &DataSource) -> Result<()>;
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SchemaRegistryConfig {
+    pub auth_type: Option<String>, // basic, bearer
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+    pub ca_cert_path: Option<String>,
+    pub client_cert_path: Option<String>,
+    pub client_key_path: Option<String>,
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+    pub max_concurrency: Option<usize>, // Parallel requests when fetching subjects
 }
 
 pub struct ConnectionManager;
 
 impl ConnectionManager {
-    pub async fn test_connection(data_source: &DataSource) -> Result<()> {
-        match data_source.data_type.as_str() {
-            "mysql" => mysql::MySQLConnector::test_connection(data_source).await,
-            "postgresql" => postgresql::PostgreSQLConnector::test_connection(data_source).await,
-            "sqlserver" => sqlserver::SQLServerConnector::test_connection(data_source).await,
-            #[cfg(feature = "kafka")]
-            "kafka" => kafka::KafkaConnector::test_connection(data_source).await,
-            #[cfg(not(feature = "kafka"))]
-            "kafka" => Err(anyhow::anyhow!("Kafka support is not enabled. Build with --features kafka")),
-            _ => Err(anyhow::anyhow!("Unsupported data source type: {}", data_source.data_type)),
-        }
+    /// A failed test is still a report; errors are for data sources that cannot be
+    /// tested at all, e.g. while offline.
+    pub async fn test_connection(data_source: &DataSource) -> Result<ConnectionDiagnostics> {
+        crate::offline::ensure_online()?;
+        let connector = connector_for(data_source, Capability::TestConnection)?;
+        Ok(ConnectionDiagnostics::run(connector.as_ref(), data_source).await)
+    }
+
+    pub async fn execute_query(
+        data_source: &DataSource,
+        sql: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult> {
+        crate::offline::ensure_online()?;
+        connector_for(data_source, Capability::Query)?
+            .execute_query(data_source, sql, max_rows)
+            .await
     }
 }
 
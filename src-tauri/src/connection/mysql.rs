@@ -1,61 +1,96 @@
-use crate::connection::{ConnectionTester, ProxyConfig};
+use crate::connection::auth::CredentialProvider;
+use crate::connection::options::MySqlAuthMethod;
+use crate::connection::{ProxyType, TlsMode};
 use crate::db::DataSource;
 use anyhow::{Context, Result};
-use sqlx::mysql::{MySqlPool, MySqlPoolOptions};
+use sqlx::mysql::{MySqlConnectOptions, MySqlPool, MySqlPoolOptions};
 use sqlx::Pool;
+use std::str::FromStr;
 use std::time::Duration;
 
 pub struct MySQLConnector;
 
-impl ConnectionTester for MySQLConnector {
-    async fn test_connection(data_source: &DataSource) -> Result<()> {
-        Self::create_pool(data_source)
-            .await
-            .context("Failed to create MySQL connection pool")?;
-        Ok(())
-    }
-}
-
 impl MySQLConnector {
     pub async fn create_pool(data_source: &DataSource) -> Result<Pool<sqlx::MySql>> {
         let url = Self::build_connection_url(data_source)?;
+        let options = data_source.mysql_options();
+        let timeout = options.connect_timeout_secs.unwrap_or(10);
+
+        let mut connect_options =
+            MySqlConnectOptions::from_str(&url).context("Invalid MySQL connection settings")?;
+        connect_options = connect_options.username(&data_source.username);
+        // The server picks mysql_native_password or caching_sha2_password and sqlx
+        // follows; only the cleartext plugin has to be allowed explicitly.
+        if options.auth_method == Some(MySqlAuthMethod::Cleartext) {
+            connect_options = connect_options.enable_cleartext_plugin(true);
+        }
+        let password = CredentialProvider::password(
+            data_source,
+            options.password_command.as_deref(),
+            options.token_lifetime_secs,
+        )
+        .await?;
+        if !password.is_empty() {
+            connect_options = connect_options.password(&password);
+        }
+        if let Some(path) = &options.ca_cert_path {
+            connect_options = connect_options.ssl_ca(path);
+        }
+        if let Some(path) = &options.client_cert_path {
+            connect_options = connect_options.ssl_client_cert(path);
+        }
+        if let Some(path) = &options.client_key_path {
+            connect_options = connect_options.ssl_client_key(path);
+        }
         
         let pool = MySqlPoolOptions::new()
             .max_connections(5)
-            .acquire_timeout(Duration::from_secs(10))
-            .connect(&url)
+            .acquire_timeout(Duration::from_secs(timeout))
+            .connect_with(connect_options)
             .await
+            .inspect_err(|_| CredentialProvider::invalidate(data_source.id))
             .context("Failed to connect to MySQL")?;
         
         Ok(pool)
     }
 
     fn build_connection_url(data_source: &DataSource) -> Result<String> {
-        let mut url = format!(
-            "mysql://{}:{}@{}:{}/",
-            data_source.username,
-            data_source.password,
-            data_source.host,
-            data_source.port
-        );
+        // Credentials are set on the connect options, where they need no URL escaping
+        let mut url = format!("mysql://{}:{}/", data_source.host, data_source.port);
         
         if let Some(database) = &data_source.database {
             url.push_str(database);
         }
+
+        let options = data_source.mysql_options();
+        let mut params = Vec::new();
+        if let Some(tls_mode) = options.tls_mode {
+            let ssl_mode = match tls_mode {
+                TlsMode::Disable => "DISABLED",
+                TlsMode::Prefer => "PREFERRED",
+                TlsMode::Require => "REQUIRED",
+                TlsMode::VerifyCa => "VERIFY_CA",
+                TlsMode::VerifyFull => "VERIFY_IDENTITY",
+            };
+            params.push(format!("ssl-mode={}", ssl_mode));
+        }
+        if let Some(charset) = &options.charset {
+            params.push(format!("charset={}", charset));
+        }
+        if !params.is_empty() {
+            url.push('?');
+            url.push_str(&params.join("&"));
+        }
         
         // Handle proxy configuration
         if let Some(proxy_type) = &data_source.proxy_type {
-            match proxy_type.as_str() {
-                "http" => {
-                    if let Some(proxy_config) = &data_source.proxy_config {
-                        let _proxy: ProxyConfig = serde_json::from_value(proxy_config.clone())
-                            .context("Invalid proxy config")?;
-                        // Note: sqlx doesn't directly support HTTP proxy for MySQL
-                        // You might need to use a different approach or library
-                        return Err(anyhow::anyhow!("HTTP proxy for MySQL is not directly supported by sqlx"));
-                    }
+            match proxy_type {
+                ProxyType::Http if data_source.proxy_config.is_some() => {
+                    // Note: sqlx doesn't directly support HTTP proxy for MySQL
+                    // You might need to use a different approach or library
+                    return Err(anyhow::anyhow!("HTTP proxy for MySQL is not directly supported by sqlx"));
                 }
-                "socks5" => {
+                ProxyType::Socks5 => {
                     // SOCKS5 proxy would need custom connection handling
                     return Err(anyhow::anyhow!("SOCKS5 proxy for MySQL requires custom implementation"));
                 }
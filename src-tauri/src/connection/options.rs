@@ -0,0 +1,700 @@
+use crate::connection::{ProxyConfig, SchemaRegistryConfig, SshConfig};
+use crate::db::DataSource;
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DataSourceKind {
+    Mysql,
+    Postgresql,
+    Sqlserver,
+    Kafka,
+    Sqlite,
+    Duckdb,
+    Clickhouse,
+    Mongodb,
+    Redis,
+    Elasticsearch,
+}
+
+impl DataSourceKind {
+    pub const ALL: [DataSourceKind; 10] = [
+        DataSourceKind::Mysql,
+        DataSourceKind::Postgresql,
+        DataSourceKind::Sqlserver,
+        DataSourceKind::Kafka,
+        DataSourceKind::Sqlite,
+        DataSourceKind::Duckdb,
+        DataSourceKind::Clickhouse,
+        DataSourceKind::Mongodb,
+        DataSourceKind::Redis,
+        DataSourceKind::Elasticsearch,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataSourceKind::Mysql => "mysql",
+            DataSourceKind::Postgresql => "postgresql",
+            DataSourceKind::Sqlserver => "sqlserver",
+            DataSourceKind::Kafka => "kafka",
+            DataSourceKind::Sqlite => "sqlite",
+            DataSourceKind::Duckdb => "duckdb",
+            DataSourceKind::Clickhouse => "clickhouse",
+            DataSourceKind::Mongodb => "mongodb",
+            DataSourceKind::Redis => "redis",
+            DataSourceKind::Elasticsearch => "elasticsearch",
+        }
+    }
+
+    /// None for file databases, which have no server to connect to.
+    pub fn default_port(&self) -> Option<u16> {
+        match self {
+            DataSourceKind::Mysql => Some(3306),
+            DataSourceKind::Postgresql => Some(5432),
+            DataSourceKind::Sqlserver => Some(1433),
+            DataSourceKind::Kafka => Some(9092),
+            DataSourceKind::Clickhouse => Some(8123),
+            DataSourceKind::Mongodb => Some(27017),
+            DataSourceKind::Redis => Some(6379),
+            DataSourceKind::Elasticsearch => Some(9200),
+            DataSourceKind::Sqlite | DataSourceKind::Duckdb => None,
+        }
+    }
+
+    /// File databases keep their path in `database` instead of a host and port.
+    pub fn is_file_based(&self) -> bool {
+        self.default_port().is_none()
+    }
+}
+
+impl fmt::Display for DataSourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DataSourceKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| {
+                let supported: Vec<&str> = Self::ALL.iter().map(|k| k.as_str()).collect();
+                anyhow::anyhow!(
+                    "Unsupported data source type '{}', expected one of {}",
+                    s,
+                    supported.join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyType {
+    Socks5,
+    Http,
+    Ssh,
+}
+
+impl ProxyType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProxyType::Socks5 => "socks5",
+            ProxyType::Http => "http",
+            ProxyType::Ssh => "ssh",
+        }
+    }
+
+    /// For `#[serde(deserialize_with)]`: forms send "" when no proxy is picked.
+    pub fn deserialize_optional<'de, D>(
+        deserializer: D,
+    ) -> std::result::Result<Option<Self>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)?.as_deref() {
+            None | Some("") => Ok(None),
+            Some(s) => s.parse().map(Some).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl fmt::Display for ProxyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ProxyType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "socks5" => Ok(ProxyType::Socks5),
+            "http" => Ok(ProxyType::Http),
+            "ssh" => Ok(ProxyType::Ssh),
+            _ => Err(anyhow::anyhow!(
+                "Unsupported proxy type '{}', expected socks5, http or ssh",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum TlsMode {
+    Disable,
+    #[default]
+    Prefer,
+    Require,
+    VerifyCa,   // Verify the certificate chain only
+    VerifyFull, // Also verify the host name
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MySqlAuthMethod {
+    Native,
+    CachingSha2,
+    Cleartext, // mysql_clear_password, as RDS IAM tokens need
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PostgresAuthMethod {
+    Password,
+    ClientCert,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SqlServerAuthMethod {
+    SqlServer,
+    Integrated,
+    AzureAdToken, // The password, or the password command's output, is the access token
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum KafkaSecurityProtocol {
+    Plaintext,
+    Ssl,
+    SaslPlaintext,
+    SaslSsl,
+}
+
+impl KafkaSecurityProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KafkaSecurityProtocol::Plaintext => "PLAINTEXT",
+            KafkaSecurityProtocol::Ssl => "SSL",
+            KafkaSecurityProtocol::SaslPlaintext => "SASL_PLAINTEXT",
+            KafkaSecurityProtocol::SaslSsl => "SASL_SSL",
+        }
+    }
+
+    pub fn uses_sasl(&self) -> bool {
+        matches!(
+            self,
+            KafkaSecurityProtocol::SaslPlaintext | KafkaSecurityProtocol::SaslSsl
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum KafkaSaslMechanism {
+    #[serde(rename = "PLAIN")]
+    Plain,
+    #[serde(rename = "SCRAM-SHA-256")]
+    ScramSha256,
+    #[serde(rename = "SCRAM-SHA-512")]
+    ScramSha512,
+}
+
+impl KafkaSaslMechanism {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KafkaSaslMechanism::Plain => "PLAIN",
+            KafkaSaslMechanism::ScramSha256 => "SCRAM-SHA-256",
+            KafkaSaslMechanism::ScramSha512 => "SCRAM-SHA-512",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MySqlOptions {
+    pub tls_mode: Option<TlsMode>,
+    pub auth_method: Option<MySqlAuthMethod>,
+    pub charset: Option<String>,
+    pub connect_timeout_secs: Option<u64>,
+    pub ca_cert_path: Option<String>,
+    pub client_cert_path: Option<String>,
+    pub client_key_path: Option<String>,
+    pub password_command: Option<String>, // Prints a token used as the password
+    pub token_lifetime_secs: Option<u64>, // How long a printed token stays valid
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PostgresOptions {
+    pub tls_mode: Option<TlsMode>,
+    pub auth_method: Option<PostgresAuthMethod>,
+    pub application_name: Option<String>,
+    pub connect_timeout_secs: Option<u64>,
+    pub schema: Option<String>, // Schema to browse; "public" when unset
+    pub ca_cert_path: Option<String>,
+    pub client_cert_path: Option<String>,
+    pub client_key_path: Option<String>,
+    pub password_command: Option<String>,
+    pub token_lifetime_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SqlServerOptions {
+    pub tls_mode: Option<TlsMode>,
+    pub auth_method: Option<SqlServerAuthMethod>,
+    pub instance_name: Option<String>,
+    pub application_name: Option<String>,
+    pub schema: Option<String>, // Schema to browse; all user schemas when unset
+    pub ca_cert_path: Option<String>, // .pem, .crt or .der; SQL Server has no client certificates
+    pub password_command: Option<String>,
+    pub token_lifetime_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct KafkaOptions {
+    pub security_protocol: Option<KafkaSecurityProtocol>,
+    pub sasl_mechanism: Option<KafkaSaslMechanism>,
+    pub client_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FileDatabaseOptions {
+    pub read_only: Option<bool>, // Defaults to true; analysts rarely mean to write
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ClickHouseOptions {
+    pub secure: Option<bool>, // HTTPS; the usual port is then 8443
+    pub insecure_skip_verify: Option<bool>,
+    pub max_execution_time_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MongoDbOptions {
+    pub srv: Option<bool>, // mongodb+srv:// lookup of the host; the port is then ignored
+    pub auth_source: Option<String>,
+    pub replica_set: Option<String>,
+    pub tls: Option<bool>,
+    pub direct_connection: Option<bool>,
+    pub sample_size: Option<u32>, // documents sampled per collection to infer its fields, 100 by default
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RedisOptions {
+    pub tls: Option<bool>,
+    pub insecure_skip_verify: Option<bool>,
+    pub scan_count: Option<u32>, // COUNT hint of each SCAN call, 100 by default
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ElasticsearchOptions {
+    pub secure: Option<bool>, // HTTPS
+    pub insecure_skip_verify: Option<bool>,
+    pub include_hidden: Option<bool>, // also list hidden and dot-prefixed system indices
+}
+
+/// Settings that only make sense for one kind of data source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DataSourceOptions {
+    Mysql(MySqlOptions),
+    Postgresql(PostgresOptions),
+    Sqlserver(SqlServerOptions),
+    Kafka(KafkaOptions),
+    Sqlite(FileDatabaseOptions),
+    Duckdb(FileDatabaseOptions),
+    Clickhouse(ClickHouseOptions),
+    Mongodb(MongoDbOptions),
+    Redis(RedisOptions),
+    Elasticsearch(ElasticsearchOptions),
+}
+
+// Stored form of the options column; bump the version when a change needs migrating
+#[derive(Serialize, Deserialize)]
+struct StoredOptions {
+    version: u32,
+    options: serde_json::Value,
+}
+
+impl DataSourceOptions {
+    const VERSION: u32 = 1;
+
+    pub fn kind(&self) -> DataSourceKind {
+        match self {
+            DataSourceOptions::Mysql(_) => DataSourceKind::Mysql,
+            DataSourceOptions::Postgresql(_) => DataSourceKind::Postgresql,
+            DataSourceOptions::Sqlserver(_) => DataSourceKind::Sqlserver,
+            DataSourceOptions::Kafka(_) => DataSourceKind::Kafka,
+            DataSourceOptions::Sqlite(_) => DataSourceKind::Sqlite,
+            DataSourceOptions::Duckdb(_) => DataSourceKind::Duckdb,
+            DataSourceOptions::Clickhouse(_) => DataSourceKind::Clickhouse,
+            DataSourceOptions::Mongodb(_) => DataSourceKind::Mongodb,
+            DataSourceOptions::Redis(_) => DataSourceKind::Redis,
+            DataSourceOptions::Elasticsearch(_) => DataSourceKind::Elasticsearch,
+        }
+    }
+
+    /// The local command run to obtain the password, for kinds that support one.
+    pub fn password_command(&self) -> Option<&str> {
+        match self {
+            DataSourceOptions::Mysql(options) => options.password_command.as_deref(),
+            DataSourceOptions::Postgresql(options) => options.password_command.as_deref(),
+            DataSourceOptions::Sqlserver(options) => options.password_command.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn to_stored(&self) -> Result<String> {
+        let stored = StoredOptions {
+            version: Self::VERSION,
+            options: serde_json::to_value(self)?,
+        };
+        Ok(serde_json::to_string(&stored)?)
+    }
+
+    pub fn from_stored(json: &str) -> Result<Self> {
+        let stored: StoredOptions = serde_json::from_str(json).context("Invalid stored options")?;
+        match stored.version {
+            1 => Ok(serde_json::from_value(stored.options)?),
+            version => Err(anyhow::anyhow!(
+                "Options were saved by a newer version (format {}); upgrade to read them",
+                version
+            )),
+        }
+    }
+}
+
+impl DataSource {
+    pub fn mysql_options(&self) -> MySqlOptions {
+        match &self.options {
+            Some(DataSourceOptions::Mysql(options)) => options.clone(),
+            _ => MySqlOptions::default(),
+        }
+    }
+
+    pub fn postgres_options(&self) -> PostgresOptions {
+        match &self.options {
+            Some(DataSourceOptions::Postgresql(options)) => options.clone(),
+            _ => PostgresOptions::default(),
+        }
+    }
+
+    pub fn sqlserver_options(&self) -> SqlServerOptions {
+        match &self.options {
+            Some(DataSourceOptions::Sqlserver(options)) => options.clone(),
+            _ => SqlServerOptions::default(),
+        }
+    }
+
+    pub fn kafka_options(&self) -> KafkaOptions {
+        match &self.options {
+            Some(DataSourceOptions::Kafka(options)) => options.clone(),
+            _ => KafkaOptions::default(),
+        }
+    }
+
+    pub fn file_database_options(&self) -> FileDatabaseOptions {
+        match &self.options {
+            Some(DataSourceOptions::Sqlite(options)) | Some(DataSourceOptions::Duckdb(options)) => {
+                options.clone()
+            }
+            _ => FileDatabaseOptions::default(),
+        }
+    }
+
+    pub fn clickhouse_options(&self) -> ClickHouseOptions {
+        match &self.options {
+            Some(DataSourceOptions::Clickhouse(options)) => options.clone(),
+            _ => ClickHouseOptions::default(),
+        }
+    }
+
+    pub fn mongodb_options(&self) -> MongoDbOptions {
+        match &self.options {
+            Some(DataSourceOptions::Mongodb(options)) => options.clone(),
+            _ => MongoDbOptions::default(),
+        }
+    }
+
+    pub fn redis_options(&self) -> RedisOptions {
+        match &self.options {
+            Some(DataSourceOptions::Redis(options)) => options.clone(),
+            _ => RedisOptions::default(),
+        }
+    }
+
+    pub fn elasticsearch_options(&self) -> ElasticsearchOptions {
+        match &self.options {
+            Some(DataSourceOptions::Elasticsearch(options)) => options.clone(),
+            _ => ElasticsearchOptions::default(),
+        }
+    }
+
+    /// Every problem with the data source's configuration, so a form can show them all.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.name.trim().is_empty() {
+            problems.push("name must not be empty".to_string());
+        }
+        if self.data_type.is_file_based() {
+            if self.database.as_deref().is_none_or(|p| p.trim().is_empty()) {
+                problems.push("database must be the path of the database file".to_string());
+            }
+            if self.proxy_type.is_some() {
+                problems.push(format!(
+                    "{} data sources cannot use a proxy",
+                    self.data_type
+                ));
+            }
+        } else {
+            if self.host.trim().is_empty() {
+                problems.push("host must not be empty".to_string());
+            }
+            if self.port == 0 {
+                problems.push("port must be between 1 and 65535".to_string());
+            }
+        }
+
+        match self.proxy_type {
+            Some(proxy_type @ (ProxyType::Socks5 | ProxyType::Http))
+                if self.proxy_config.is_none() =>
+            {
+                problems.push(format!(
+                    "proxy_config is required for a {} proxy",
+                    proxy_type
+                ));
+            }
+            Some(ProxyType::Ssh) if self.ssh_config.is_none() => {
+                problems.push("ssh_config is required for an ssh proxy".to_string());
+            }
+            _ => {}
+        }
+
+        if self.data_type == DataSourceKind::Redis {
+            if let Some(database) = self.database.as_deref().filter(|d| !d.trim().is_empty()) {
+                if database.trim().parse::<i64>().is_err() {
+                    problems.push("database must be a Redis database number".to_string());
+                }
+            }
+        }
+
+        if let Some(options) = &self.options {
+            if options.kind() != self.data_type {
+                problems.push(format!(
+                    "options are for {} but data_type is {}",
+                    options.kind(),
+                    self.data_type
+                ));
+            }
+        }
+
+        match &self.options {
+            Some(DataSourceOptions::Mysql(options)) => {
+                problems.extend(tls_file_problems(
+                    options.tls_mode,
+                    options.ca_cert_path.as_deref(),
+                    options.client_cert_path.as_deref(),
+                    options.client_key_path.as_deref(),
+                ));
+                problems.extend(password_command_problems(
+                    options.password_command.as_deref(),
+                    options.token_lifetime_secs,
+                ));
+                // The cleartext plugin sends the password as is
+                if options.auth_method == Some(MySqlAuthMethod::Cleartext)
+                    && !matches!(
+                        options.tls_mode,
+                        Some(TlsMode::Require | TlsMode::VerifyCa | TlsMode::VerifyFull)
+                    )
+                {
+                    problems.push(
+                        "auth_method cleartext needs tls_mode require, verify-ca or verify-full"
+                            .to_string(),
+                    );
+                }
+            }
+            Some(DataSourceOptions::Postgresql(options)) => {
+                problems.extend(tls_file_problems(
+                    options.tls_mode,
+                    options.ca_cert_path.as_deref(),
+                    options.client_cert_path.as_deref(),
+                    options.client_key_path.as_deref(),
+                ));
+                problems.extend(password_command_problems(
+                    options.password_command.as_deref(),
+                    options.token_lifetime_secs,
+                ));
+                if options.auth_method == Some(PostgresAuthMethod::ClientCert) {
+                    if options.client_cert_path.is_none() {
+                        problems.push(
+                            "auth_method client_cert needs client_cert_path and client_key_path"
+                                .to_string(),
+                        );
+                    }
+                    if options.password_command.is_some() {
+                        problems.push(
+                            "password_command does not apply to auth_method client_cert"
+                                .to_string(),
+                        );
+                    }
+                }
+            }
+            Some(DataSourceOptions::Sqlserver(options)) => {
+                problems.extend(tls_file_problems(
+                    options.tls_mode,
+                    options.ca_cert_path.as_deref(),
+                    None,
+                    None,
+                ));
+                problems.extend(password_command_problems(
+                    options.password_command.as_deref(),
+                    options.token_lifetime_secs,
+                ));
+                if let Some(path) = &options.ca_cert_path {
+                    let extension = std::path::Path::new(path)
+                        .extension()
+                        .map(|e| e.to_string_lossy().to_lowercase());
+                    if !matches!(extension.as_deref(), Some("pem" | "crt" | "der")) {
+                        problems.push("ca_cert_path must be a .pem, .crt or .der file".to_string());
+                    }
+                }
+                if options.auth_method == Some(SqlServerAuthMethod::Integrated) {
+                    problems.push(
+                        "auth_method integrated is not supported; use sql_server or azure_ad_token"
+                            .to_string(),
+                    );
+                }
+            }
+            _ => {}
+        }
+
+        if self.data_type == DataSourceKind::Kafka {
+            if let Some(config) = &self.schema_registry_config {
+                if let Err(e) = serde_json::from_value::<SchemaRegistryConfig>(config.clone()) {
+                    problems.push(format!("schema_registry_config is invalid: {}", e));
+                }
+            }
+            let kafka = self.kafka_options();
+            if kafka.sasl_mechanism.is_some()
+                && !kafka.security_protocol.is_some_and(|p| p.uses_sasl())
+            {
+                problems.push(
+                    "sasl_mechanism needs security_protocol SASL_PLAINTEXT or SASL_SSL".to_string(),
+                );
+            }
+        } else if self.schema_registry_url.is_some() || self.schema_registry_config.is_some() {
+            problems.push("schema registry settings only apply to kafka data sources".to_string());
+        }
+
+        problems
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Invalid data source {}: {}",
+                self.name,
+                problems.join("; ")
+            ))
+        }
+    }
+}
+
+/// Certificate files only matter when the connection is encrypted, and a client
+/// certificate is useless without its key.
+fn tls_file_problems(
+    tls_mode: Option<TlsMode>,
+    ca_cert_path: Option<&str>,
+    client_cert_path: Option<&str>,
+    client_key_path: Option<&str>,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    let any_file =
+        ca_cert_path.is_some() || client_cert_path.is_some() || client_key_path.is_some();
+    if any_file && tls_mode == Some(TlsMode::Disable) {
+        problems.push("certificate files need a tls_mode other than disable".to_string());
+    }
+    if client_cert_path.is_some() != client_key_path.is_some() {
+        problems.push("client_cert_path and client_key_path must be set together".to_string());
+    }
+    problems
+}
+
+fn password_command_problems(
+    password_command: Option<&str>,
+    token_lifetime_secs: Option<u64>,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    if password_command.is_some_and(|c| c.trim().is_empty()) {
+        problems.push("password_command must not be empty".to_string());
+    }
+    if let Some(lifetime) = token_lifetime_secs {
+        if password_command.is_none() {
+            problems.push("token_lifetime_secs only applies with a password_command".to_string());
+        } else if lifetime == 0 {
+            problems.push("token_lifetime_secs must be positive".to_string());
+        }
+    }
+    problems
+}
+
+/// JSON schemas the frontend uses to render data source forms.
+pub fn json_schemas() -> serde_json::Value {
+    serde_json::json!({
+        "data_source_kinds": DataSourceKind::ALL
+            .iter()
+            .map(|kind| serde_json::json!({
+                "kind": kind,
+                "default_port": kind.default_port(),
+                "file_based": kind.is_file_based(),
+            }))
+            .collect::<Vec<_>>(),
+        "data_source": schemars::schema_for!(crate::commands::CreateDataSourceRequest),
+        "options": {
+            "mysql": schemars::schema_for!(MySqlOptions),
+            "postgresql": schemars::schema_for!(PostgresOptions),
+            "sqlserver": schemars::schema_for!(SqlServerOptions),
+            "kafka": schemars::schema_for!(KafkaOptions),
+            "sqlite": schemars::schema_for!(FileDatabaseOptions),
+            "duckdb": schemars::schema_for!(FileDatabaseOptions),
+            "clickhouse": schemars::schema_for!(ClickHouseOptions),
+            "mongodb": schemars::schema_for!(MongoDbOptions),
+            "redis": schemars::schema_for!(RedisOptions),
+            "elasticsearch": schemars::schema_for!(ElasticsearchOptions),
+        },
+        "proxy_config": schemars::schema_for!(ProxyConfig),
+        "ssh_config": schemars::schema_for!(SshConfig),
+        "schema_registry_config": schemars::schema_for!(SchemaRegistryConfig),
+    })
+}
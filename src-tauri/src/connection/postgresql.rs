@@ -1,62 +1,95 @@
-use crate::connection::{ConnectionTester, ProxyConfig};
+use crate::connection::auth::CredentialProvider;
+use crate::connection::options::PostgresAuthMethod;
+use crate::connection::{ProxyType, TlsMode};
 use crate::db::DataSource;
 use anyhow::{Context, Result};
-use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::postgres::{PgConnectOptions, PgPool, PgPoolOptions};
 use sqlx::Pool;
+use std::str::FromStr;
 use std::time::Duration;
 
 pub struct PostgreSQLConnector;
 
-impl ConnectionTester for PostgreSQLConnector {
-    async fn test_connection(data_source: &DataSource) -> Result<()> {
-        Self::create_pool(data_source)
-            .await
-            .context("Failed to create PostgreSQL connection pool")?;
-        Ok(())
-    }
-}
-
 impl PostgreSQLConnector {
     pub async fn create_pool(data_source: &DataSource) -> Result<Pool<sqlx::Postgres>> {
         let url = Self::build_connection_url(data_source)?;
+        let options = data_source.postgres_options();
+        let timeout = options.connect_timeout_secs.unwrap_or(10);
+
+        let mut connect_options =
+            PgConnectOptions::from_str(&url).context("Invalid PostgreSQL connection settings")?;
+        connect_options = connect_options.username(&data_source.username);
+        // With client certificate auth the certificate is the credential
+        if options.auth_method != Some(PostgresAuthMethod::ClientCert) {
+            let password = CredentialProvider::password(
+                data_source,
+                options.password_command.as_deref(),
+                options.token_lifetime_secs,
+            )
+            .await?;
+            if !password.is_empty() {
+                connect_options = connect_options.password(&password);
+            }
+        }
+        if let Some(path) = &options.ca_cert_path {
+            connect_options = connect_options.ssl_root_cert(path);
+        }
+        if let Some(path) = &options.client_cert_path {
+            connect_options = connect_options.ssl_client_cert(path);
+        }
+        if let Some(path) = &options.client_key_path {
+            connect_options = connect_options.ssl_client_key(path);
+        }
         
         let pool = PgPoolOptions::new()
             .max_connections(5)
-            .acquire_timeout(Duration::from_secs(10))
-            .connect(&url)
+            .acquire_timeout(Duration::from_secs(timeout))
+            .connect_with(connect_options)
             .await
+            .inspect_err(|_| CredentialProvider::invalidate(data_source.id))
             .context("Failed to connect to PostgreSQL")?;
         
         Ok(pool)
     }
 
     fn build_connection_url(data_source: &DataSource) -> Result<String> {
-        let mut url = format!(
-            "postgresql://{}:{}@{}:{}/",
-            data_source.username,
-            data_source.password,
-            data_source.host,
-            data_source.port
-        );
+        // Credentials are set on the connect options, where they need no URL escaping
+        let mut url = format!("postgresql://{}:{}/", data_source.host, data_source.port);
         
         if let Some(database) = &data_source.database {
             url.push_str(database);
         } else {
             url.push_str("postgres");
         }
+
+        let options = data_source.postgres_options();
+        let mut params = Vec::new();
+        if let Some(tls_mode) = options.tls_mode {
+            let ssl_mode = match tls_mode {
+                TlsMode::Disable => "disable",
+                TlsMode::Prefer => "prefer",
+                TlsMode::Require => "require",
+                TlsMode::VerifyCa => "verify-ca",
+                TlsMode::VerifyFull => "verify-full",
+            };
+            params.push(format!("sslmode={}", ssl_mode));
+        }
+        if let Some(application_name) = &options.application_name {
+            params.push(format!("application_name={}", application_name));
+        }
+        if !params.is_empty() {
+            url.push('?');
+            url.push_str(&params.join("&"));
+        }
         
         // Handle proxy configuration
         if let Some(proxy_type) = &data_source.proxy_type {
-            match proxy_type.as_str() {
-                "http" => {
-                    if let Some(proxy_config) = &data_source.proxy_config {
-                        let _proxy: ProxyConfig = serde_json::from_value(proxy_config.clone())
-                            .context("Invalid proxy config")?;
-                        // Note: sqlx doesn't directly support HTTP proxy for PostgreSQL
-                        return Err(anyhow::anyhow!("HTTP proxy for PostgreSQL is not directly supported by sqlx"));
-                    }
+            match proxy_type {
+                ProxyType::Http if data_source.proxy_config.is_some() => {
+                    // Note: sqlx doesn't directly support HTTP proxy for PostgreSQL
+                    return Err(anyhow::anyhow!("HTTP proxy for PostgreSQL is not directly supported by sqlx"));
                 }
-                "socks5" => {
+                ProxyType::Socks5 => {
                     return Err(anyhow::anyhow!("SOCKS5 proxy for PostgreSQL requires custom implementation"));
                 }
                 _ => {}
@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
-use crate::connection::{ProxyConfig, SshConfig};
+use crate::connection::{ProxyConfig, ProxyType, SshConfig};
+use crate::db::DataSource;
 use std::net::TcpStream;
 use std::io::{Read, Write};
 use std::time::Duration;
@@ -105,6 +106,42 @@ impl ProxyManager {
         }
     }
 
+    pub fn create_socks5_proxy_url(proxy: &ProxyConfig) -> String {
+        // socks5h resolves the target host on the proxy side
+        if let (Some(username), Some(password)) = (&proxy.username, &proxy.password) {
+            format!("socks5h://{}:{}@{}:{}", username, password, proxy.host, proxy.port)
+        } else {
+            format!("socks5h://{}:{}", proxy.host, proxy.port)
+        }
+    }
+
+    /// The proxy HTTP clients of a data source should use. SSH tunnels are an error
+    /// rather than None so the client never silently connects directly.
+    pub fn http_client_proxy(data_source: &DataSource) -> Result<Option<reqwest::Proxy>> {
+        let proxy_type = match data_source.proxy_type {
+            None => return Ok(None),
+            Some(ProxyType::Ssh) => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported proxy type ssh for {} over HTTP; use a SOCKS5 or HTTP proxy",
+                    data_source.data_type
+                ))
+            }
+            Some(proxy_type) => proxy_type,
+        };
+        let proxy = data_source
+            .proxy_config
+            .as_ref()
+            .context("Proxy config is missing")?;
+        let proxy_url = if proxy_type == ProxyType::Http {
+            Self::create_http_proxy_url(proxy)
+        } else {
+            Self::create_socks5_proxy_url(proxy)
+        };
+        Ok(Some(
+            reqwest::Proxy::all(&proxy_url).context("Invalid proxy URL")?,
+        ))
+    }
+
     pub async fn create_ssh_tunnel(_ssh: &SshConfig) -> Result<()> {
         // SSH tunnel implementation using russh
         // This is a simplified placeholder implementation.
@@ -120,3 +157,71 @@ impl ProxyManager {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::DataSourceKind;
+
+    fn data_source(
+        proxy_type: Option<ProxyType>,
+        proxy_config: Option<ProxyConfig>,
+    ) -> DataSource {
+        let now = chrono::Utc::now();
+        DataSource {
+            id: 1,
+            context_id: 1,
+            name: "logs".to_string(),
+            data_type: DataSourceKind::Elasticsearch,
+            host: "es.internal".to_string(),
+            port: 9200,
+            database: None,
+            username: String::new(),
+            password: String::new(),
+            proxy_type,
+            proxy_config,
+            ssh_config: Some(SshConfig {
+                host: "bastion".to_string(),
+                port: 22,
+                username: "ops".to_string(),
+                password: None,
+                private_key_path: None,
+                local_port: 0,
+            }),
+            schema_registry_url: None,
+            schema_registry_config: None,
+            options: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn ssh_is_refused_without_proxy_config() {
+        let error =
+            ProxyManager::http_client_proxy(&data_source(Some(ProxyType::Ssh), None)).unwrap_err();
+        assert!(error.to_string().contains("ssh"));
+    }
+
+    #[test]
+    fn missing_proxy_config_is_an_error() {
+        assert!(
+            ProxyManager::http_client_proxy(&data_source(Some(ProxyType::Socks5), None)).is_err()
+        );
+        assert!(ProxyManager::http_client_proxy(&data_source(None, None))
+            .unwrap()
+            .is_none());
+        let config = ProxyConfig {
+            proxy_type: "socks5".to_string(),
+            host: "proxy.internal".to_string(),
+            port: 1080,
+            username: None,
+            password: None,
+        };
+        assert!(ProxyManager::http_client_proxy(&data_source(
+            Some(ProxyType::Socks5),
+            Some(config)
+        ))
+        .unwrap()
+        .is_some());
+    }
+}
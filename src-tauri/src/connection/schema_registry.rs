@@ -0,0 +1,224 @@
+use crate::connection::proxy::ProxyManager;
+use crate::connection::SchemaRegistryConfig;
+use crate::db::DataSource;
+use anyhow::{Context, Result};
+use reqwest::{Client as HttpClient, Method, RequestBuilder, Response, Url};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::time::Duration;
+
+const CONTENT_TYPE: &str = "application/vnd.schemaregistry.v1+json";
+const DEFAULT_MAX_CONCURRENCY: usize = 8;
+
+pub struct SchemaRegistryClient {
+    client: HttpClient,
+    base_url: Url,
+    config: SchemaRegistryConfig,
+}
+
+impl SchemaRegistryClient {
+    pub fn new(data_source: &DataSource) -> Result<Self> {
+        let registry_url = data_source
+            .schema_registry_url
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Schema Registry URL not configured"))?;
+
+        let base_url = Url::parse(registry_url.trim_end_matches('/'))
+            .context(format!("Invalid Schema Registry URL: {}", registry_url))?;
+
+        let config: SchemaRegistryConfig = match &data_source.schema_registry_config {
+            Some(value) if !value.is_null() => {
+                serde_json::from_value(value.clone()).context("Invalid Schema Registry config")?
+            }
+            _ => SchemaRegistryConfig::default(),
+        };
+
+        let client = Self::build_http_client(data_source, &config)?;
+
+        Ok(SchemaRegistryClient {
+            client,
+            base_url,
+            config,
+        })
+    }
+
+    pub fn max_concurrency(&self) -> usize {
+        self.config
+            .max_concurrency
+            .unwrap_or(DEFAULT_MAX_CONCURRENCY)
+            .max(1)
+    }
+
+    fn build_http_client(
+        data_source: &DataSource,
+        config: &SchemaRegistryConfig,
+    ) -> Result<HttpClient> {
+        let mut builder = HttpClient::builder()
+            .timeout(Duration::from_secs(30))
+            .danger_accept_invalid_certs(config.insecure_skip_verify);
+
+        if let Some(ca_path) = &config.ca_cert_path {
+            let pem = std::fs::read(ca_path)
+                .context(format!("Failed to read CA certificate: {}", ca_path))?;
+            let cert = reqwest::Certificate::from_pem(&pem).context("Invalid CA certificate")?;
+            builder = builder.add_root_certificate(cert);
+        }
+
+        match (&config.client_cert_path, &config.client_key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                let cert = std::fs::read(cert_path)
+                    .context(format!("Failed to read client certificate: {}", cert_path))?;
+                let key = std::fs::read(key_path)
+                    .context(format!("Failed to read client key: {}", key_path))?;
+                let identity = reqwest::Identity::from_pkcs8_pem(&cert, &key)
+                    .context("Invalid client certificate or key")?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Both client_cert_path and client_key_path must be set for mutual TLS"
+                ))
+            }
+        }
+
+        // Route registry traffic through the data source's proxy when one is configured
+        if let Some(proxy) = ProxyManager::http_client_proxy(data_source)? {
+            builder = builder.proxy(proxy);
+        }
+
+        builder
+            .build()
+            .context("Failed to create Schema Registry HTTP client")
+    }
+
+    fn url(&self, segments: &[&str]) -> Result<Url> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Schema Registry URL cannot be a base URL"))?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let builder = self
+            .client
+            .request(method, url)
+            .header(reqwest::header::ACCEPT, CONTENT_TYPE);
+
+        match self.config.auth_type.as_deref() {
+            Some("basic") => builder.basic_auth(
+                self.config.username.clone().unwrap_or_default(),
+                self.config.password.clone(),
+            ),
+            Some("bearer") => builder.bearer_auth(self.config.token.clone().unwrap_or_default()),
+            _ => builder,
+        }
+    }
+
+    async fn check_status(response: Response, what: &str) -> Result<Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        // Schema Registry errors look like {"error_code": 40401, "message": "..."}
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|v| {
+                v.get("message")
+                    .and_then(|m| m.as_str())
+                    .map(|m| m.to_string())
+            })
+            .unwrap_or(body);
+
+        Err(anyhow::anyhow!(
+            "Schema Registry returned {} for {}: {}",
+            status,
+            what,
+            message
+        ))
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T> {
+        let url = self.url(segments)?;
+        let what = url.path().to_string();
+        let response = self
+            .request(Method::GET, url)
+            .send()
+            .await
+            .context(format!("Failed to reach Schema Registry: {}", what))?;
+
+        Self::check_status(response, &what)
+            .await?
+            .json()
+            .await
+            .context(format!(
+                "Failed to parse Schema Registry response: {}",
+                what
+            ))
+    }
+
+    /// GET that maps 404 to `None` instead of an error.
+    pub async fn get_optional<T: DeserializeOwned>(
+        &self,
+        segments: &[&str],
+        query: &[(&str, &str)],
+    ) -> Result<Option<T>> {
+        let mut url = self.url(segments)?;
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        let what = url.path().to_string();
+        let response = self
+            .request(Method::GET, url)
+            .send()
+            .await
+            .context(format!("Failed to reach Schema Registry: {}", what))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let value = Self::check_status(response, &what)
+            .await?
+            .json()
+            .await
+            .context(format!(
+                "Failed to parse Schema Registry response: {}",
+                what
+            ))?;
+        Ok(Some(value))
+    }
+
+    pub async fn post<T: DeserializeOwned>(
+        &self,
+        segments: &[&str],
+        query: &[(&str, &str)],
+        body: &Value,
+    ) -> Result<T> {
+        let mut url = self.url(segments)?;
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        let what = url.path().to_string();
+        let response = self
+            .request(Method::POST, url)
+            .header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE)
+            .body(serde_json::to_vec(body)?)
+            .send()
+            .await
+            .context(format!("Failed to reach Schema Registry: {}", what))?;
+
+        Self::check_status(response, &what)
+            .await?
+            .json()
+            .await
+            .context(format!(
+                "Failed to parse Schema Registry response: {}",
+                what
+            ))
+    }
+}
@@ -1,33 +1,60 @@
-use crate::connection::{ConnectionTester, ProxyConfig};
+use crate::connection::auth::CredentialProvider;
+use crate::connection::options::SqlServerAuthMethod;
+use crate::connection::{ProxyConfig, TlsMode};
 use crate::db::DataSource;
 use anyhow::{Context, Result};
-use tiberius::{Client, Config, AuthMethod};
+use tiberius::{AuthMethod, Client, Config, EncryptionLevel};
 use tokio::net::TcpStream;
 use tokio_util::compat::TokioAsyncWriteCompatExt;
 
 pub struct SQLServerConnector;
 
-impl ConnectionTester for SQLServerConnector {
-    async fn test_connection(data_source: &DataSource) -> Result<()> {
-        Self::create_client(data_source)
-            .await
-            .context("Failed to create SQL Server connection")?;
-        Ok(())
-    }
-}
-
 impl SQLServerConnector {
     pub async fn create_client(data_source: &DataSource) -> Result<Client<tokio_util::compat::Compat<TcpStream>>> {
         let mut config = Config::new();
         config.host(data_source.host.clone());
         config.port(data_source.port);
-        config.authentication(AuthMethod::sql_server(&data_source.username, &data_source.password));
         
         if let Some(database) = &data_source.database {
             config.database(database);
         }
-        
-        config.trust_cert();
+
+        let options = data_source.sqlserver_options();
+        let password = CredentialProvider::password(
+            data_source,
+            options.password_command.as_deref(),
+            options.token_lifetime_secs,
+        )
+        .await?;
+        config.authentication(match options.auth_method {
+            None | Some(SqlServerAuthMethod::SqlServer) => {
+                AuthMethod::sql_server(&data_source.username, password)
+            }
+            Some(SqlServerAuthMethod::AzureAdToken) => AuthMethod::aad_token(password),
+            Some(SqlServerAuthMethod::Integrated) => {
+                anyhow::bail!("Integrated authentication is not supported; use SQL Server or Azure AD token authentication")
+            }
+        });
+        if let Some(instance_name) = &options.instance_name {
+            config.instance_name(instance_name);
+        }
+        if let Some(application_name) = &options.application_name {
+            config.application_name(application_name);
+        }
+        let tls_mode = options.tls_mode.unwrap_or_default();
+        config.encryption(match tls_mode {
+            TlsMode::Disable => EncryptionLevel::NotSupported,
+            TlsMode::Prefer => EncryptionLevel::On,
+            TlsMode::Require | TlsMode::VerifyCa | TlsMode::VerifyFull => EncryptionLevel::Required,
+        });
+        // Only the verify modes check the server certificate, against the CA bundle if
+        // one is given and the system trust store otherwise. tiberius always checks the
+        // host name too, so verify-ca is as strict as verify-full.
+        match (tls_mode, &options.ca_cert_path) {
+            (TlsMode::VerifyCa | TlsMode::VerifyFull, Some(path)) => config.trust_cert_ca(path),
+            (TlsMode::VerifyCa | TlsMode::VerifyFull, None) => {}
+            _ => config.trust_cert(),
+        }
         
         let tcp = TcpStream::connect(config.get_addr())
             .await
@@ -38,6 +65,7 @@ impl SQLServerConnector {
         let compat_stream = tcp.compat_write();
         let client = Client::connect(config, compat_stream)
             .await
+            .inspect_err(|_| CredentialProvider::invalidate(data_source.id))
             .context("Failed to establish SQL Server connection")?;
         
         Ok(client)
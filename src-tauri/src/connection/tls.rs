@@ -0,0 +1,193 @@
+use crate::connection::proxy::ProxyManager;
+use crate::connection::ProxyType;
+use crate::db::DataSource;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// What a connection test learned about the encryption of the connection.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TlsInfo {
+    pub encrypted: bool,
+    pub version: Option<String>, // e.g. TLSv1.3
+    pub cipher: Option<String>,
+    pub certificate_subject: Option<String>,
+    pub certificate_issuer: Option<String>,
+    pub certificate_error: Option<String>, // Why the certificate could not be read
+}
+
+impl TlsInfo {
+    pub fn with_certificate(mut self, certificate: Result<Option<PeerCertificate>>) -> Self {
+        match certificate {
+            Ok(Some(certificate)) => {
+                self.certificate_subject = Some(certificate.subject);
+                self.certificate_issuer = Some(certificate.issuer);
+            }
+            Ok(None) => {}
+            Err(e) => self.certificate_error = Some(format!("{:#}", e)),
+        }
+        self
+    }
+}
+
+pub struct PeerCertificate {
+    pub subject: String,
+    pub issuer: String,
+}
+
+// SSLRequest: length 8, then the magic request code 80877103
+const POSTGRES_SSL_REQUEST: [u8; 8] = [0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f];
+
+const MYSQL_CLIENT_LONG_PASSWORD: u32 = 0x0000_0001;
+const MYSQL_CLIENT_PROTOCOL_41: u32 = 0x0000_0200;
+const MYSQL_CLIENT_SSL: u32 = 0x0000_0800;
+const MYSQL_CLIENT_SECURE_CONNECTION: u32 = 0x0000_8000;
+const MYSQL_UTF8MB4_GENERAL_CI: u8 = 45;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The drivers do not expose the server certificate, so these probes open a second
+/// connection, upgrade it to TLS the way the protocol does and read the certificate
+/// before hanging up. The probe goes the same way as the real connection, through
+/// its proxy if it has one. The certificate is not verified here; the real
+/// connection already did that according to the data source's TLS mode.
+pub struct TlsProbe;
+
+impl TlsProbe {
+    pub async fn postgres_certificate(data_source: &DataSource) -> Result<Option<PeerCertificate>> {
+        let mut stream = Self::connect(data_source).await?;
+        stream.write_all(&POSTGRES_SSL_REQUEST).await?;
+        if stream.read_u8().await? != b'S' {
+            return Ok(None);
+        }
+        Self::peer_certificate(&data_source.host, stream).await
+    }
+
+    pub async fn mysql_certificate(data_source: &DataSource) -> Result<Option<PeerCertificate>> {
+        let mut stream = Self::connect(data_source).await?;
+
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await?;
+        let length = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+        let sequence = header[3];
+        let mut handshake = vec![0u8; length];
+        stream.read_exact(&mut handshake).await?;
+        if !Self::mysql_supports_tls(&handshake)? {
+            return Ok(None);
+        }
+
+        let capabilities = MYSQL_CLIENT_LONG_PASSWORD
+            | MYSQL_CLIENT_PROTOCOL_41
+            | MYSQL_CLIENT_SSL
+            | MYSQL_CLIENT_SECURE_CONNECTION;
+        let mut request = vec![32, 0, 0, sequence.wrapping_add(1)];
+        request.extend_from_slice(&capabilities.to_le_bytes());
+        request.extend_from_slice(&(16u32 * 1024 * 1024).to_le_bytes());
+        request.push(MYSQL_UTF8MB4_GENERAL_CI);
+        request.extend_from_slice(&[0u8; 23]);
+        stream.write_all(&request).await?;
+
+        Self::peer_certificate(&data_source.host, stream).await
+    }
+
+    async fn connect(data_source: &DataSource) -> Result<TcpStream> {
+        let (host, port) = (data_source.host.clone(), data_source.port);
+        match (&data_source.proxy_type, &data_source.proxy_config) {
+            (None, _) => {
+                tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect((host.as_str(), port)))
+                    .await
+                    .context(format!("Connecting to {}:{} timed out", host, port))?
+                    .context(format!("Failed to connect to {}:{}", host, port))
+            }
+            (Some(ProxyType::Socks5), Some(proxy)) => {
+                let proxy = proxy.clone();
+                let stream = tokio::task::spawn_blocking(move || {
+                    ProxyManager::create_socks5_proxy_stream(&proxy, &host, port)
+                })
+                .await??;
+                stream.set_nonblocking(true)?;
+                Ok(TcpStream::from_std(stream)?)
+            }
+            (Some(ProxyType::Socks5), None) => Err(anyhow::anyhow!("Proxy config is missing")),
+            (Some(proxy_type), _) => Err(anyhow::anyhow!(
+                "The certificate cannot be read through {} proxies",
+                proxy_type
+            )),
+        }
+    }
+
+    /// Reads the lower capability flags of the initial handshake packet: protocol
+    /// version, NUL terminated server version, connection id, 8 bytes of scramble
+    /// and a filler byte come first.
+    fn mysql_supports_tls(handshake: &[u8]) -> Result<bool> {
+        if handshake.first() == Some(&0xff) {
+            anyhow::bail!("MySQL refused the connection");
+        }
+        let version_end = handshake
+            .iter()
+            .skip(1)
+            .position(|b| *b == 0)
+            .context("Malformed MySQL handshake")?
+            + 1;
+        let flags_at = version_end + 1 + 4 + 8 + 1;
+        let flags = handshake
+            .get(flags_at..flags_at + 2)
+            .context("Malformed MySQL handshake")?;
+        Ok(u16::from_le_bytes([flags[0], flags[1]]) as u32 & MYSQL_CLIENT_SSL != 0)
+    }
+
+    async fn peer_certificate(host: &str, stream: TcpStream) -> Result<Option<PeerCertificate>> {
+        let connector = native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true)
+            .build()?;
+        let stream = tokio::time::timeout(
+            PROBE_TIMEOUT,
+            tokio_native_tls::TlsConnector::from(connector).connect(host, stream),
+        )
+        .await
+        .context("The TLS handshake timed out")?
+        .context("TLS handshake failed")?;
+
+        let Some(certificate) = stream.get_ref().peer_certificate()? else {
+            return Ok(None);
+        };
+        let der = certificate.to_der()?;
+        let (_, certificate) = X509Certificate::from_der(&der)
+            .map_err(|e| anyhow::anyhow!("Invalid server certificate: {}", e))?;
+        Ok(Some(PeerCertificate {
+            subject: certificate.subject().to_string(),
+            issuer: certificate.issuer().to_string(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handshake(flags: u16) -> Vec<u8> {
+        let mut packet = vec![10];
+        packet.extend_from_slice(b"8.0.36\0");
+        packet.extend_from_slice(&[1, 0, 0, 0]);
+        packet.extend_from_slice(&[0x41; 8]);
+        packet.push(0);
+        packet.extend_from_slice(&flags.to_le_bytes());
+        packet
+    }
+
+    #[test]
+    fn mysql_handshake_advertises_tls() {
+        assert!(TlsProbe::mysql_supports_tls(&handshake(0xffff)).unwrap());
+        assert!(!TlsProbe::mysql_supports_tls(&handshake(0xf7ff)).unwrap());
+    }
+
+    #[test]
+    fn mysql_handshake_errors_are_reported() {
+        assert!(TlsProbe::mysql_supports_tls(&[0xff, 0x15, 0x04]).is_err());
+        assert!(TlsProbe::mysql_supports_tls(&handshake(0xffff)[..12]).is_err());
+    }
+}
@@ -0,0 +1,395 @@
+use crate::connection::proxy::ProxyManager;
+use crate::connection::DataSourceKind;
+use crate::connector::{Capability, Connector, QueryResult, SQL_CAPABILITIES};
+use crate::db::DataSource;
+use crate::metadata::{ColumnInfo, TableInfo};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{Client as HttpClient, Url};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+const DEFAULT_DATABASE: &str = "default";
+
+/// Talks to ClickHouse over its HTTP interface, so the existing proxy support applies.
+pub struct ClickHouseConnector;
+
+struct ClickHouseClient {
+    client: HttpClient,
+    url: Url,
+    username: String,
+    password: String,
+    database: String,
+    max_execution_time_secs: Option<u64>,
+}
+
+// Response of the JSONCompact output format
+#[derive(Deserialize)]
+struct CompactResponse {
+    meta: Vec<ColumnMeta>,
+    data: Vec<Vec<Value>>,
+}
+
+#[derive(Deserialize)]
+struct ColumnMeta {
+    name: String,
+}
+
+impl ClickHouseClient {
+    fn new(data_source: &DataSource) -> Result<Self> {
+        let options = data_source.clickhouse_options();
+        let scheme = if options.secure.unwrap_or(false) {
+            "https"
+        } else {
+            "http"
+        };
+        let url = Url::parse(&format!(
+            "{}://{}:{}/",
+            scheme, data_source.host, data_source.port
+        ))
+        .context("Invalid ClickHouse host")?;
+
+        let mut builder = HttpClient::builder()
+            .timeout(Duration::from_secs(
+                options.max_execution_time_secs.unwrap_or(30) + 5,
+            ))
+            .danger_accept_invalid_certs(options.insecure_skip_verify.unwrap_or(false));
+        if let Some(proxy) = ProxyManager::http_client_proxy(data_source)? {
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(ClickHouseClient {
+            client: builder
+                .build()
+                .context("Failed to create ClickHouse HTTP client")?,
+            url,
+            username: data_source.username.clone(),
+            password: data_source.password.clone(),
+            database: data_source
+                .database
+                .clone()
+                .unwrap_or_else(|| DEFAULT_DATABASE.to_string()),
+            max_execution_time_secs: options.max_execution_time_secs,
+        })
+    }
+
+    /// Runs `sql` with `{name:Type}` placeholders bound from `params`. Statements
+    /// without a result set come back as None.
+    async fn query(
+        &self,
+        sql: &str,
+        params: &[(&str, &str)],
+        settings: &[(&str, String)],
+    ) -> Result<Option<CompactResponse>> {
+        let mut url = self.url.clone();
+        {
+            let mut pairs = url.query_pairs_mut();
+            pairs
+                .append_pair("database", &self.database)
+                .append_pair("default_format", "JSONCompact")
+                .append_pair("output_format_json_quote_64bit_integers", "0");
+            for (name, value) in params {
+                pairs.append_pair(&format!("param_{}", name), value);
+            }
+            if let Some(secs) = self.max_execution_time_secs {
+                pairs.append_pair("max_execution_time", &secs.to_string());
+            }
+            for (name, value) in settings {
+                pairs.append_pair(name, value);
+            }
+        }
+
+        let mut request = self.client.post(url).body(sql.to_string());
+        if !self.username.is_empty() {
+            request = request
+                .header("X-ClickHouse-User", &self.username)
+                .header("X-ClickHouse-Key", &self.password);
+        }
+
+        let response = request.send().await.context("Failed to reach ClickHouse")?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .context("Failed to read ClickHouse response")?;
+        if !status.is_success() {
+            return Err(anyhow::anyhow!(
+                "ClickHouse returned {}: {}",
+                status,
+                body.trim()
+            ));
+        }
+        if body.trim().is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(
+            serde_json::from_str(&body).context("Unexpected ClickHouse response")?,
+        ))
+    }
+
+    async fn rows(&self, sql: &str, params: &[(&str, &str)]) -> Result<Vec<Vec<Value>>> {
+        Ok(self
+            .query(sql, params, &[])
+            .await?
+            .map(|r| r.data)
+            .unwrap_or_default())
+    }
+
+    /// Columns of every table in the database, or of one table, keyed by table name.
+    async fn columns(
+        &self,
+        database: &str,
+        table_name: Option<&str>,
+    ) -> Result<HashMap<String, Vec<ColumnInfo>>> {
+        let mut sql = "SELECT table, name, type, default_kind, default_expression,
+                    is_in_partition_key, is_in_sorting_key, is_in_primary_key
+             FROM system.columns
+             WHERE database = {database:String}"
+            .to_string();
+        let mut params = vec![("database", database)];
+        if let Some(table_name) = table_name {
+            sql.push_str(" AND table = {table:String}");
+            params.push(("table", table_name));
+        }
+        sql.push_str(" ORDER BY table, position");
+
+        let mut columns: HashMap<String, Vec<ColumnInfo>> = HashMap::new();
+        for row in self.rows(&sql, &params).await? {
+            let data_type = text(&row[2]);
+            let default_kind = text(&row[3]);
+            let mut constraints = Vec::new();
+            if flag(&row[7]) {
+                constraints.push("PRIMARY KEY".to_string());
+            }
+            if flag(&row[6]) {
+                constraints.push("SORTING KEY".to_string());
+            }
+            if flag(&row[5]) {
+                constraints.push("PARTITION KEY".to_string());
+            }
+            // MATERIALIZED and ALIAS columns are computed rather than defaulted
+            if default_kind == "MATERIALIZED" || default_kind == "ALIAS" {
+                constraints.push(default_kind.clone());
+            }
+
+            columns.entry(text(&row[0])).or_default().push(ColumnInfo {
+                name: text(&row[1]),
+                is_nullable: data_type.starts_with("Nullable("),
+                data_type,
+                default_value: Some(text(&row[4])).filter(|d| !d.is_empty()),
+                constraints,
+                properties: Default::default(),
+            });
+        }
+        Ok(columns)
+    }
+
+    async fn tables(&self, database: &str, table_name: Option<&str>) -> Result<Vec<TableInfo>> {
+        let mut sql = "SELECT name, engine, partition_key, sorting_key, primary_key,
+                    total_rows, engine_full
+             FROM system.tables
+             WHERE database = {database:String} AND NOT is_temporary"
+            .to_string();
+        let mut params = vec![("database", database)];
+        if let Some(table_name) = table_name {
+            sql.push_str(" AND name = {table:String}");
+            params.push(("table", table_name));
+        }
+        sql.push_str(" ORDER BY name");
+
+        let rows = self.rows(&sql, &params).await?;
+        let mut columns = self.columns(database, table_name).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let name = text(&row[0]);
+                let mut properties = BTreeMap::new();
+                for (key, value) in [
+                    ("engine", text(&row[1])),
+                    ("partition_key", text(&row[2])),
+                    ("sorting_key", text(&row[3])),
+                    ("primary_key", text(&row[4])),
+                ] {
+                    if !value.is_empty() {
+                        properties.insert(key.to_string(), value);
+                    }
+                }
+                if let Some(ttl) = table_ttl(&text(&row[6])) {
+                    properties.insert("ttl".to_string(), ttl);
+                }
+
+                TableInfo {
+                    columns: columns.remove(&name).unwrap_or_default(),
+                    name,
+                    schema: Some(database.to_string()),
+                    row_count: row[5].as_i64(),
+                    properties,
+                }
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Connector for ClickHouseConnector {
+    fn kind(&self) -> DataSourceKind {
+        DataSourceKind::Clickhouse
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        SQL_CAPABILITIES
+    }
+
+    async fn test_connection(&self, data_source: &DataSource) -> Result<()> {
+        ClickHouseClient::new(data_source)?
+            .query("SELECT 1", &[], &[])
+            .await
+            .context("Failed to connect to ClickHouse")?;
+        Ok(())
+    }
+
+    async fn get_tables(&self, data_source: &DataSource) -> Result<Vec<TableInfo>> {
+        let client = ClickHouseClient::new(data_source)?;
+        client.tables(&client.database, None).await
+    }
+
+    async fn get_table_structure(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableInfo> {
+        let client = ClickHouseClient::new(data_source)?;
+        let database = schema.unwrap_or(&client.database);
+        client
+            .tables(database, Some(table_name))
+            .await?
+            .pop()
+            .context(format!("Table {}.{} not found", database, table_name))
+    }
+
+    /// Counts rows of active parts, which is free for MergeTree tables; other engines
+    /// have no parts and are counted directly.
+    async fn get_table_row_count(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<i64> {
+        let client = ClickHouseClient::new(data_source)?;
+        let database = schema.unwrap_or(&client.database);
+        let params = [("database", database), ("table", table_name)];
+
+        let parts = client
+            .rows(
+                "SELECT count(), sum(rows) FROM system.parts
+                 WHERE database = {database:String} AND table = {table:String} AND active",
+                &params,
+            )
+            .await?;
+        if let Some(row) = parts.first() {
+            if row[0].as_i64().unwrap_or(0) > 0 {
+                return Ok(row[1].as_i64().unwrap_or(0));
+            }
+        }
+
+        let counted = client
+            .rows(
+                "SELECT count() FROM {database:Identifier}.{table:Identifier}",
+                &params,
+            )
+            .await?;
+        counted
+            .first()
+            .and_then(|row| row[0].as_i64())
+            .context("ClickHouse returned no row count")
+    }
+
+    async fn execute_query(
+        &self,
+        data_source: &DataSource,
+        sql: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult> {
+        let client = ClickHouseClient::new(data_source)?;
+        // Ask for one row more than shown so truncation can be reported
+        let settings = [
+            ("max_result_rows", (max_rows + 1).to_string()),
+            ("result_overflow_mode", "break".to_string()),
+        ];
+        let Some(response) = client.query(sql, &[], &settings).await? else {
+            return Ok(QueryResult::default());
+        };
+
+        let mut rows = response.data;
+        let truncated = rows.len() > max_rows;
+        rows.truncate(max_rows);
+        Ok(QueryResult {
+            columns: response.meta.into_iter().map(|c| c.name).collect(),
+            rows,
+            rows_affected: None,
+            truncated,
+        })
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn flag(value: &Value) -> bool {
+    value.as_u64().is_some_and(|v| v != 0)
+}
+
+/// The TTL clause of `system.tables.engine_full`, e.g. `MergeTree ORDER BY id
+/// TTL ts + toIntervalDay(30) SETTINGS index_granularity = 8192`; there is no column
+/// for it. SETTINGS is always the last clause.
+fn table_ttl(engine_full: &str) -> Option<String> {
+    let start = engine_full.find(" TTL ")? + " TTL ".len();
+    let rest = &engine_full[start..];
+    let end = rest.find(" SETTINGS ").unwrap_or(rest.len());
+    Some(rest[..end].trim().to_string()).filter(|ttl| !ttl.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn ttl_from_engine_full() {
+        assert_eq!(
+            table_ttl("MergeTree PARTITION BY toYYYYMM(ts) ORDER BY id TTL ts + toIntervalDay(30) SETTINGS index_granularity = 8192")
+                .as_deref(),
+            Some("ts + toIntervalDay(30)")
+        );
+        assert_eq!(
+            table_ttl("ReplacingMergeTree ORDER BY id TTL ts + toIntervalMonth(1) DELETE, ts + toIntervalWeek(1) TO VOLUME 'cold'")
+                .as_deref(),
+            Some("ts + toIntervalMonth(1) DELETE, ts + toIntervalWeek(1) TO VOLUME 'cold'")
+        );
+        assert_eq!(
+            table_ttl("MergeTree ORDER BY id SETTINGS index_granularity = 8192"),
+            None
+        );
+        assert_eq!(table_ttl("Memory"), None);
+        assert_eq!(table_ttl(""), None);
+    }
+
+    #[test]
+    fn compact_values() {
+        assert_eq!(text(&json!("UInt64")), "UInt64");
+        assert_eq!(text(&json!(null)), "");
+        assert_eq!(text(&json!(42)), "42");
+        assert!(flag(&json!(1)));
+        assert!(!flag(&json!(0)));
+        assert!(!flag(&json!("1")));
+    }
+}
@@ -0,0 +1,237 @@
+use crate::connection::DataSourceKind;
+use crate::connector::sqlite::{database_path, quote};
+use crate::connector::{Capability, Connector, QueryResult, SQL_CAPABILITIES};
+use crate::db::DataSource;
+use crate::metadata::{ColumnInfo, TableInfo};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use duckdb::types::{TimeUnit, Value as DuckValue};
+use duckdb::{AccessMode, Config, Connection};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+pub struct DuckDbConnector;
+
+impl DuckDbConnector {
+    fn open(data_source: &DataSource) -> Result<Connection> {
+        let path = database_path(data_source)?;
+        let read_only = data_source
+            .file_database_options()
+            .read_only
+            .unwrap_or(true);
+        let access_mode = if read_only {
+            AccessMode::ReadOnly
+        } else {
+            AccessMode::ReadWrite
+        };
+
+        let config = Config::default().access_mode(access_mode)?;
+        Connection::open_with_flags(&path, config)
+            .context(format!("Failed to open DuckDB database {}", path.display()))
+    }
+
+    async fn run<T, F>(data_source: &DataSource, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let data_source = data_source.clone();
+        tokio::task::spawn_blocking(move || f(&Self::open(&data_source)?)).await?
+    }
+
+    fn table_structure(conn: &Connection, schema: &str, table_name: &str) -> Result<TableInfo> {
+        let mut stmt = conn.prepare(
+            "SELECT unnest(constraint_column_names) FROM duckdb_constraints()
+             WHERE schema_name = ? AND table_name = ? AND constraint_type = 'PRIMARY KEY'",
+        )?;
+        let primary_key = stmt
+            .query_map([schema, table_name], |row| row.get::<_, String>(0))?
+            .collect::<duckdb::Result<HashSet<_>>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT column_name, data_type, is_nullable, column_default
+             FROM information_schema.columns
+             WHERE table_schema = ? AND table_name = ?
+             ORDER BY ordinal_position",
+        )?;
+        let columns = stmt
+            .query_map([schema, table_name], |row| {
+                let name: String = row.get(0)?;
+                let is_nullable: String = row.get(2)?;
+                Ok(ColumnInfo {
+                    constraints: if primary_key.contains(&name) {
+                        vec!["PRIMARY KEY".to_string()]
+                    } else {
+                        Vec::new()
+                    },
+                    name,
+                    data_type: row.get(1)?,
+                    is_nullable: is_nullable == "YES",
+                    default_value: row.get(3)?,
+                    properties: Default::default(),
+                })
+            })?
+            .collect::<duckdb::Result<Vec<_>>>()?;
+
+        if columns.is_empty() {
+            return Err(anyhow::anyhow!("Table {}.{} not found", schema, table_name));
+        }
+
+        Ok(TableInfo {
+            name: table_name.to_string(),
+            schema: Some(schema.to_string()),
+            row_count: None,
+            columns,
+            properties: Default::default(),
+        })
+    }
+}
+
+#[async_trait]
+impl Connector for DuckDbConnector {
+    fn kind(&self) -> DataSourceKind {
+        DataSourceKind::Duckdb
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        SQL_CAPABILITIES
+    }
+
+    async fn test_connection(&self, data_source: &DataSource) -> Result<()> {
+        Self::run(data_source, |conn| {
+            conn.query_row("SELECT 1", [], |row| row.get::<_, i32>(0))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_tables(&self, data_source: &DataSource) -> Result<Vec<TableInfo>> {
+        Self::run(data_source, |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT table_schema, table_name FROM information_schema.tables
+                 WHERE table_catalog = current_database() AND table_type = 'BASE TABLE'
+                 ORDER BY table_schema, table_name",
+            )?;
+            let names = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<duckdb::Result<Vec<_>>>()?;
+
+            names
+                .iter()
+                .map(|(schema, name)| Self::table_structure(conn, schema, name))
+                .collect()
+        })
+        .await
+    }
+
+    async fn get_table_structure(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableInfo> {
+        let schema = schema.unwrap_or(MAIN_SCHEMA).to_string();
+        let table_name = table_name.to_string();
+        Self::run(data_source, move |conn| {
+            Self::table_structure(conn, &schema, &table_name)
+        })
+        .await
+    }
+
+    async fn get_table_row_count(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<i64> {
+        let query = format!(
+            "SELECT COUNT(*) FROM \"{}\".\"{}\"",
+            quote(schema.unwrap_or(MAIN_SCHEMA)),
+            quote(table_name)
+        );
+        Self::run(data_source, move |conn| {
+            Ok(conn.query_row(&query, [], |row| row.get(0))?)
+        })
+        .await
+    }
+
+    /// Column names are only known once DuckDB has run the statement; statements
+    /// that change data return their `Count` as a one-row result.
+    async fn execute_query(
+        &self,
+        data_source: &DataSource,
+        sql: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult> {
+        let sql = sql.to_string();
+        Self::run(data_source, move |conn| {
+            let mut stmt = conn.prepare(&sql).context("DuckDB query failed")?;
+            let mut rows = stmt.query([]).context("DuckDB query failed")?;
+            let mut result = QueryResult {
+                columns: rows
+                    .as_ref()
+                    .map(|stmt| stmt.column_names())
+                    .unwrap_or_default(),
+                ..Default::default()
+            };
+
+            let column_count = result.columns.len();
+            while let Some(row) = rows.next()? {
+                if result.rows.len() >= max_rows {
+                    result.truncated = true;
+                    break;
+                }
+                let values = (0..column_count)
+                    .map(|i| row.get::<_, DuckValue>(i).map(json_value))
+                    .collect::<duckdb::Result<Vec<_>>>()?;
+                result.rows.push(values);
+            }
+            Ok(result)
+        })
+        .await
+    }
+}
+
+const MAIN_SCHEMA: &str = "main";
+
+fn json_value(value: DuckValue) -> Value {
+    match value {
+        DuckValue::Null => Value::Null,
+        DuckValue::Boolean(b) => Value::from(b),
+        DuckValue::TinyInt(i) => Value::from(i),
+        DuckValue::SmallInt(i) => Value::from(i),
+        DuckValue::Int(i) => Value::from(i),
+        DuckValue::BigInt(i) => Value::from(i),
+        DuckValue::HugeInt(i) => Value::from(i.to_string()),
+        DuckValue::UTinyInt(i) => Value::from(i),
+        DuckValue::USmallInt(i) => Value::from(i),
+        DuckValue::UInt(i) => Value::from(i),
+        DuckValue::UBigInt(i) => Value::from(i),
+        DuckValue::Float(f) => Value::from(f),
+        DuckValue::Double(f) => Value::from(f),
+        DuckValue::Decimal(d) => Value::from(d.to_string()),
+        DuckValue::Text(s) | DuckValue::Enum(s) => Value::from(s),
+        DuckValue::Blob(b) => Value::from(format!("<{} bytes>", b.len())),
+        DuckValue::Timestamp(unit, v) => {
+            let micros = match unit {
+                TimeUnit::Second => v * 1_000_000,
+                TimeUnit::Millisecond => v * 1_000,
+                TimeUnit::Microsecond => v,
+                TimeUnit::Nanosecond => v / 1_000,
+            };
+            chrono::DateTime::from_timestamp_micros(micros)
+                .map(|t| json!(t.naive_utc()))
+                .unwrap_or(Value::Null)
+        }
+        DuckValue::Date32(days) => chrono::NaiveDate::from_ymd_opt(1970, 1, 1)
+            .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::days(days.into())))
+            .map(|d| json!(d))
+            .unwrap_or(Value::Null),
+        DuckValue::List(values) | DuckValue::Array(values) => {
+            Value::Array(values.into_iter().map(json_value).collect())
+        }
+        other => Value::from(format!("{:?}", other)),
+    }
+}
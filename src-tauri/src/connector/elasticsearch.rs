@@ -0,0 +1,501 @@
+use crate::connection::proxy::ProxyManager;
+use crate::connection::DataSourceKind;
+use crate::connector::{Capability, Connector, QueryResult, SQL_CAPABILITIES};
+use crate::db::DataSource;
+use crate::metadata::{ColumnInfo, TableInfo};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{Client as HttpClient, Method, Url};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// Works with Elasticsearch and OpenSearch alike; both share the REST API used here.
+/// Indices are tables and their mappings the columns.
+pub struct ElasticsearchConnector;
+
+struct ElasticsearchClient {
+    client: HttpClient,
+    url: Url,
+    username: String,
+    password: String,
+}
+
+impl ElasticsearchClient {
+    fn new(data_source: &DataSource) -> Result<Self> {
+        let options = data_source.elasticsearch_options();
+        let scheme = if options.secure.unwrap_or(false) {
+            "https"
+        } else {
+            "http"
+        };
+        let url = Url::parse(&format!(
+            "{}://{}:{}/",
+            scheme, data_source.host, data_source.port
+        ))
+        .context("Invalid Elasticsearch host")?;
+
+        let mut builder = HttpClient::builder()
+            .timeout(Duration::from_secs(30))
+            .danger_accept_invalid_certs(options.insecure_skip_verify.unwrap_or(false));
+        if let Some(proxy) = ProxyManager::http_client_proxy(data_source)? {
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(ElasticsearchClient {
+            client: builder
+                .build()
+                .context("Failed to create Elasticsearch HTTP client")?,
+            url,
+            username: data_source.username.clone(),
+            password: data_source.password.clone(),
+        })
+    }
+
+    /// Sends a request to `path`, relative to the cluster URL, and returns the JSON
+    /// response. A password without a username is used as an API key.
+    async fn request(&self, method: Method, path: &str, body: Option<&Value>) -> Result<Value> {
+        let url = self
+            .url
+            .join(path.trim_start_matches('/'))
+            .context("Invalid Elasticsearch path")?;
+        let mut request = self.client.request(method, url);
+        if !self.username.is_empty() {
+            request = request.basic_auth(&self.username, Some(&self.password));
+        } else if !self.password.is_empty() {
+            request = request.header("Authorization", format!("ApiKey {}", self.password));
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context("Failed to reach Elasticsearch")?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .context("Failed to read Elasticsearch response")?;
+        if !status.is_success() {
+            return Err(anyhow::anyhow!(
+                "Elasticsearch returned {}: {}",
+                status,
+                error_reason(&body)
+            ));
+        }
+        serde_json::from_str(&body).context("Unexpected Elasticsearch response")
+    }
+
+    async fn get(&self, path: &str) -> Result<Value> {
+        self.request(Method::GET, path, None).await
+    }
+
+    /// Indices matching `target` (an index, alias or pattern) with their mappings, aliases,
+    /// document counts and shard health.
+    async fn indices(&self, target: &str, include_hidden: bool) -> Result<Vec<TableInfo>> {
+        let target = encode(target);
+        let expand = if include_hidden { "all" } else { "open" };
+        let indices = self
+            .get(&format!(
+                "_cat/indices/{}?format=json&h=index,health,status,docs.count,pri,rep,store.size&expand_wildcards={}",
+                target, expand
+            ))
+            .await?;
+        let mappings = self
+            .get(&format!("{}/_mapping?expand_wildcards={}", target, expand))
+            .await?;
+        let aliases = self
+            .get(&format!("{}/_alias?expand_wildcards={}", target, expand))
+            .await?;
+        let health = self
+            .get(&format!(
+                "_cluster/health/{}?level=indices&expand_wildcards={}",
+                target, expand
+            ))
+            .await?;
+
+        let mut tables: Vec<TableInfo> = indices
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| {
+                let name = row["index"].as_str()?.to_string();
+                if !include_hidden && name.starts_with('.') {
+                    return None;
+                }
+
+                let mut properties = BTreeMap::new();
+                for (key, column) in [
+                    ("health", "health"),
+                    ("status", "status"),
+                    ("primary_shards", "pri"),
+                    ("replicas", "rep"),
+                    ("store_size", "store.size"),
+                ] {
+                    if let Some(value) = row[column].as_str() {
+                        properties.insert(key.to_string(), value.to_string());
+                    }
+                }
+                let index_health = &health["indices"][&name];
+                for key in [
+                    "active_shards",
+                    "relocating_shards",
+                    "initializing_shards",
+                    "unassigned_shards",
+                ] {
+                    if let Some(value) = index_health[key].as_u64() {
+                        properties.insert(key.to_string(), value.to_string());
+                    }
+                }
+                if let Some(index_aliases) = aliases[&name]["aliases"].as_object() {
+                    if !index_aliases.is_empty() {
+                        let names: Vec<&str> = index_aliases.keys().map(String::as_str).collect();
+                        properties.insert("aliases".to_string(), names.join(", "));
+                    }
+                }
+
+                let mut columns = Vec::new();
+                mapping_columns("", &mappings[&name]["mappings"], &mut columns);
+
+                Some(TableInfo {
+                    row_count: row["docs.count"].as_str().and_then(|c| c.parse().ok()),
+                    name,
+                    schema: None,
+                    columns,
+                    properties,
+                })
+            })
+            .collect();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tables)
+    }
+}
+
+#[async_trait]
+impl Connector for ElasticsearchConnector {
+    fn kind(&self) -> DataSourceKind {
+        DataSourceKind::Elasticsearch
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        SQL_CAPABILITIES
+    }
+
+    async fn test_connection(&self, data_source: &DataSource) -> Result<()> {
+        ElasticsearchClient::new(data_source)?
+            .get("")
+            .await
+            .context("Failed to connect to Elasticsearch")?;
+        Ok(())
+    }
+
+    async fn get_tables(&self, data_source: &DataSource) -> Result<Vec<TableInfo>> {
+        let include_hidden = data_source
+            .elasticsearch_options()
+            .include_hidden
+            .unwrap_or(false);
+        ElasticsearchClient::new(data_source)?
+            .indices("*", include_hidden)
+            .await
+    }
+
+    /// `table_name` may also be an alias; its first index is described then.
+    async fn get_table_structure(
+        &self,
+        data_source: &DataSource,
+        _schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableInfo> {
+        ElasticsearchClient::new(data_source)?
+            .indices(table_name, true)
+            .await?
+            .into_iter()
+            .next()
+            .context(format!("Index {} not found", table_name))
+    }
+
+    async fn get_table_row_count(
+        &self,
+        data_source: &DataSource,
+        _schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<i64> {
+        let response = ElasticsearchClient::new(data_source)?
+            .get(&format!("{}/_count", encode(table_name)))
+            .await?;
+        response["count"]
+            .as_i64()
+            .context("Elasticsearch returned no document count")
+    }
+
+    /// `sql` is a request in the Dev Tools console format: a `METHOD /path` line, then
+    /// an optional JSON body, e.g. `GET /logs-*/_search` and a query DSL body. A bare
+    /// body is sent to `POST /_search`. Search hits come back one row per document.
+    async fn execute_query(
+        &self,
+        data_source: &DataSource,
+        sql: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult> {
+        let sql = sql.trim();
+        let (request_line, body) = match sql.split_once('\n') {
+            _ if sql.starts_with('{') => ("POST /_search", sql),
+            Some((line, body)) => (line.trim(), body.trim()),
+            None => (sql, ""),
+        };
+        let (method, path) = request_line
+            .split_once(char::is_whitespace)
+            .context("Expected a request line such as GET /index/_search")?;
+        let method = Method::from_bytes(method.to_uppercase().as_bytes())
+            .context(format!("Invalid HTTP method {}", method))?;
+        let body: Option<Value> = if body.is_empty() {
+            None
+        } else {
+            Some(serde_json::from_str(body).context("The request body must be JSON")?)
+        };
+
+        let response = ElasticsearchClient::new(data_source)?
+            .request(method, path.trim(), body.as_ref())
+            .await?;
+
+        let Some(hits) = response["hits"]["hits"].as_array() else {
+            // Anything but a search answers with a single document
+            let row = response.as_object().cloned().unwrap_or_default();
+            return Ok(QueryResult {
+                columns: row.keys().cloned().collect(),
+                rows: vec![row.into_iter().map(|(_, v)| v).collect()],
+                ..Default::default()
+            });
+        };
+
+        let total = response["hits"]["total"]
+            .as_u64()
+            .or_else(|| response["hits"]["total"]["value"].as_u64())
+            .unwrap_or(hits.len() as u64);
+        let mut result = hit_rows(&hits[..hits.len().min(max_rows)]);
+        result.truncated = hits.len() > max_rows || total > result.rows.len() as u64;
+        Ok(result)
+    }
+}
+
+/// Fields of a mapping with dotted paths. Objects and nested fields are listed
+/// themselves and then their sub-fields; multi-fields appear as `field.keyword`.
+fn mapping_columns(prefix: &str, mapping: &Value, columns: &mut Vec<ColumnInfo>) {
+    let Some(fields) = mapping["properties"].as_object() else {
+        return;
+    };
+    for (name, field) in fields {
+        let path = format!("{}{}", prefix, name);
+        let data_type = field["type"].as_str().unwrap_or(
+            // Objects leave the type out
+            if field.get("properties").is_some() {
+                "object"
+            } else {
+                "unknown"
+            },
+        );
+
+        let mut properties = BTreeMap::new();
+        for key in [
+            "analyzer",
+            "format",
+            "index",
+            "doc_values",
+            "dynamic",
+            "path",
+        ] {
+            match &field[key] {
+                Value::Null => {}
+                Value::String(value) => {
+                    properties.insert(key.to_string(), value.clone());
+                }
+                value => {
+                    properties.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+
+        columns.push(ColumnInfo {
+            name: path.clone(),
+            data_type: data_type.to_string(),
+            is_nullable: true,
+            default_value: match &field["null_value"] {
+                Value::Null => None,
+                Value::String(value) => Some(value.clone()),
+                value => Some(value.to_string()),
+            },
+            constraints: Vec::new(),
+            properties,
+        });
+
+        if let Some(multi_fields) = field["fields"].as_object() {
+            let multi = Value::Object(
+                [(
+                    "properties".to_string(),
+                    Value::Object(multi_fields.clone()),
+                )]
+                .into_iter()
+                .collect(),
+            );
+            mapping_columns(&format!("{}.", path), &multi, columns);
+        }
+        mapping_columns(&format!("{}.", path), field, columns);
+    }
+}
+
+/// One row per hit with `_index`, `_id` and `_score` followed by the top-level
+/// `_source` fields, in the order they were first seen.
+fn hit_rows(hits: &[Value]) -> QueryResult {
+    let mut columns: Vec<String> = vec!["_index".into(), "_id".into(), "_score".into()];
+    let mut index: HashMap<String, usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (c.clone(), i))
+        .collect();
+    let empty = Map::new();
+    for hit in hits {
+        for key in hit["_source"].as_object().unwrap_or(&empty).keys() {
+            if !index.contains_key(key) {
+                index.insert(key.clone(), columns.len());
+                columns.push(key.clone());
+            }
+        }
+    }
+
+    let rows = hits
+        .iter()
+        .map(|hit| {
+            let mut row = vec![Value::Null; columns.len()];
+            row[0] = hit["_index"].clone();
+            row[1] = hit["_id"].clone();
+            row[2] = hit["_score"].clone();
+            for (key, value) in hit["_source"].as_object().unwrap_or(&empty) {
+                row[index[key]] = value.clone();
+            }
+            row
+        })
+        .collect();
+    QueryResult {
+        columns,
+        rows,
+        ..Default::default()
+    }
+}
+
+/// The root cause of an error response, or the body as is.
+fn error_reason(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|error| error["error"]["reason"].as_str().map(String::from))
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+/// Escapes an index name or pattern for use as a path segment, keeping the `*`
+/// wildcards and `,` separators of multi-target syntax.
+fn encode(target: &str) -> String {
+    target
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'*' | b',' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn mapping_paths_and_types() {
+        let mapping = json!({
+            "properties": {
+                "address": {
+                    "properties": {
+                        "city": { "type": "keyword", "null_value": "unknown" }
+                    }
+                },
+                "created": { "type": "date", "format": "strict_date_optional_time" },
+                "title": {
+                    "type": "text",
+                    "analyzer": "english",
+                    "fields": { "keyword": { "type": "keyword", "index": false } }
+                }
+            }
+        });
+
+        let mut columns = Vec::new();
+        mapping_columns("", &mapping, &mut columns);
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "address",
+                "address.city",
+                "created",
+                "title",
+                "title.keyword"
+            ]
+        );
+
+        let column = |name: &str| columns.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(column("address").data_type, "object");
+        assert_eq!(
+            column("address.city").default_value.as_deref(),
+            Some("unknown")
+        );
+        assert_eq!(
+            column("created").properties["format"],
+            "strict_date_optional_time"
+        );
+        assert_eq!(column("title").properties["analyzer"], "english");
+        assert_eq!(column("title.keyword").data_type, "keyword");
+        assert_eq!(column("title.keyword").properties["index"], "false");
+    }
+
+    #[test]
+    fn hits_become_rows() {
+        let hits = vec![
+            json!({ "_index": "logs", "_id": "1", "_score": 1.5, "_source": { "level": "warn" } }),
+            json!({ "_index": "logs", "_id": "2", "_score": null, "_source": { "msg": "hi", "level": "info" } }),
+        ];
+        let result = hit_rows(&hits);
+        assert_eq!(result.columns, ["_index", "_id", "_score", "level", "msg"]);
+        assert_eq!(
+            result.rows[0],
+            [
+                json!("logs"),
+                json!("1"),
+                json!(1.5),
+                json!("warn"),
+                Value::Null
+            ]
+        );
+        assert_eq!(result.rows[1][3], json!("info"));
+        assert_eq!(result.rows[1][4], json!("hi"));
+    }
+
+    #[test]
+    fn error_reasons() {
+        assert_eq!(
+            error_reason(
+                r#"{"error":{"root_cause":[],"reason":"no such index [x]"},"status":404}"#
+            ),
+            "no such index [x]"
+        );
+        assert_eq!(error_reason("  Bad Gateway\n"), "Bad Gateway");
+    }
+
+    #[test]
+    fn path_segments() {
+        assert_eq!(encode("logs-2024.*,metrics"), "logs-2024.*,metrics");
+        assert_eq!(encode("a b/c?"), "a%20b%2Fc%3F");
+        assert_eq!(encode("é"), "%C3%A9");
+    }
+}
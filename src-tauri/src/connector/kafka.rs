@@ -0,0 +1,25 @@
+use crate::connection::kafka::KafkaConnector;
+use crate::connection::DataSourceKind;
+use crate::connector::{Capability, Connector};
+use crate::db::DataSource;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+/// Topics and consumer groups are Kafka specific and stay on `MetadataFetcher`.
+#[async_trait]
+impl Connector for KafkaConnector {
+    fn kind(&self) -> DataSourceKind {
+        DataSourceKind::Kafka
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        &[Capability::TestConnection, Capability::Topics]
+    }
+
+    async fn test_connection(&self, data_source: &DataSource) -> Result<()> {
+        Self::create_admin_client(data_source)
+            .await
+            .context("Failed to create Kafka admin client")?;
+        Ok(())
+    }
+}
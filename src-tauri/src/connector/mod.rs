@@ -0,0 +1,313 @@
+#[cfg(feature = "kafka")]
+use crate::connection::kafka::KafkaConnector;
+use crate::connection::mysql::MySQLConnector;
+use crate::connection::postgresql::PostgreSQLConnector;
+use crate::connection::sqlserver::SQLServerConnector;
+use crate::connection::{DataSourceKind, ServerDetails, TlsInfo};
+use crate::db::DataSource;
+use crate::metadata::{PostgresObjects, RedisInfoSection, RedisKeyPage, RedisValue, TableInfo};
+use anyhow::Result;
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+pub mod clickhouse;
+#[cfg(feature = "duckdb")]
+pub mod duckdb;
+pub mod elasticsearch;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod mongodb;
+pub mod mysql;
+pub mod postgresql;
+pub mod redis;
+pub mod sqlite;
+pub mod sqlserver;
+
+/// Operations a connector may support; the frontend uses these to hide what an engine
+/// cannot do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    TestConnection,
+    Tables,
+    TableStructure,
+    RowCount,
+    Query,
+    Topics,
+    Keys,
+    ServerInfo,
+    SchemaObjects,
+    Schemas,
+    Databases,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Capability::TestConnection => "connection tests",
+            Capability::Tables => "table listing",
+            Capability::TableStructure => "table structure",
+            Capability::RowCount => "row counts",
+            Capability::Query => "query execution",
+            Capability::Topics => "topic listing",
+            Capability::Keys => "key browsing",
+            Capability::ServerInfo => "server info",
+            Capability::SchemaObjects => "schema objects",
+            Capability::Schemas => "schema listing",
+            Capability::Databases => "database listing",
+        })
+    }
+}
+
+/// Capabilities shared by the relational engines.
+pub const SQL_CAPABILITIES: &[Capability] = &[
+    Capability::TestConnection,
+    Capability::Tables,
+    Capability::TableStructure,
+    Capability::RowCount,
+    Capability::Query,
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    pub rows_affected: Option<u64>,
+    pub truncated: bool, // More rows were returned than max_rows
+}
+
+/// What an established connection reports about itself. Each part carries its own
+/// error: the connection works even when a detail cannot be read.
+pub struct SessionInfo {
+    pub tls: Result<Option<TlsInfo>>,
+    pub server: Result<Option<ServerDetails>>,
+}
+
+impl Default for SessionInfo {
+    fn default() -> Self {
+        Self {
+            tls: Ok(None),
+            server: Ok(None),
+        }
+    }
+}
+
+/// One database engine. Everything but `test_connection` is optional; the defaults
+/// report the capability as unsupported for the engine.
+#[async_trait]
+pub trait Connector: Send + Sync {
+    fn kind(&self) -> DataSourceKind;
+
+    fn capabilities(&self) -> &'static [Capability];
+
+    async fn test_connection(&self, data_source: &DataSource) -> Result<()>;
+
+    /// Connects like `test_connection` and, over that same connection, reads how it
+    /// is encrypted and the server version, user and database as the server sees
+    /// them. Fails only when connecting does; `None` parts are unknown for the engine.
+    async fn inspect_connection(&self, data_source: &DataSource) -> Result<SessionInfo> {
+        self.test_connection(data_source).await?;
+        Ok(SessionInfo::default())
+    }
+
+    async fn get_tables(&self, _data_source: &DataSource) -> Result<Vec<TableInfo>> {
+        Err(self.unsupported(Capability::Tables))
+    }
+
+    async fn get_table_structure(
+        &self,
+        _data_source: &DataSource,
+        _schema: Option<&str>,
+        _table_name: &str,
+    ) -> Result<TableInfo> {
+        Err(self.unsupported(Capability::TableStructure))
+    }
+
+    async fn get_table_row_count(
+        &self,
+        _data_source: &DataSource,
+        _schema: Option<&str>,
+        _table_name: &str,
+    ) -> Result<i64> {
+        Err(self.unsupported(Capability::RowCount))
+    }
+
+    async fn execute_query(
+        &self,
+        _data_source: &DataSource,
+        _sql: &str,
+        _max_rows: usize,
+    ) -> Result<QueryResult> {
+        Err(self.unsupported(Capability::Query))
+    }
+
+    async fn list_databases(&self, _data_source: &DataSource) -> Result<Vec<String>> {
+        Err(self.unsupported(Capability::Databases))
+    }
+
+    /// Schemas of `database`, or of the database the data source connects to.
+    async fn list_schemas(
+        &self,
+        _data_source: &DataSource,
+        _database: Option<&str>,
+    ) -> Result<Vec<String>> {
+        Err(self.unsupported(Capability::Schemas))
+    }
+
+    /// One step of browsing keys from `cursor`; pass the returned cursor back until it is 0.
+    async fn scan_keys(
+        &self,
+        _data_source: &DataSource,
+        _pattern: Option<&str>,
+        _cursor: u64,
+    ) -> Result<RedisKeyPage> {
+        Err(self.unsupported(Capability::Keys))
+    }
+
+    /// The value of `key`; collections are cut off after `limit` entries.
+    async fn get_key_value(
+        &self,
+        _data_source: &DataSource,
+        _key: &str,
+        _limit: usize,
+    ) -> Result<RedisValue> {
+        Err(self.unsupported(Capability::Keys))
+    }
+
+    async fn get_server_info(&self, _data_source: &DataSource) -> Result<Vec<RedisInfoSection>> {
+        Err(self.unsupported(Capability::ServerInfo))
+    }
+
+    /// Types, sequences and extensions, limited to `schema` where they belong to one.
+    async fn get_schema_objects(
+        &self,
+        _data_source: &DataSource,
+        _schema: Option<&str>,
+    ) -> Result<PostgresObjects> {
+        Err(self.unsupported(Capability::SchemaObjects))
+    }
+
+    fn supports(&self, capability: Capability) -> bool {
+        self.capabilities().contains(&capability)
+    }
+
+    fn unsupported(&self, capability: Capability) -> anyhow::Error {
+        anyhow::anyhow!("{} data sources do not support {}", self.kind(), capability)
+    }
+}
+
+/// Connectors keyed by the data source kind they handle.
+pub struct ConnectorRegistry {
+    connectors: HashMap<DataSourceKind, Arc<dyn Connector>>,
+}
+
+static REGISTRY: Lazy<ConnectorRegistry> = Lazy::new(ConnectorRegistry::builtin);
+
+impl ConnectorRegistry {
+    pub fn new() -> Self {
+        Self {
+            connectors: HashMap::new(),
+        }
+    }
+
+    /// All engines compiled into this build.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(Arc::new(MySQLConnector));
+        registry.register(Arc::new(PostgreSQLConnector));
+        registry.register(Arc::new(SQLServerConnector));
+        #[cfg(feature = "kafka")]
+        registry.register(Arc::new(KafkaConnector));
+        registry.register(Arc::new(sqlite::SqliteConnector));
+        registry.register(Arc::new(clickhouse::ClickHouseConnector));
+        #[cfg(feature = "duckdb")]
+        registry.register(Arc::new(duckdb::DuckDbConnector));
+        registry.register(Arc::new(mongodb::MongoDbConnector));
+        registry.register(Arc::new(redis::RedisConnector));
+        registry.register(Arc::new(elasticsearch::ElasticsearchConnector));
+        registry
+    }
+
+    pub fn global() -> &'static ConnectorRegistry {
+        &REGISTRY
+    }
+
+    /// Adds a connector; it replaces any existing one for the same kind.
+    pub fn register(&mut self, connector: Arc<dyn Connector>) {
+        self.connectors.insert(connector.kind(), connector);
+    }
+
+    pub fn get(&self, kind: DataSourceKind) -> Result<Arc<dyn Connector>> {
+        match self.connectors.get(&kind) {
+            Some(connector) => Ok(connector.clone()),
+            None => match kind {
+                DataSourceKind::Kafka => Err(anyhow::anyhow!(
+                    "Kafka support is not enabled. Build with --features kafka"
+                )),
+                DataSourceKind::Duckdb => Err(anyhow::anyhow!(
+                    "DuckDB support is not enabled. Build with --features duckdb"
+                )),
+                _ => Err(anyhow::anyhow!(
+                    "No connector is available for {} data sources",
+                    kind
+                )),
+            },
+        }
+    }
+
+    /// Empty for kinds without a connector in this build.
+    pub fn capabilities(&self, kind: DataSourceKind) -> Vec<Capability> {
+        self.connectors
+            .get(&kind)
+            .map(|c| c.capabilities().to_vec())
+            .unwrap_or_default()
+    }
+}
+
+impl Default for ConnectorRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The connector for a data source, checking it supports `capability` up front.
+pub fn connector_for(
+    data_source: &DataSource,
+    capability: Capability,
+) -> Result<Arc<dyn Connector>> {
+    let connector = ConnectorRegistry::global().get(data_source.data_type)?;
+    if !connector.supports(capability) {
+        return Err(connector.unsupported(capability));
+    }
+    Ok(connector)
+}
+
+/// Converts the first of the listed types `row` can decode at `index` into JSON, for
+/// engines driven through sqlx where value types are only known at runtime. Other types
+/// (decimals, intervals, ...) fall back to their text form, which `raw_sql` results use.
+macro_rules! sqlx_json_value {
+    ($row:expr, $index:expr, [$($ty:ty),* $(,)?]) => {{
+        use sqlx::Row as _;
+        let mut value = None;
+        $(
+            if value.is_none() {
+                if let Ok(v) = $row.try_get::<Option<$ty>, _>($index) {
+                    value = Some(v.map(|v| serde_json::json!(v)).unwrap_or(serde_json::Value::Null));
+                }
+            }
+        )*
+        value.unwrap_or_else(|| match $row.try_get_unchecked::<Option<String>, _>($index) {
+            Ok(text) => text.map(serde_json::Value::String).unwrap_or(serde_json::Value::Null),
+            Err(_) => serde_json::Value::String(format!(
+                "<{}>",
+                sqlx::Column::type_info(&$row.columns()[$index])
+            )),
+        })
+    }};
+}
+pub(crate) use sqlx_json_value;
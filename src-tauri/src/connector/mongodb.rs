@@ -0,0 +1,567 @@
+use crate::connection::{DataSourceKind, ProxyType};
+use crate::connector::{Capability, Connector, QueryResult};
+use crate::db::DataSource;
+use crate::metadata::{ColumnInfo, TableInfo};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::ClientOptions;
+use mongodb::{Client, Database};
+use reqwest::Url;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+const DEFAULT_SAMPLE_SIZE: u32 = 100;
+// Databases every deployment has; they are only listed when configured explicitly
+const SYSTEM_DATABASES: [&str; 3] = ["admin", "config", "local"];
+
+/// Collections have no schema, so their fields are inferred from a sample of documents.
+pub struct MongoDbConnector;
+
+const CAPABILITIES: &[Capability] = &[
+    Capability::TestConnection,
+    Capability::Tables,
+    Capability::TableStructure,
+    Capability::RowCount,
+    Capability::Query,
+];
+
+impl MongoDbConnector {
+    async fn client(data_source: &DataSource) -> Result<Client> {
+        let options = ClientOptions::parse(Self::connection_string(data_source)?)
+            .await
+            .context("Invalid MongoDB connection settings")?;
+        Client::with_options(options).context("Failed to create MongoDB client")
+    }
+
+    fn connection_string(data_source: &DataSource) -> Result<String> {
+        let options = data_source.mongodb_options();
+        let srv = options.srv.unwrap_or(false);
+        let mut url = if srv {
+            Url::parse(&format!("mongodb+srv://{}/", data_source.host))
+        } else {
+            Url::parse(&format!(
+                "mongodb://{}:{}/",
+                data_source.host, data_source.port
+            ))
+        }
+        .context("Invalid MongoDB host")?;
+
+        if !data_source.username.is_empty() {
+            url.set_username(&data_source.username)
+                .and_then(|_| url.set_password(Some(&data_source.password)))
+                .map_err(|_| anyhow::anyhow!("Invalid MongoDB credentials"))?;
+        }
+
+        {
+            let mut pairs = url.query_pairs_mut();
+            pairs.append_pair("serverSelectionTimeoutMS", "10000");
+            if let Some(auth_source) = &options.auth_source {
+                pairs.append_pair("authSource", auth_source);
+            }
+            if let Some(replica_set) = &options.replica_set {
+                pairs.append_pair("replicaSet", replica_set);
+            }
+            if let Some(tls) = options.tls {
+                pairs.append_pair("tls", &tls.to_string());
+            }
+            if let Some(direct_connection) = options.direct_connection {
+                pairs.append_pair("directConnection", &direct_connection.to_string());
+            }
+
+            // The driver speaks SOCKS5 itself; other proxies would need a tunnel
+            match (&data_source.proxy_type, &data_source.proxy_config) {
+                (None, _) => {}
+                (Some(ProxyType::Socks5), Some(proxy)) => {
+                    pairs
+                        .append_pair("proxyHost", &proxy.host)
+                        .append_pair("proxyPort", &proxy.port.to_string());
+                    if let (Some(username), Some(password)) = (&proxy.username, &proxy.password) {
+                        pairs
+                            .append_pair("proxyUsername", username)
+                            .append_pair("proxyPassword", password);
+                    }
+                }
+                (Some(ProxyType::Socks5), None) => {
+                    return Err(anyhow::anyhow!("Proxy config is missing"));
+                }
+                (Some(proxy_type), _) => {
+                    return Err(anyhow::anyhow!(
+                        "MongoDB data sources do not support {} proxies, only socks5",
+                        proxy_type
+                    ));
+                }
+            }
+        }
+        Ok(url.to_string())
+    }
+
+    fn database(
+        client: &Client,
+        data_source: &DataSource,
+        schema: Option<&str>,
+    ) -> Result<Database> {
+        schema
+            .or(data_source.database.as_deref())
+            .filter(|name| !name.is_empty())
+            .map(|name| client.database(name))
+            .context("No MongoDB database configured")
+    }
+
+    /// The configured database, or every non-system database of the deployment.
+    async fn databases(client: &Client, data_source: &DataSource) -> Result<Vec<Database>> {
+        if let Some(name) = data_source.database.as_deref().filter(|d| !d.is_empty()) {
+            return Ok(vec![client.database(name)]);
+        }
+        let names = client
+            .list_database_names()
+            .await
+            .context("Failed to list MongoDB databases")?;
+        Ok(names
+            .iter()
+            .filter(|name| !SYSTEM_DATABASES.contains(&name.as_str()))
+            .map(|name| client.database(name))
+            .collect())
+    }
+
+    async fn collection_info(
+        database: &Database,
+        name: &str,
+        sample_size: u32,
+        row_count: Option<i64>,
+    ) -> Result<TableInfo> {
+        let collection = database.collection::<Document>(name);
+        let mut properties = BTreeMap::new();
+        let mut unique = HashSet::new();
+        let mut indexes = collection.list_indexes().await.context(format!(
+            "Failed to list indexes of {}.{}",
+            database.name(),
+            name
+        ))?;
+        while let Some(index) = indexes.try_next().await? {
+            let options = index.options.unwrap_or_default();
+            let is_unique = options.unique.unwrap_or(false);
+            if is_unique && index.keys.len() == 1 {
+                unique.extend(index.keys.keys().cloned());
+            }
+            let mut keys = Bson::Document(index.keys)
+                .into_relaxed_extjson()
+                .to_string();
+            if is_unique {
+                keys.push_str(" unique");
+            }
+            properties.insert(format!("index.{}", options.name.unwrap_or_default()), keys);
+        }
+
+        let documents: Vec<Document> = collection
+            .aggregate([doc! { "$sample": { "size": sample_size as i64 } }])
+            .await
+            .context(format!("Failed to sample {}.{}", database.name(), name))?
+            .try_collect()
+            .await?;
+        properties.insert("sampled_documents".to_string(), documents.len().to_string());
+
+        let mut schema = SampledSchema::default();
+        for document in &documents {
+            schema.add(document);
+        }
+
+        Ok(TableInfo {
+            name: name.to_string(),
+            schema: Some(database.name().to_string()),
+            row_count,
+            columns: schema.columns(documents.len(), &unique),
+            properties,
+        })
+    }
+}
+
+#[async_trait]
+impl Connector for MongoDbConnector {
+    fn kind(&self) -> DataSourceKind {
+        DataSourceKind::Mongodb
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        CAPABILITIES
+    }
+
+    async fn test_connection(&self, data_source: &DataSource) -> Result<()> {
+        Self::client(data_source)
+            .await?
+            .database("admin")
+            .run_command(doc! { "ping": 1 })
+            .await
+            .context("Failed to connect to MongoDB")?;
+        Ok(())
+    }
+
+    async fn get_tables(&self, data_source: &DataSource) -> Result<Vec<TableInfo>> {
+        let client = Self::client(data_source).await?;
+        let sample_size = data_source
+            .mongodb_options()
+            .sample_size
+            .unwrap_or(DEFAULT_SAMPLE_SIZE);
+
+        let mut tables = Vec::new();
+        for database in Self::databases(&client, data_source).await? {
+            let mut names = database
+                .list_collection_names()
+                .await
+                .context(format!("Failed to list collections of {}", database.name()))?;
+            names.retain(|name| !name.starts_with("system."));
+            names.sort();
+
+            for name in names {
+                // Views have no count of their own
+                let row_count = database
+                    .collection::<Document>(&name)
+                    .estimated_document_count()
+                    .await
+                    .ok()
+                    .map(|count| count as i64);
+                tables.push(Self::collection_info(&database, &name, sample_size, row_count).await?);
+            }
+        }
+        Ok(tables)
+    }
+
+    async fn get_table_structure(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableInfo> {
+        let client = Self::client(data_source).await?;
+        let database = Self::database(&client, data_source, schema)?;
+        let found = database
+            .list_collection_names()
+            .filter(doc! { "name": table_name })
+            .await?;
+        if found.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Collection {}.{} not found",
+                database.name(),
+                table_name
+            ));
+        }
+
+        let sample_size = data_source
+            .mongodb_options()
+            .sample_size
+            .unwrap_or(DEFAULT_SAMPLE_SIZE);
+        Self::collection_info(&database, table_name, sample_size, None).await
+    }
+
+    async fn get_table_row_count(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<i64> {
+        let client = Self::client(data_source).await?;
+        let count = Self::database(&client, data_source, schema)?
+            .collection::<Document>(table_name)
+            .count_documents(doc! {})
+            .await
+            .context("Failed to count MongoDB documents")?;
+        Ok(count as i64)
+    }
+
+    /// `sql` is a database command as extended JSON, e.g. `{"find": "users", "filter": {}}`.
+    /// A `$db` field picks the database, otherwise the configured one is used. Only the
+    /// first batch of a cursor is read; the cursor is then closed.
+    async fn execute_query(
+        &self,
+        data_source: &DataSource,
+        sql: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult> {
+        let command: serde_json::Map<String, Value> =
+            serde_json::from_str(sql).context("A MongoDB query must be a JSON command document")?;
+        let mut command =
+            Document::try_from(command).context("Invalid extended JSON in MongoDB command")?;
+        let database_name = match command.remove("$db") {
+            Some(Bson::String(name)) => Some(name),
+            Some(_) => return Err(anyhow::anyhow!("$db must be a string")),
+            None => None,
+        };
+
+        let client = Self::client(data_source).await?;
+        let database = Self::database(&client, data_source, database_name.as_deref())?;
+        let mut response = database
+            .run_command(command)
+            .await
+            .context("MongoDB command failed")?;
+
+        let mut cursor = match response.remove("cursor") {
+            Some(Bson::Document(cursor)) => cursor,
+            _ => {
+                // Commands without a cursor answer with a single document
+                let rows_affected = response.get("n").and_then(|n| match n {
+                    Bson::Int32(n) => Some(*n as u64),
+                    Bson::Int64(n) => Some(*n as u64),
+                    _ => None,
+                });
+                let mut result = document_rows(vec![response]);
+                result.rows_affected = rows_affected;
+                return Ok(result);
+            }
+        };
+
+        let batch = match cursor.remove("firstBatch") {
+            Some(Bson::Array(batch)) => batch,
+            _ => Vec::new(),
+        };
+        let cursor_id = cursor.get_i64("id").unwrap_or(0);
+        if cursor_id != 0 {
+            if let Some((_, collection)) =
+                cursor.get_str("ns").ok().and_then(|ns| ns.split_once('.'))
+            {
+                // Best effort; the server would otherwise keep it open until it times out
+                let _ = database
+                    .run_command(doc! { "killCursors": collection, "cursors": [cursor_id] })
+                    .await;
+            }
+        }
+
+        let truncated = batch.len() > max_rows || cursor_id != 0;
+        let documents = batch
+            .into_iter()
+            .take(max_rows)
+            .map(|value| match value {
+                Bson::Document(document) => document,
+                other => doc! { "value": other },
+            })
+            .collect();
+        let mut result = document_rows(documents);
+        result.truncated = truncated;
+        Ok(result)
+    }
+}
+
+/// One column per top-level field, in the order fields were first seen.
+fn document_rows(documents: Vec<Document>) -> QueryResult {
+    let mut columns: Vec<String> = Vec::new();
+    for document in &documents {
+        for key in document.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+
+    let rows = documents
+        .into_iter()
+        .map(|mut document| {
+            columns
+                .iter()
+                .map(|column| {
+                    document
+                        .remove(column)
+                        .map(Bson::into_relaxed_extjson)
+                        .unwrap_or(Value::Null)
+                })
+                .collect()
+        })
+        .collect();
+    QueryResult {
+        columns,
+        rows,
+        ..Default::default()
+    }
+}
+
+#[derive(Default)]
+struct FieldStats {
+    documents: usize,
+    types: BTreeSet<&'static str>,
+}
+
+/// Field paths seen in sampled documents. Nested fields are `a.b` and array elements
+/// `a[]`, so `tags[]` holds the types of the elements of `tags`.
+#[derive(Default)]
+struct SampledSchema {
+    order: Vec<String>,
+    fields: HashMap<String, FieldStats>,
+}
+
+impl SampledSchema {
+    fn add(&mut self, document: &Document) {
+        let mut seen = HashSet::new();
+        self.add_fields("", document, &mut seen);
+    }
+
+    fn add_fields(&mut self, prefix: &str, document: &Document, seen: &mut HashSet<String>) {
+        for (key, value) in document {
+            self.add_value(format!("{}{}", prefix, key), value, seen);
+        }
+    }
+
+    fn add_value(&mut self, path: String, value: &Bson, seen: &mut HashSet<String>) {
+        if !self.fields.contains_key(&path) {
+            self.order.push(path.clone());
+        }
+        let stats = self.fields.entry(path.clone()).or_default();
+        stats.types.insert(bson_type(value));
+        // A field counts once per document however many array elements carry it
+        if seen.insert(path.clone()) {
+            stats.documents += 1;
+        }
+
+        match value {
+            Bson::Document(document) => self.add_fields(&format!("{}.", path), document, seen),
+            Bson::Array(values) => {
+                for value in values {
+                    self.add_value(format!("{}[]", path), value, seen);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn columns(mut self, sampled: usize, unique: &HashSet<String>) -> Vec<ColumnInfo> {
+        self.order
+            .into_iter()
+            .map(|path| {
+                let stats = self.fields.remove(&path).unwrap_or_default();
+                let presence = if sampled == 0 {
+                    0.0
+                } else {
+                    stats.documents as f64 / sampled as f64
+                };
+                let types: Vec<&str> = stats.types.iter().copied().collect();
+                let non_null: Vec<&str> = types.iter().copied().filter(|t| *t != "null").collect();
+
+                let mut constraints = Vec::new();
+                if path == "_id" {
+                    constraints.push("PRIMARY KEY".to_string());
+                } else if unique.contains(&path) {
+                    constraints.push("UNIQUE".to_string());
+                }
+
+                let mut properties = BTreeMap::new();
+                properties.insert("presence".to_string(), format!("{:.2}", presence));
+                properties.insert("bson_types".to_string(), types.join(", "));
+
+                ColumnInfo {
+                    data_type: if non_null.is_empty() {
+                        "null".to_string()
+                    } else {
+                        non_null.join(" | ")
+                    },
+                    is_nullable: presence < 1.0 || types.contains(&"null"),
+                    default_value: None,
+                    constraints,
+                    properties,
+                    name: path,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The `$type` alias of a value.
+fn bson_type(value: &Bson) -> &'static str {
+    match value {
+        Bson::Double(_) => "double",
+        Bson::String(_) => "string",
+        Bson::Document(_) => "object",
+        Bson::Array(_) => "array",
+        Bson::Binary(_) => "binData",
+        Bson::Undefined => "undefined",
+        Bson::ObjectId(_) => "objectId",
+        Bson::Boolean(_) => "bool",
+        Bson::DateTime(_) => "date",
+        Bson::Null => "null",
+        Bson::RegularExpression(_) => "regex",
+        Bson::DbPointer(_) => "dbPointer",
+        Bson::JavaScriptCode(_) => "javascript",
+        Bson::Symbol(_) => "symbol",
+        Bson::JavaScriptCodeWithScope(_) => "javascriptWithScope",
+        Bson::Int32(_) => "int",
+        Bson::Timestamp(_) => "timestamp",
+        Bson::Int64(_) => "long",
+        Bson::Decimal128(_) => "decimal",
+        Bson::MinKey => "minKey",
+        Bson::MaxKey => "maxKey",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::oid::ObjectId;
+
+    #[test]
+    fn sampled_fields_and_presence() {
+        let mut schema = SampledSchema::default();
+        schema.add(&doc! {
+            "_id": ObjectId::new(),
+            "name": "a",
+            "tags": ["x", "y"],
+            "address": { "city": "Berlin" },
+        });
+        schema.add(&doc! {
+            "_id": ObjectId::new(),
+            "name": Bson::Null,
+            "tags": [1],
+            "email": "b@example.com",
+        });
+
+        let unique: HashSet<String> = ["email".to_string()].into();
+        let columns = schema.columns(2, &unique);
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "_id",
+                "name",
+                "tags",
+                "tags[]",
+                "address",
+                "address.city",
+                "email"
+            ]
+        );
+
+        let column = |name: &str| columns.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(column("_id").constraints, ["PRIMARY KEY"]);
+        assert_eq!(column("_id").data_type, "objectId");
+        assert!(!column("_id").is_nullable);
+
+        assert_eq!(column("name").data_type, "string");
+        assert_eq!(column("name").properties["bson_types"], "null, string");
+        assert!(column("name").is_nullable);
+
+        // Counted once per document, not once per element
+        assert_eq!(column("tags[]").properties["presence"], "1.00");
+        assert_eq!(column("tags[]").data_type, "int | string");
+
+        assert_eq!(column("address.city").properties["presence"], "0.50");
+        assert!(column("address.city").is_nullable);
+        assert_eq!(column("email").constraints, ["UNIQUE"]);
+    }
+
+    #[test]
+    fn empty_sample() {
+        assert!(SampledSchema::default()
+            .columns(0, &HashSet::new())
+            .is_empty());
+    }
+
+    #[test]
+    fn rows_follow_first_seen_fields() {
+        let result = document_rows(vec![doc! { "a": 1, "b": "x" }, doc! { "c": true, "a": 2 }]);
+        assert_eq!(result.columns, ["a", "b", "c"]);
+        assert_eq!(
+            result.rows[0],
+            [Value::from(1), Value::from("x"), Value::Null]
+        );
+        assert_eq!(
+            result.rows[1],
+            [Value::from(2), Value::Null, Value::from(true)]
+        );
+    }
+}
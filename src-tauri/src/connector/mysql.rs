@@ -0,0 +1,179 @@
+use crate::connection::mysql::MySQLConnector;
+use crate::connection::tls::TlsProbe;
+use crate::connection::{DataSourceKind, ServerDetails, TlsInfo};
+use crate::connector::{sqlx_json_value, Capability, Connector, QueryResult, SessionInfo};
+use crate::db::DataSource;
+use crate::metadata::mysql::MySQLMetadata;
+use crate::metadata::TableInfo;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use sqlx::{Column, Either, MySqlConnection, Row};
+
+#[async_trait]
+impl Connector for MySQLConnector {
+    fn kind(&self) -> DataSourceKind {
+        DataSourceKind::Mysql
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        &[
+            Capability::TestConnection,
+            Capability::Tables,
+            Capability::TableStructure,
+            Capability::RowCount,
+            Capability::Query,
+            Capability::Databases,
+            Capability::Schemas,
+        ]
+    }
+
+    async fn test_connection(&self, data_source: &DataSource) -> Result<()> {
+        Self::create_pool(data_source)
+            .await
+            .context("Failed to create MySQL connection pool")?;
+        Ok(())
+    }
+
+    async fn inspect_connection(&self, data_source: &DataSource) -> Result<SessionInfo> {
+        let pool = Self::create_pool(data_source)
+            .await
+            .context("Failed to create MySQL connection pool")?;
+        let mut connection = pool.acquire().await?;
+        Ok(SessionInfo {
+            tls: Self::session_tls(&mut connection, data_source).await,
+            server: Self::session_server(&mut connection).await,
+        })
+    }
+
+    async fn get_tables(&self, data_source: &DataSource) -> Result<Vec<TableInfo>> {
+        MySQLMetadata::get_tables(data_source).await
+    }
+
+    async fn get_table_structure(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableInfo> {
+        MySQLMetadata::get_table_structure(data_source, schema, table_name).await
+    }
+
+    async fn get_table_row_count(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<i64> {
+        MySQLMetadata::get_table_row_count(data_source, schema, table_name).await
+    }
+
+    async fn list_databases(&self, data_source: &DataSource) -> Result<Vec<String>> {
+        MySQLMetadata::get_databases(data_source).await
+    }
+
+    async fn list_schemas(
+        &self,
+        data_source: &DataSource,
+        database: Option<&str>,
+    ) -> Result<Vec<String>> {
+        MySQLMetadata::get_schemas(data_source, database).await
+    }
+
+    async fn execute_query(
+        &self,
+        data_source: &DataSource,
+        sql: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult> {
+        let pool = Self::create_pool(data_source).await?;
+        let mut stream = sqlx::raw_sql(sql).fetch_many(&pool);
+
+        let mut result = QueryResult::default();
+        let mut rows_affected = 0;
+        while let Some(item) = stream.try_next().await.context("MySQL query failed")? {
+            match item {
+                Either::Left(done) => rows_affected += done.rows_affected(),
+                Either::Right(row) => {
+                    if result.columns.is_empty() {
+                        result.columns =
+                            row.columns().iter().map(|c| c.name().to_string()).collect();
+                    }
+                    if result.rows.len() >= max_rows {
+                        result.truncated = true;
+                        break;
+                    }
+                    let values = (0..row.len())
+                        .map(|i| {
+                            sqlx_json_value!(row, i, [
+                                i64,
+                                u64,
+                                f64,
+                                f32,
+                                String,
+                                chrono::NaiveDateTime,
+                                chrono::DateTime<chrono::Utc>,
+                                chrono::NaiveDate,
+                                chrono::NaiveTime,
+                            ])
+                        })
+                        .collect();
+                    result.rows.push(values);
+                }
+            }
+        }
+        if result.columns.is_empty() {
+            result.rows_affected = Some(rows_affected);
+        }
+
+        Ok(result)
+    }
+}
+
+impl MySQLConnector {
+    /// The session status has the protocol and cipher but not the certificate, which
+    /// a probe connection reads.
+    async fn session_tls(
+        connection: &mut MySqlConnection,
+        data_source: &DataSource,
+    ) -> Result<Option<TlsInfo>> {
+        let status: Vec<(String, String)> = sqlx::query_as(
+            "SHOW SESSION STATUS WHERE Variable_name IN ('Ssl_version', 'Ssl_cipher')",
+        )
+        .fetch_all(&mut *connection)
+        .await?;
+        let value = |name: &str| {
+            status
+                .iter()
+                .find(|(variable, _)| variable.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+                .filter(|value| !value.is_empty())
+        };
+
+        let Some(version) = value("Ssl_version") else {
+            return Ok(Some(TlsInfo::default()));
+        };
+        Ok(Some(
+            TlsInfo {
+                encrypted: true,
+                version: Some(version),
+                cipher: value("Ssl_cipher"),
+                ..Default::default()
+            }
+            .with_certificate(TlsProbe::mysql_certificate(data_source).await),
+        ))
+    }
+
+    async fn session_server(connection: &mut MySqlConnection) -> Result<Option<ServerDetails>> {
+        let (version, user, database): (String, String, Option<String>) = sqlx::query_as(
+            "SELECT CAST(VERSION() AS CHAR), CAST(CURRENT_USER() AS CHAR), CAST(DATABASE() AS CHAR)",
+        )
+        .fetch_one(&mut *connection)
+        .await?;
+        Ok(Some(ServerDetails {
+            version: Some(version),
+            current_user: Some(user),
+            current_database: database,
+        }))
+    }
+}
@@ -0,0 +1,183 @@
+use crate::connection::postgresql::PostgreSQLConnector;
+use crate::connection::tls::TlsProbe;
+use crate::connection::{DataSourceKind, ServerDetails, TlsInfo};
+use crate::connector::{sqlx_json_value, Capability, Connector, QueryResult, SessionInfo};
+use crate::db::DataSource;
+use crate::metadata::postgresql::PostgreSQLMetadata;
+use crate::metadata::{PostgresObjects, TableInfo};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use sqlx::{Column, Either, PgConnection, Row};
+
+#[async_trait]
+impl Connector for PostgreSQLConnector {
+    fn kind(&self) -> DataSourceKind {
+        DataSourceKind::Postgresql
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        &[
+            Capability::TestConnection,
+            Capability::Tables,
+            Capability::TableStructure,
+            Capability::RowCount,
+            Capability::Query,
+            Capability::SchemaObjects,
+            Capability::Databases,
+            Capability::Schemas,
+        ]
+    }
+
+    async fn test_connection(&self, data_source: &DataSource) -> Result<()> {
+        Self::create_pool(data_source)
+            .await
+            .context("Failed to create PostgreSQL connection pool")?;
+        Ok(())
+    }
+
+    async fn inspect_connection(&self, data_source: &DataSource) -> Result<SessionInfo> {
+        let pool = Self::create_pool(data_source)
+            .await
+            .context("Failed to create PostgreSQL connection pool")?;
+        let mut connection = pool.acquire().await?;
+        Ok(SessionInfo {
+            tls: Self::session_tls(&mut connection, data_source).await,
+            server: Self::session_server(&mut connection).await,
+        })
+    }
+
+    async fn get_tables(&self, data_source: &DataSource) -> Result<Vec<TableInfo>> {
+        PostgreSQLMetadata::get_tables(data_source).await
+    }
+
+    async fn get_table_structure(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableInfo> {
+        PostgreSQLMetadata::get_table_structure(data_source, schema, table_name).await
+    }
+
+    async fn get_table_row_count(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<i64> {
+        PostgreSQLMetadata::get_table_row_count(data_source, schema, table_name).await
+    }
+
+    async fn list_databases(&self, data_source: &DataSource) -> Result<Vec<String>> {
+        PostgreSQLMetadata::get_databases(data_source).await
+    }
+
+    async fn list_schemas(
+        &self,
+        data_source: &DataSource,
+        database: Option<&str>,
+    ) -> Result<Vec<String>> {
+        PostgreSQLMetadata::get_schemas(data_source, database).await
+    }
+
+    async fn get_schema_objects(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+    ) -> Result<PostgresObjects> {
+        PostgreSQLMetadata::get_objects(data_source, schema).await
+    }
+
+    async fn execute_query(
+        &self,
+        data_source: &DataSource,
+        sql: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult> {
+        let pool = Self::create_pool(data_source).await?;
+        let mut stream = sqlx::raw_sql(sql).fetch_many(&pool);
+
+        let mut result = QueryResult::default();
+        let mut rows_affected = 0;
+        while let Some(item) = stream.try_next().await.context("PostgreSQL query failed")? {
+            match item {
+                Either::Left(done) => rows_affected += done.rows_affected(),
+                Either::Right(row) => {
+                    if result.columns.is_empty() {
+                        result.columns =
+                            row.columns().iter().map(|c| c.name().to_string()).collect();
+                    }
+                    if result.rows.len() >= max_rows {
+                        result.truncated = true;
+                        break;
+                    }
+                    let values = (0..row.len())
+                        .map(|i| {
+                            sqlx_json_value!(row, i, [
+                                i64,
+                                i32,
+                                i16,
+                                f64,
+                                f32,
+                                bool,
+                                String,
+                                uuid::Uuid,
+                                chrono::NaiveDateTime,
+                                chrono::DateTime<chrono::Utc>,
+                                chrono::NaiveDate,
+                                chrono::NaiveTime,
+                            ])
+                        })
+                        .collect();
+                    result.rows.push(values);
+                }
+            }
+        }
+        if result.columns.is_empty() {
+            result.rows_affected = Some(rows_affected);
+        }
+
+        Ok(result)
+    }
+}
+
+impl PostgreSQLConnector {
+    /// pg_stat_ssl has the protocol and cipher but not the server certificate, which
+    /// a probe connection reads.
+    async fn session_tls(
+        connection: &mut PgConnection,
+        data_source: &DataSource,
+    ) -> Result<Option<TlsInfo>> {
+        let ssl: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT version, cipher FROM pg_stat_ssl WHERE pid = pg_backend_pid() AND ssl",
+        )
+        .fetch_optional(&mut *connection)
+        .await?;
+
+        let Some((version, cipher)) = ssl else {
+            return Ok(Some(TlsInfo::default()));
+        };
+        Ok(Some(
+            TlsInfo {
+                encrypted: true,
+                version,
+                cipher,
+                ..Default::default()
+            }
+            .with_certificate(TlsProbe::postgres_certificate(data_source).await),
+        ))
+    }
+
+    async fn session_server(connection: &mut PgConnection) -> Result<Option<ServerDetails>> {
+        let (version, user, database): (String, String, String) =
+            sqlx::query_as("SELECT version(), current_user::text, current_database()::text")
+                .fetch_one(&mut *connection)
+                .await?;
+        Ok(Some(ServerDetails {
+            version: Some(version),
+            current_user: Some(user),
+            current_database: Some(database),
+        }))
+    }
+}
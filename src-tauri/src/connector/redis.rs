@@ -0,0 +1,425 @@
+use crate::connection::proxy::ProxyManager;
+use crate::connection::{DataSourceKind, ProxyType};
+use crate::connector::{Capability, Connector};
+use crate::db::DataSource;
+use crate::metadata::{RedisInfoSection, RedisKeyInfo, RedisKeyPage, RedisValue};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+use redis::{ConnectionAddr, ConnectionInfo, RedisConnectionInfo, Value as RedisReply};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const DEFAULT_SCAN_COUNT: u32 = 100;
+// Strings are read with GETRANGE so a huge value is never transferred whole
+const MAX_STRING_BYTES: usize = 1024 * 1024;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Keys are browsed with SCAN only; KEYS would block the server on large keyspaces.
+pub struct RedisConnector;
+
+impl RedisConnector {
+    async fn connect(data_source: &DataSource) -> Result<MultiplexedConnection> {
+        tokio::time::timeout(CONNECT_TIMEOUT, Self::open(data_source))
+            .await
+            .context("Timed out connecting to Redis")?
+    }
+
+    async fn open(data_source: &DataSource) -> Result<MultiplexedConnection> {
+        let options = data_source.redis_options();
+        let tls = options.tls.unwrap_or(false);
+        let redis = RedisConnectionInfo {
+            db: match data_source.database.as_deref().map(str::trim) {
+                Some(database) if !database.is_empty() => database
+                    .parse()
+                    .context("The database of a Redis data source must be a number")?,
+                _ => 0,
+            },
+            // An empty username authenticates as the default user, like a plain AUTH
+            username: Some(data_source.username.clone()).filter(|u| !u.is_empty()),
+            password: Some(data_source.password.clone()).filter(|p| !p.is_empty()),
+            protocol: Default::default(),
+        };
+
+        match (&data_source.proxy_type, &data_source.proxy_config) {
+            (None, _) => {
+                let addr = if tls {
+                    ConnectionAddr::TcpTls {
+                        host: data_source.host.clone(),
+                        port: data_source.port,
+                        insecure: options.insecure_skip_verify.unwrap_or(false),
+                        tls_params: None,
+                    }
+                } else {
+                    ConnectionAddr::Tcp(data_source.host.clone(), data_source.port)
+                };
+                let client = redis::Client::open(ConnectionInfo { addr, redis })
+                    .context("Invalid Redis connection settings")?;
+                client
+                    .get_multiplexed_async_connection()
+                    .await
+                    .context("Failed to connect to Redis")
+            }
+            (Some(ProxyType::Socks5), Some(proxy)) => {
+                if tls {
+                    return Err(anyhow::anyhow!(
+                        "TLS through a SOCKS5 proxy is not supported for Redis"
+                    ));
+                }
+                let proxy = proxy.clone();
+                let (host, port) = (data_source.host.clone(), data_source.port);
+                let stream = tokio::task::spawn_blocking(move || {
+                    ProxyManager::create_socks5_proxy_stream(&proxy, &host, port)
+                })
+                .await??;
+                stream.set_nonblocking(true)?;
+                let stream = tokio::net::TcpStream::from_std(stream)?;
+
+                let (connection, driver) = MultiplexedConnection::new(&redis, stream)
+                    .await
+                    .context("Failed to connect to Redis")?;
+                tokio::spawn(driver);
+                Ok(connection)
+            }
+            (Some(ProxyType::Socks5), None) => Err(anyhow::anyhow!("Proxy config is missing")),
+            (Some(proxy_type), _) => Err(anyhow::anyhow!(
+                "Redis data sources do not support {} proxies, only socks5",
+                proxy_type
+            )),
+        }
+    }
+
+    /// One SCAN step from `cursor`; pass the returned cursor back until it is 0.
+    async fn scan(
+        data_source: &DataSource,
+        pattern: Option<&str>,
+        cursor: u64,
+    ) -> Result<RedisKeyPage> {
+        let count = data_source
+            .redis_options()
+            .scan_count
+            .unwrap_or(DEFAULT_SCAN_COUNT);
+        let mut connection = Self::connect(data_source).await?;
+
+        let mut scan = redis::cmd("SCAN");
+        scan.arg(cursor);
+        if let Some(pattern) = pattern.filter(|p| !p.is_empty()) {
+            scan.arg("MATCH").arg(pattern);
+        }
+        scan.arg("COUNT").arg(count);
+        let (cursor, keys): (u64, Vec<Vec<u8>>) = scan
+            .query_async(&mut connection)
+            .await
+            .context("SCAN failed")?;
+
+        Ok(RedisKeyPage {
+            cursor,
+            keys: Self::key_info(&mut connection, &keys).await?,
+        })
+    }
+
+    /// Type, TTL and memory usage of each key, in two round trips.
+    async fn key_info(
+        connection: &mut MultiplexedConnection,
+        keys: &[Vec<u8>],
+    ) -> Result<Vec<RedisKeyInfo>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut pipe = redis::pipe();
+        for key in keys {
+            pipe.cmd("TYPE").arg(key).cmd("PTTL").arg(key);
+        }
+        let replies: Vec<RedisReply> = pipe.query_async(connection).await?;
+
+        // MEMORY USAGE is missing before Redis 4 and often renamed or disabled by hosted services
+        let mut pipe = redis::pipe();
+        for key in keys {
+            pipe.cmd("MEMORY").arg("USAGE").arg(key);
+        }
+        let memory: Vec<Option<u64>> = pipe
+            .query_async(connection)
+            .await
+            .unwrap_or_else(|_| vec![None; keys.len()]);
+
+        keys.iter()
+            .zip(replies.chunks(2))
+            .zip(memory)
+            .map(|((key, replies), memory_bytes)| {
+                let key_type: String = redis::from_redis_value(&replies[0])?;
+                let ttl_ms: i64 = redis::from_redis_value(&replies[1])?;
+                Ok(RedisKeyInfo {
+                    key: String::from_utf8_lossy(key).into_owned(),
+                    key_type,
+                    // -1 is no expiry and -2 a key that vanished in between
+                    ttl_ms: Some(ttl_ms).filter(|ttl| *ttl >= 0),
+                    memory_bytes,
+                })
+            })
+            .collect()
+    }
+
+    /// The value of a key, with at most `limit` entries of a collection type.
+    async fn get_value(data_source: &DataSource, key: &str, limit: usize) -> Result<RedisValue> {
+        let mut connection = Self::connect(data_source).await?;
+        let info = Self::key_info(&mut connection, &[key.as_bytes().to_vec()])
+            .await?
+            .pop()
+            .context("Redis returned no key info")?;
+        if info.key_type == "none" {
+            return Err(anyhow::anyhow!("Key {} does not exist", key));
+        }
+
+        let limit = limit.max(1);
+        let conn = &mut connection;
+        let end = limit as isize - 1;
+        let (length, value): (u64, Value) = match info.key_type.as_str() {
+            "string" => {
+                let length = redis::cmd("STRLEN").arg(key).query_async(conn).await?;
+                let bytes: Vec<u8> = redis::cmd("GETRANGE")
+                    .arg(key)
+                    .arg(0)
+                    .arg(MAX_STRING_BYTES - 1)
+                    .query_async(conn)
+                    .await?;
+                (length, text(&bytes))
+            }
+            "hash" => {
+                let length = redis::cmd("HLEN").arg(key).query_async(conn).await?;
+                let fields = scan_members(conn, "HSCAN", key, limit * 2).await?;
+                let map: Map<String, Value> = fields
+                    .chunks(2)
+                    .filter(|pair| pair.len() == 2)
+                    .map(|pair| {
+                        (
+                            String::from_utf8_lossy(&pair[0]).into_owned(),
+                            text(&pair[1]),
+                        )
+                    })
+                    .collect();
+                (length, Value::Object(map))
+            }
+            "list" => {
+                let length = redis::cmd("LLEN").arg(key).query_async(conn).await?;
+                let items: Vec<Vec<u8>> = redis::cmd("LRANGE")
+                    .arg(key)
+                    .arg(0)
+                    .arg(end)
+                    .query_async(conn)
+                    .await?;
+                (length, items.iter().map(|i| text(i)).collect())
+            }
+            "set" => {
+                let length = redis::cmd("SCARD").arg(key).query_async(conn).await?;
+                let mut members = scan_members(conn, "SSCAN", key, limit).await?;
+                members.sort();
+                (length, members.iter().map(|m| text(m)).collect())
+            }
+            "zset" => {
+                let length = redis::cmd("ZCARD").arg(key).query_async(conn).await?;
+                let members: Vec<(Vec<u8>, f64)> = redis::cmd("ZRANGE")
+                    .arg(key)
+                    .arg(0)
+                    .arg(end)
+                    .arg("WITHSCORES")
+                    .query_async(conn)
+                    .await?;
+                let members = members
+                    .iter()
+                    .map(|(member, score)| json!({ "member": text(member), "score": score }))
+                    .collect();
+                (length, members)
+            }
+            "stream" => {
+                let length = redis::cmd("XLEN").arg(key).query_async(conn).await?;
+                let entries: Vec<(String, Vec<Vec<u8>>)> = redis::cmd("XRANGE")
+                    .arg(key)
+                    .arg("-")
+                    .arg("+")
+                    .arg("COUNT")
+                    .arg(limit)
+                    .query_async(conn)
+                    .await?;
+                let entries = entries
+                    .iter()
+                    .map(|(id, fields)| {
+                        let fields: Map<String, Value> = fields
+                            .chunks(2)
+                            .filter(|pair| pair.len() == 2)
+                            .map(|pair| {
+                                (
+                                    String::from_utf8_lossy(&pair[0]).into_owned(),
+                                    text(&pair[1]),
+                                )
+                            })
+                            .collect();
+                        json!({ "id": id, "fields": fields })
+                    })
+                    .collect();
+                (length, entries)
+            }
+            // Module types such as ReJSON-RL have their own commands
+            _ => {
+                return Ok(RedisValue {
+                    key: info,
+                    length: None,
+                    value: Value::Null,
+                    truncated: false,
+                })
+            }
+        };
+
+        let limit = if info.key_type == "string" {
+            MAX_STRING_BYTES
+        } else {
+            limit
+        };
+        Ok(RedisValue {
+            key: info,
+            truncated: length > limit as u64,
+            length: Some(length),
+            value,
+        })
+    }
+
+    /// The default INFO sections, e.g. server, clients, memory and keyspace.
+    async fn info(data_source: &DataSource) -> Result<Vec<RedisInfoSection>> {
+        let mut connection = Self::connect(data_source).await?;
+        let info: String = redis::cmd("INFO")
+            .query_async(&mut connection)
+            .await
+            .context("INFO failed")?;
+        Ok(parse_info(&info))
+    }
+}
+
+#[async_trait]
+impl Connector for RedisConnector {
+    fn kind(&self) -> DataSourceKind {
+        DataSourceKind::Redis
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        &[
+            Capability::TestConnection,
+            Capability::Keys,
+            Capability::ServerInfo,
+        ]
+    }
+
+    async fn test_connection(&self, data_source: &DataSource) -> Result<()> {
+        let mut connection = Self::connect(data_source).await?;
+        redis::cmd("PING")
+            .query_async::<String>(&mut connection)
+            .await
+            .context("PING failed")?;
+        Ok(())
+    }
+
+    async fn scan_keys(
+        &self,
+        data_source: &DataSource,
+        pattern: Option<&str>,
+        cursor: u64,
+    ) -> Result<RedisKeyPage> {
+        Self::scan(data_source, pattern, cursor).await
+    }
+
+    async fn get_key_value(
+        &self,
+        data_source: &DataSource,
+        key: &str,
+        limit: usize,
+    ) -> Result<RedisValue> {
+        Self::get_value(data_source, key, limit).await
+    }
+
+    async fn get_server_info(&self, data_source: &DataSource) -> Result<Vec<RedisInfoSection>> {
+        Self::info(data_source).await
+    }
+}
+
+/// Members of a hash (as field, value pairs) or set via HSCAN/SSCAN, stopping once
+/// `limit` items were read.
+async fn scan_members(
+    connection: &mut MultiplexedConnection,
+    command: &str,
+    key: &str,
+    limit: usize,
+) -> Result<Vec<Vec<u8>>> {
+    let mut items = Vec::new();
+    let mut cursor = 0u64;
+    loop {
+        let (next, batch): (u64, Vec<Vec<u8>>) = redis::cmd(command)
+            .arg(key)
+            .arg(cursor)
+            .arg("COUNT")
+            .arg(DEFAULT_SCAN_COUNT)
+            .query_async(connection)
+            .await?;
+        items.extend(batch);
+        cursor = next;
+        if cursor == 0 || items.len() >= limit {
+            break;
+        }
+    }
+    items.truncate(limit);
+    Ok(items)
+}
+
+/// `# Section` headers followed by `key:value` lines.
+fn parse_info(info: &str) -> Vec<RedisInfoSection> {
+    let mut sections: Vec<RedisInfoSection> = Vec::new();
+    for line in info.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(name) = line.strip_prefix('#') {
+            sections.push(RedisInfoSection {
+                name: name.trim().to_string(),
+                entries: BTreeMap::new(),
+            });
+        } else if let (Some(section), Some((key, value))) =
+            (sections.last_mut(), line.split_once(':'))
+        {
+            section.entries.insert(key.to_string(), value.to_string());
+        }
+    }
+    sections
+}
+
+/// Text when the bytes are UTF-8, otherwise just their size.
+fn text(bytes: &[u8]) -> Value {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => Value::from(s),
+        Err(_) => Value::from(format!("<{} bytes>", bytes.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info_sections() {
+        let info = "# Server\r\nredis_version:7.2.4\r\nos:Linux 6.1 x86_64\r\n\r\n# Keyspace\r\ndb0:keys=12,expires=0,avg_ttl=0\r\n";
+        let sections = parse_info(info);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].name, "Server");
+        assert_eq!(sections[0].entries["redis_version"], "7.2.4");
+        assert_eq!(sections[0].entries["os"], "Linux 6.1 x86_64");
+        assert_eq!(sections[1].name, "Keyspace");
+        assert_eq!(sections[1].entries["db0"], "keys=12,expires=0,avg_ttl=0");
+    }
+
+    #[test]
+    fn lines_before_a_section_are_ignored() {
+        assert!(parse_info("stray:line\n").is_empty());
+        assert!(parse_info("").is_empty());
+    }
+
+    #[test]
+    fn binary_values_show_their_size() {
+        assert_eq!(text(b"hello"), Value::from("hello"));
+        assert_eq!(text(&[0xff, 0xfe, 0x00]), Value::from("<3 bytes>"));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::connection::DataSourceKind;
+use crate::connector::{Capability, Connector, QueryResult, SQL_CAPABILITIES};
+use crate::db::DataSource;
+use crate::metadata::{ColumnInfo, TableInfo};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde_json::Value;
+use std::path::PathBuf;
+
+pub struct SqliteConnector;
+
+impl SqliteConnector {
+    /// Opens the file without ever creating it, read-only unless the options say otherwise.
+    fn open(data_source: &DataSource) -> Result<Connection> {
+        let path = database_path(data_source)?;
+        let read_only = data_source
+            .file_database_options()
+            .read_only
+            .unwrap_or(true);
+        let flags = if read_only {
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+        } else {
+            OpenFlags::SQLITE_OPEN_READ_WRITE
+        };
+
+        let conn = Connection::open_with_flags(&path, flags | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+            .context(format!("Failed to open SQLite database {}", path.display()))?;
+        // Opening succeeds for any file; the first read tells whether it is a database
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .context(format!("{} is not a SQLite database", path.display()))?;
+        Ok(conn)
+    }
+
+    async fn run<T, F>(data_source: &DataSource, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let data_source = data_source.clone();
+        tokio::task::spawn_blocking(move || f(&Self::open(&data_source)?)).await?
+    }
+
+    fn table_structure(conn: &Connection, schema: &str, table_name: &str) -> Result<TableInfo> {
+        let mut stmt = conn.prepare(&format!(
+            "PRAGMA \"{}\".table_info(\"{}\")",
+            quote(schema),
+            quote(table_name)
+        ))?;
+        let columns = stmt
+            .query_map([], |row| {
+                let not_null: bool = row.get(3)?;
+                let primary_key: i64 = row.get(5)?;
+                Ok(ColumnInfo {
+                    name: row.get(1)?,
+                    data_type: row.get(2)?,
+                    is_nullable: !not_null && primary_key == 0,
+                    default_value: row.get(4)?,
+                    constraints: if primary_key > 0 {
+                        vec!["PRIMARY KEY".to_string()]
+                    } else {
+                        Vec::new()
+                    },
+                    properties: Default::default(),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        if columns.is_empty() {
+            return Err(anyhow::anyhow!("Table {}.{} not found", schema, table_name));
+        }
+
+        Ok(TableInfo {
+            name: table_name.to_string(),
+            schema: Some(schema.to_string()),
+            row_count: None,
+            columns,
+            properties: Default::default(),
+        })
+    }
+}
+
+#[async_trait]
+impl Connector for SqliteConnector {
+    fn kind(&self) -> DataSourceKind {
+        DataSourceKind::Sqlite
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        SQL_CAPABILITIES
+    }
+
+    async fn test_connection(&self, data_source: &DataSource) -> Result<()> {
+        Self::run(data_source, |_| Ok(())).await
+    }
+
+    async fn get_tables(&self, data_source: &DataSource) -> Result<Vec<TableInfo>> {
+        Self::run(data_source, |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT name FROM sqlite_master
+                 WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+                 ORDER BY name",
+            )?;
+            let names = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            names
+                .iter()
+                .map(|name| Self::table_structure(conn, MAIN_SCHEMA, name))
+                .collect()
+        })
+        .await
+    }
+
+    async fn get_table_structure(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableInfo> {
+        let schema = schema.unwrap_or(MAIN_SCHEMA).to_string();
+        let table_name = table_name.to_string();
+        Self::run(data_source, move |conn| {
+            Self::table_structure(conn, &schema, &table_name)
+        })
+        .await
+    }
+
+    async fn get_table_row_count(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<i64> {
+        let query = format!(
+            "SELECT COUNT(*) FROM \"{}\".\"{}\"",
+            quote(schema.unwrap_or(MAIN_SCHEMA)),
+            quote(table_name)
+        );
+        Self::run(data_source, move |conn| {
+            Ok(conn.query_row(&query, [], |row| row.get(0))?)
+        })
+        .await
+    }
+
+    async fn execute_query(
+        &self,
+        data_source: &DataSource,
+        sql: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult> {
+        let sql = sql.to_string();
+        Self::run(data_source, move |conn| {
+            let mut stmt = conn.prepare(&sql).context("SQLite query failed")?;
+            let mut result = QueryResult {
+                columns: stmt.column_names().into_iter().map(String::from).collect(),
+                ..Default::default()
+            };
+            if result.columns.is_empty() {
+                result.rows_affected = Some(stmt.execute([])? as u64);
+                return Ok(result);
+            }
+
+            let column_count = result.columns.len();
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                if result.rows.len() >= max_rows {
+                    result.truncated = true;
+                    break;
+                }
+                let values = (0..column_count)
+                    .map(|i| row.get_ref(i).map(json_value))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                result.rows.push(values);
+            }
+            Ok(result)
+        })
+        .await
+    }
+}
+
+const MAIN_SCHEMA: &str = "main";
+
+/// The `database` of a file data source is its path; `~/` is expanded.
+pub(crate) fn database_path(data_source: &DataSource) -> Result<PathBuf> {
+    let path = data_source
+        .database
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+        .context("No database file configured")?;
+    let path = crate::secrets::expand_home(path.trim());
+    if !path.is_file() {
+        return Err(anyhow::anyhow!(
+            "Database file {} does not exist",
+            path.display()
+        ));
+    }
+    Ok(path)
+}
+
+/// Escapes an identifier for use inside double quotes.
+pub(crate) fn quote(identifier: &str) -> String {
+    identifier.replace('"', "\"\"")
+}
+
+fn json_value(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::from(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(b) => Value::from(format!("<{} bytes>", b.len())),
+    }
+}
@@ -0,0 +1,208 @@
+use crate::connection::sqlserver::SQLServerConnector;
+use crate::connection::{DataSourceKind, ServerDetails, TlsInfo};
+use crate::connector::{Capability, Connector, QueryResult, SessionInfo};
+use crate::db::DataSource;
+use crate::metadata::sqlserver::SQLServerMetadata;
+use crate::metadata::TableInfo;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde_json::{json, Value};
+use tiberius::{Client, ColumnData, FromSql, Query, QueryItem};
+use tokio::net::TcpStream;
+use tokio_util::compat::Compat;
+
+type SqlServerClient = Client<Compat<TcpStream>>;
+
+#[async_trait]
+impl Connector for SQLServerConnector {
+    fn kind(&self) -> DataSourceKind {
+        DataSourceKind::Sqlserver
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        &[
+            Capability::TestConnection,
+            Capability::Tables,
+            Capability::TableStructure,
+            Capability::RowCount,
+            Capability::Query,
+            Capability::Databases,
+            Capability::Schemas,
+        ]
+    }
+
+    async fn test_connection(&self, data_source: &DataSource) -> Result<()> {
+        Self::create_client(data_source)
+            .await
+            .context("Failed to create SQL Server connection")?;
+        Ok(())
+    }
+
+    async fn inspect_connection(&self, data_source: &DataSource) -> Result<SessionInfo> {
+        let mut client = Self::create_client(data_source)
+            .await
+            .context("Failed to create SQL Server connection")?;
+        Ok(SessionInfo {
+            tls: Self::session_tls(&mut client).await,
+            server: Self::session_server(&mut client).await,
+        })
+    }
+
+    async fn get_tables(&self, data_source: &DataSource) -> Result<Vec<TableInfo>> {
+        SQLServerMetadata::get_tables(data_source).await
+    }
+
+    async fn get_table_structure(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableInfo> {
+        SQLServerMetadata::get_table_structure(data_source, schema, table_name).await
+    }
+
+    async fn get_table_row_count(
+        &self,
+        data_source: &DataSource,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<i64> {
+        SQLServerMetadata::get_table_row_count(data_source, schema, table_name).await
+    }
+
+    async fn list_databases(&self, data_source: &DataSource) -> Result<Vec<String>> {
+        SQLServerMetadata::get_databases(data_source).await
+    }
+
+    async fn list_schemas(
+        &self,
+        data_source: &DataSource,
+        database: Option<&str>,
+    ) -> Result<Vec<String>> {
+        SQLServerMetadata::get_schemas(data_source, database).await
+    }
+
+    /// Only the first result set is returned; SQL Server batches may produce several.
+    async fn execute_query(
+        &self,
+        data_source: &DataSource,
+        sql: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult> {
+        let mut client = Self::create_client(data_source).await?;
+        let mut stream = client
+            .simple_query(sql)
+            .await
+            .context("SQL Server query failed")?;
+
+        let mut result = QueryResult::default();
+        while let Some(item) = stream.try_next().await.context("SQL Server query failed")? {
+            match item {
+                QueryItem::Metadata(meta) if meta.result_index() == 0 => {
+                    result.columns = meta
+                        .columns()
+                        .iter()
+                        .map(|c| c.name().to_string())
+                        .collect();
+                }
+                QueryItem::Row(row) if row.result_index() == 0 => {
+                    if result.rows.len() >= max_rows {
+                        result.truncated = true;
+                        break;
+                    }
+                    result
+                        .rows
+                        .push(row.into_iter().map(|data| column_json(&data)).collect());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl SQLServerConnector {
+    /// Only whether the connection is encrypted: SQL Server reports no protocol or
+    /// cipher, and its TLS handshake travels inside TDS packets where a probe cannot
+    /// easily read the certificate. Reading sys.dm_exec_connections needs VIEW SERVER
+    /// STATE; without it nothing is known.
+    async fn session_tls(client: &mut SqlServerClient) -> Result<Option<TlsInfo>> {
+        let rows = match Query::new(
+            "SELECT CAST(encrypt_option AS nvarchar(40)) FROM sys.dm_exec_connections
+             WHERE session_id = @@SPID",
+        )
+        .query(client)
+        .await
+        {
+            Ok(stream) => stream.into_first_result().await?,
+            Err(_) => return Ok(None),
+        };
+        Ok(rows
+            .first()
+            .and_then(|row| row.get::<&str, _>(0))
+            .map(|encrypted| TlsInfo {
+                encrypted: encrypted.eq_ignore_ascii_case("TRUE"),
+                ..Default::default()
+            }))
+    }
+
+    /// @@VERSION spans several lines; the first names the product and build.
+    async fn session_server(client: &mut SqlServerClient) -> Result<Option<ServerDetails>> {
+        let rows = Query::new("SELECT CAST(@@VERSION AS nvarchar(4000)), SUSER_SNAME(), DB_NAME()")
+            .query(client)
+            .await?
+            .into_first_result()
+            .await?;
+        let Some(row) = rows.first() else {
+            return Ok(None);
+        };
+        Ok(Some(ServerDetails {
+            version: row
+                .get::<&str, _>(0)
+                .and_then(|v| v.lines().next())
+                .map(|v| v.trim().to_string()),
+            current_user: row.get::<&str, _>(1).map(String::from),
+            current_database: row.get::<&str, _>(2).map(String::from),
+        }))
+    }
+}
+
+fn column_json(data: &ColumnData<'static>) -> Value {
+    let value = match data {
+        ColumnData::U8(v) => v.map(Value::from),
+        ColumnData::I16(v) => v.map(Value::from),
+        ColumnData::I32(v) => v.map(Value::from),
+        ColumnData::I64(v) => v.map(Value::from),
+        ColumnData::F32(v) => v.map(Value::from),
+        ColumnData::F64(v) => v.map(Value::from),
+        ColumnData::Bit(v) => v.map(Value::from),
+        ColumnData::String(v) => v.as_ref().map(|s| Value::from(s.to_string())),
+        ColumnData::Guid(v) => v.map(|g| Value::from(g.to_string())),
+        ColumnData::Numeric(v) => v.map(|n| Value::from(n.to_string())),
+        ColumnData::Xml(v) => v.as_ref().map(|x| Value::from(x.to_string())),
+        ColumnData::Binary(v) => v
+            .as_ref()
+            .map(|b| Value::from(format!("<{} bytes>", b.len()))),
+        ColumnData::DateTime(_) | ColumnData::SmallDateTime(_) | ColumnData::DateTime2(_) => {
+            chrono::NaiveDateTime::from_sql(data)
+                .ok()
+                .flatten()
+                .map(|d| json!(d))
+        }
+        ColumnData::Date(_) => chrono::NaiveDate::from_sql(data)
+            .ok()
+            .flatten()
+            .map(|d| json!(d)),
+        ColumnData::Time(_) => chrono::NaiveTime::from_sql(data)
+            .ok()
+            .flatten()
+            .map(|t| json!(t)),
+        ColumnData::DateTimeOffset(_) => chrono::DateTime::<chrono::FixedOffset>::from_sql(data)
+            .ok()
+            .flatten()
+            .map(|d| json!(d)),
+    };
+    value.unwrap_or(Value::Null)
+}
@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+// Overrides, in priority order: CLI flag, then environment variable
+const DB_PATH_FLAG: &str = "--db-path";
+const PROFILE_FLAG: &str = "--profile";
+const DB_PATH_ENV: &str = "TAURI_VUE3_DEMO_DB_PATH";
+const PROFILE_ENV: &str = "TAURI_VUE3_DEMO_PROFILE";
+
+const DEFAULT_DB_FILE: &str = "app.db";
+const PROFILES_DIR: &str = "profiles";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseLocation {
+    pub path: PathBuf,
+    pub profile: Option<String>,
+    pub source: String, // default, profile, cli, env
+    pub migrated_from: Option<PathBuf>,
+}
+
+impl DatabaseLocation {
+    /// Works out which database file to open and prepares its directory.
+    ///
+    /// An explicit path (`--db-path` / `TAURI_VUE3_DEMO_DB_PATH`) wins, then a named
+    /// profile (`--profile` / `TAURI_VUE3_DEMO_PROFILE`) stored under the app data
+    /// directory, then the default `app.db` in the app data directory.
+    pub fn resolve(app: &AppHandle) -> Result<Self> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+
+        let mut location = if let Some(path) = cli_value(&args, DB_PATH_FLAG) {
+            Self::explicit(PathBuf::from(path), "cli")
+        } else if let Some(path) = env_value(DB_PATH_ENV) {
+            Self::explicit(PathBuf::from(path), "env")
+        } else {
+            let data_dir = app
+                .path()
+                .app_data_dir()
+                .context("Failed to resolve the app data directory")?;
+            match cli_value(&args, PROFILE_FLAG).or_else(|| env_value(PROFILE_ENV)) {
+                Some(profile) => Self::profile(&data_dir, &profile)?,
+                None => Self {
+                    path: data_dir.join(DEFAULT_DB_FILE),
+                    profile: None,
+                    source: "default".to_string(),
+                    migrated_from: None,
+                },
+            }
+        };
+
+        if let Some(parent) = location.path.parent() {
+            std::fs::create_dir_all(parent).context(format!(
+                "Failed to create database directory {}",
+                parent.display()
+            ))?;
+        }
+
+        if location.source == "default" {
+            location.migrated_from = migrate_legacy_db(&location.path)?;
+        }
+
+        Ok(location)
+    }
+
+    fn explicit(path: PathBuf, source: &str) -> Self {
+        Self {
+            path,
+            profile: None,
+            source: source.to_string(),
+            migrated_from: None,
+        }
+    }
+
+    fn profile(data_dir: &Path, profile: &str) -> Result<Self> {
+        let valid = !profile.is_empty()
+            && profile
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(anyhow::anyhow!(
+                "Invalid profile name '{}': use letters, digits, '-' or '_'",
+                profile
+            ));
+        }
+
+        Ok(Self {
+            path: data_dir.join(PROFILES_DIR).join(format!("{}.db", profile)),
+            profile: Some(profile.to_string()),
+            source: "profile".to_string(),
+            migrated_from: None,
+        })
+    }
+}
+
+/// Accepts both `--flag value` and `--flag=value`.
+fn cli_value(args: &[String], flag: &str) -> Option<String> {
+    let prefix = format!("{}=", flag);
+    args.iter()
+        .enumerate()
+        .find_map(|(i, arg)| {
+            if arg == flag {
+                args.get(i + 1).cloned()
+            } else {
+                arg.strip_prefix(&prefix).map(|v| v.to_string())
+            }
+        })
+        .filter(|v| !v.is_empty())
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// Earlier versions kept the store in `./data/app.db` relative to wherever the app
+/// was launched. Copy it into place the first time the default location is used;
+/// the old file is left untouched so nothing is lost if the copy is incomplete.
+fn migrate_legacy_db(target: &Path) -> Result<Option<PathBuf>> {
+    if target.exists() {
+        return Ok(None);
+    }
+
+    let legacy = match std::env::current_dir() {
+        Ok(dir) => dir.join("data").join(DEFAULT_DB_FILE),
+        Err(_) => return Ok(None),
+    };
+    if !legacy.is_file() || legacy == target {
+        return Ok(None);
+    }
+
+    std::fs::copy(&legacy, target).context(format!(
+        "Failed to migrate {} to {}",
+        legacy.display(),
+        target.display()
+    ))?;
+
+    // Carry over an unflushed write-ahead log, if any, so no committed data is dropped
+    for suffix in ["-wal", "-shm"] {
+        let from = PathBuf::from(format!("{}{}", legacy.display(), suffix));
+        if from.is_file() {
+            let to = PathBuf::from(format!("{}{}", target.display(), suffix));
+            std::fs::copy(&from, &to).context(format!("Failed to migrate {}", from.display()))?;
+        }
+    }
+
+    Ok(Some(legacy))
+}
@@ -1,9 +1,17 @@
-use rusqlite::{Connection, Result as SqliteResult, params};
+use crate::connection::{DataSourceKind, DataSourceOptions, ProxyConfig, ProxyType, SshConfig};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{Result as SqliteResult, TransactionBehavior, params};
+use r2d2::PooledConnection;
+use r2d2_sqlite::SqliteConnectionManager;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
-use std::path::PathBuf;
+use std::time::Duration;
 use anyhow::{Context as AnyhowContext, Result};
 
+mod location;
+
+pub use location::DatabaseLocation;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Context {
     pub id: i64,
@@ -18,16 +26,21 @@ pub struct DataSource {
     pub id: i64,
     pub context_id: i64,
     pub name: String,
-    pub data_type: String, // mysql, postgresql, sqlserver, kafka
+    pub data_type: DataSourceKind,
     pub host: String,
     pub port: u16,
     pub database: Option<String>,
     pub username: String,
     pub password: String, // TODO: 加密存储
-    pub proxy_type: Option<String>, // socks5, http, ssh
-    pub proxy_config: Option<serde_json::Value>,
-    pub ssh_config: Option<serde_json::Value>,
+    #[serde(default, deserialize_with = "ProxyType::deserialize_optional")]
+    pub proxy_type: Option<ProxyType>,
+    pub proxy_config: Option<ProxyConfig>,
+    pub ssh_config: Option<SshConfig>,
     pub schema_registry_url: Option<String>, // For Kafka
+    #[serde(default)]
+    pub schema_registry_config: Option<serde_json::Value>, // Auth and TLS for the registry
+    #[serde(default)]
+    pub options: Option<DataSourceOptions>, // Settings specific to data_type
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -43,34 +56,183 @@ pub struct MetadataCache {
     pub expires_at: Option<DateTime<Utc>>,
 }
 
-pub struct Database {
-    conn: Connection,
+impl MetadataCache {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|exp| exp < Utc::now())
+    }
 }
 
-impl Database {
-    pub fn new() -> Result<Self> {
-        let db_path = Self::get_db_path()?;
-        let conn = Connection::open(&db_path)
-            .context("Failed to open database connection")?;
-        
-        let db = Database { conn };
-        db.init_schema()?;
-        Ok(db)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachePolicy {
+    pub id: i64,
+    pub data_source_id: Option<i64>, // None applies to every data source
+    pub cache_type: String, // tables, topics, ... or * for every type
+    pub ttl_seconds: Option<i64>, // None means never expire
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataChangeRecord {
+    pub id: i64,
+    pub data_source_id: i64,
+    pub cache_type: String,
+    pub cache_key: String,
+    pub object_type: String,
+    pub object_name: String,
+    pub change_type: String, // added, removed, modified
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub detected_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataSnapshot {
+    pub id: i64,
+    pub data_source_id: i64,
+    pub cache_type: String, // tables, topics
+    pub cache_key: String,
+    pub snapshot_data: serde_json::Value,
+    pub label: Option<String>,
+    pub taken_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataSnapshotInfo {
+    pub id: i64,
+    pub data_source_id: i64,
+    pub cache_type: String,
+    pub cache_key: String,
+    pub object_count: i64,
+    pub label: Option<String>,
+    pub taken_at: DateTime<Utc>,
+}
+
+/// Pool of connections to the local store, registered as Tauri managed state.
+/// Cloning is cheap and shares the same pool.
+#[derive(Clone)]
+pub struct DbPool {
+    pool: r2d2::Pool<SqliteConnectionManager>,
+    location: DatabaseLocation,
+}
+
+impl DbPool {
+    const MAX_CONNECTIONS: u32 = 8;
+
+    pub fn open(location: DatabaseLocation) -> Result<Self> {
+        let path = location.path.as_path();
+        let manager = SqliteConnectionManager::file(path).with_init(|conn| {
+            // WAL lets readers proceed while a writer holds the database
+            conn.execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 PRAGMA synchronous = NORMAL;
+                 PRAGMA busy_timeout = 5000;",
+            )
+        });
+
+        let pool = r2d2::Pool::builder()
+            .max_size(Self::MAX_CONNECTIONS)
+            .connection_timeout(Duration::from_secs(10))
+            .build(manager)
+            .context(format!("Failed to open database at {}", path.display()))?;
+
+        let db_pool = DbPool { pool, location };
+        db_pool.get()?.init_schema()?;
+        Ok(db_pool)
     }
 
-    fn get_db_path() -> Result<PathBuf> {
-        // Use Tauri's app data directory
-        // For now, use a local path. In production, use tauri::api::path::app_data_dir()
-        let mut path = std::env::current_dir()?;
-        path.push("data");
-        path.push("app.db");
-        
-        // Create directory if it doesn't exist
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        
-        Ok(path)
+    pub fn location(&self) -> &DatabaseLocation {
+        &self.location
+    }
+
+    pub fn get(&self) -> Result<Database> {
+        let conn = self.pool.get().context("Failed to get a database connection")?;
+        Ok(Database { conn })
+    }
+
+    /// Runs blocking database work on tokio's blocking pool so async commands
+    /// never stall the runtime threads.
+    pub async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T> + Send + 'static,
+    {
+        let pool = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let db = pool.get()?;
+            f(&db)
+        })
+        .await
+        .context("Database task panicked")?
+    }
+
+    /// Loads a data source a command is about to connect to, marking it recently used
+    /// so the refresh scheduler keeps its metadata current.
+    pub async fn use_data_source(&self, id: i64) -> Result<DataSource> {
+        self.run(move |db| {
+            db.touch_data_source(id)?;
+            db.get_data_source(id)
+        })
+        .await
+    }
+}
+
+// data_type and proxy_type are stored as their lowercase names
+impl ToSql for DataSourceKind {
+    fn to_sql(&self) -> SqliteResult<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for DataSourceKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str()?.parse().map_err(|e: anyhow::Error| FromSqlError::Other(e.into()))
+    }
+}
+
+impl ToSql for ProxyType {
+    fn to_sql(&self) -> SqliteResult<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for ProxyType {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str()?.parse().map_err(|e: anyhow::Error| FromSqlError::Other(e.into()))
+    }
+}
+
+// Other spellings of data_type and proxy_type found in older databases
+const LEGACY_DATA_TYPES: &[(&str, &str)] = &[
+    ("postgres", "postgresql"),
+    ("pg", "postgresql"),
+    ("mssql", "sqlserver"),
+    ("sql server", "sqlserver"),
+    ("mariadb", "mysql"),
+    ("mongo", "mongodb"),
+    ("elastic", "elasticsearch"),
+];
+
+const LEGACY_PROXY_TYPES: &[(&str, &str)] = &[
+    ("socks", "socks5"),
+    ("socks5h", "socks5"),
+    ("https", "http"),
+];
+
+pub struct Database {
+    conn: PooledConnection<SqliteConnectionManager>,
+}
+
+impl Database {
+    /// Runs `f` in a transaction, committing only if it succeeds.
+    pub fn transaction<T>(&self, f: impl FnOnce(&Database) -> Result<T>) -> Result<T> {
+        // IMMEDIATE takes the write lock up front so a concurrent writer cannot
+        // make the commit fail halfway through. `f` needs `self`, hence the unchecked
+        // constructor; the transaction still rolls back when dropped uncommitted, so a
+        // failed `f` or COMMIT never leaves it open on a pooled connection.
+        let tx = rusqlite::Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)?;
+        let value = f(self)?;
+        tx.commit()?;
+        Ok(value)
     }
 
     fn init_schema(&self) -> Result<()> {
@@ -102,6 +264,9 @@ impl Database {
                 schema_registry_url TEXT,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
+                schema_registry_config TEXT,
+                last_used_at TEXT,
+                options TEXT,
                 FOREIGN KEY (context_id) REFERENCES contexts(id) ON DELETE CASCADE
             )",
             [],
@@ -122,6 +287,58 @@ impl Database {
             [],
         )?;
 
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS cache_policies (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                data_source_id INTEGER,
+                cache_type TEXT NOT NULL,
+                ttl_seconds INTEGER,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (data_source_id) REFERENCES data_sources(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS metadata_changes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                data_source_id INTEGER NOT NULL,
+                cache_type TEXT NOT NULL,
+                cache_key TEXT NOT NULL,
+                object_type TEXT NOT NULL,
+                object_name TEXT NOT NULL,
+                change_type TEXT NOT NULL,
+                old_value TEXT,
+                new_value TEXT,
+                detected_at TEXT NOT NULL,
+                FOREIGN KEY (data_source_id) REFERENCES data_sources(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS metadata_snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                data_source_id INTEGER NOT NULL,
+                cache_type TEXT NOT NULL,
+                cache_key TEXT NOT NULL,
+                snapshot_data TEXT NOT NULL,
+                label TEXT,
+                taken_at TEXT NOT NULL,
+                FOREIGN KEY (data_source_id) REFERENCES data_sources(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS app_settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
         // Create indexes
         self.conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_data_sources_context_id ON data_sources(context_id)",
@@ -133,9 +350,81 @@ impl Database {
             [],
         )?;
 
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_metadata_changes_data_source_id ON metadata_changes(data_source_id, detected_at)",
+            [],
+        )?;
+
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_metadata_snapshots_lookup
+             ON metadata_snapshots(data_source_id, cache_type, cache_key, taken_at)",
+            [],
+        )?;
+
+        self.migrate_schema()?;
+
         Ok(())
     }
 
+    fn migrate_schema(&self) -> Result<()> {
+        // Columns added after the initial schema; CREATE TABLE IF NOT EXISTS won't add them
+        if !self.column_exists("data_sources", "schema_registry_config")? {
+            self.conn.execute(
+                "ALTER TABLE data_sources ADD COLUMN schema_registry_config TEXT",
+                [],
+            )?;
+        }
+
+        if !self.column_exists("data_sources", "last_used_at")? {
+            self.conn.execute(
+                "ALTER TABLE data_sources ADD COLUMN last_used_at TEXT",
+                [],
+            )?;
+        }
+
+        if !self.column_exists("data_sources", "options")? {
+            self.conn.execute(
+                "ALTER TABLE data_sources ADD COLUMN options TEXT",
+                [],
+            )?;
+        }
+
+        // Older versions stored the names as typed, e.g. "PostgreSQL" or "SOCKS5"
+        self.conn.execute(
+            "UPDATE data_sources SET data_type = LOWER(TRIM(data_type)),
+                                     proxy_type = LOWER(TRIM(proxy_type))",
+            [],
+        )?;
+        for (alias, name) in LEGACY_DATA_TYPES {
+            self.conn.execute(
+                "UPDATE data_sources SET data_type = ?2 WHERE data_type = ?1",
+                params![alias, name],
+            )?;
+        }
+        for (alias, name) in LEGACY_PROXY_TYPES {
+            self.conn.execute(
+                "UPDATE data_sources SET proxy_type = ?2 WHERE proxy_type = ?1",
+                params![alias, name],
+            )?;
+        }
+
+        self.conn.execute(
+            "UPDATE data_sources SET proxy_type = NULL WHERE proxy_type IN ('', 'none')",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    fn column_exists(&self, table: &str, column: &str) -> Result<bool> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+        Ok(exists)
+    }
+
     // Context CRUD operations
     pub fn create_context(&self, name: &str, description: Option<&str>) -> Result<i64> {
         let now = Utc::now();
@@ -189,8 +478,9 @@ impl Database {
         self.conn.execute(
             "INSERT INTO data_sources (
                 context_id, name, data_type, host, port, database, username, password,
-                proxy_type, proxy_config, ssh_config, schema_registry_url, created_at, updated_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                proxy_type, proxy_config, ssh_config, schema_registry_url, created_at, updated_at,
+                schema_registry_config, options
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
             params![
                 ds.context_id,
                 ds.name,
@@ -201,11 +491,13 @@ impl Database {
                 ds.username,
                 ds.password,
                 ds.proxy_type,
-                ds.proxy_config.as_ref().map(|v| serde_json::to_string(v).unwrap()),
-                ds.ssh_config.as_ref().map(|v| serde_json::to_string(v).unwrap()),
+                ds.proxy_config.as_ref().map(|c| serde_json::to_string(c).unwrap()),
+                ds.ssh_config.as_ref().map(|c| serde_json::to_string(c).unwrap()),
                 ds.schema_registry_url,
                 now.to_rfc3339(),
-                now.to_rfc3339()
+                now.to_rfc3339(),
+                ds.schema_registry_config.as_ref().map(|v| serde_json::to_string(v).unwrap()),
+                ds.options.as_ref().map(|o| o.to_stored()).transpose()?
             ],
         )?;
         Ok(self.conn.last_insert_rowid())
@@ -214,11 +506,13 @@ impl Database {
     pub fn list_data_sources(&self, context_id: Option<i64>) -> Result<Vec<DataSource>> {
         let query = if context_id.is_some() {
             "SELECT id, context_id, name, data_type, host, port, database, username, password,
-                    proxy_type, proxy_config, ssh_config, schema_registry_url, created_at, updated_at
+                    proxy_type, proxy_config, ssh_config, schema_registry_url, created_at, updated_at,
+                    schema_registry_config, options
              FROM data_sources WHERE context_id = ?1 ORDER BY created_at DESC"
         } else {
             "SELECT id, context_id, name, data_type, host, port, database, username, password,
-                    proxy_type, proxy_config, ssh_config, schema_registry_url, created_at, updated_at
+                    proxy_type, proxy_config, ssh_config, schema_registry_url, created_at, updated_at,
+                    schema_registry_config, options
              FROM data_sources ORDER BY created_at DESC"
         };
 
@@ -226,19 +520,62 @@ impl Database {
         
         let data_sources: Vec<DataSource> = if let Some(cid) = context_id {
             stmt.query_map(params![cid], |row| {
-                self.row_to_data_source(row)
+                self.readable_data_source(row)
             })?
+            .filter_map(Result::transpose)
             .collect::<SqliteResult<Vec<_>>>()?
         } else {
             stmt.query_map([], |row| {
-                self.row_to_data_source(row)
+                self.readable_data_source(row)
             })?
+            .filter_map(Result::transpose)
             .collect::<SqliteResult<Vec<_>>>()?
         };
         
         Ok(data_sources)
     }
 
+    // Rows saved before proxy types were typed hold '' for no proxy
+    fn proxy_type_column(row: &rusqlite::Row, idx: usize) -> SqliteResult<Option<ProxyType>> {
+        if let ValueRef::Text(b"") = row.get_ref(idx)? {
+            return Ok(None);
+        }
+        row.get(idx)
+    }
+
+    // For listings: a row whose type columns this version cannot read is left out
+    // rather than hiding every other data source behind the error
+    fn readable_data_source(&self, row: &rusqlite::Row) -> SqliteResult<Option<DataSource>> {
+        match self.row_to_data_source(row) {
+            Ok(data_source) => Ok(Some(data_source)),
+            Err(e @ rusqlite::Error::FromSqlConversionFailure(..)) => {
+                let name: String = row.get(2).unwrap_or_default();
+                eprintln!("Skipping unreadable data source {}: {}", name, e);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Like unreadable options, a config saved in a shape this version cannot read is
+    // dropped with a warning; validate() then reports it as missing
+    fn config_column<T: serde::de::DeserializeOwned>(
+        row: &rusqlite::Row,
+        idx: usize,
+        what: &str,
+    ) -> SqliteResult<Option<T>> {
+        Ok(row.get::<_, Option<String>>(idx)?
+            .filter(|s| !s.is_empty() && s != "null")
+            .and_then(|s| match serde_json::from_str(&s) {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    let name: String = row.get(2).unwrap_or_default();
+                    eprintln!("Ignoring unreadable {} of data source {}: {}", what, name, e);
+                    None
+                }
+            }))
+    }
+
     fn row_to_data_source(&self, row: &rusqlite::Row) -> SqliteResult<DataSource> {
         Ok(DataSource {
             id: row.get(0)?,
@@ -250,11 +587,9 @@ impl Database {
             database: row.get(6)?,
             username: row.get(7)?,
             password: row.get(8)?,
-            proxy_type: row.get(9)?,
-            proxy_config: row.get::<_, Option<String>>(10)?
-                .map(|s| serde_json::from_str(&s).unwrap_or(serde_json::Value::Null)),
-            ssh_config: row.get::<_, Option<String>>(11)?
-                .map(|s| serde_json::from_str(&s).unwrap_or(serde_json::Value::Null)),
+            proxy_type: Self::proxy_type_column(row, 9)?,
+            proxy_config: Self::config_column(row, 10, "proxy config")?,
+            ssh_config: Self::config_column(row, 11, "SSH config")?,
             schema_registry_url: row.get(12)?,
             created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(13)?)
                 .unwrap()
@@ -262,13 +597,25 @@ impl Database {
             updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(14)?)
                 .unwrap()
                 .with_timezone(&Utc),
+            schema_registry_config: row.get::<_, Option<String>>(15)?
+                .map(|s| serde_json::from_str(&s).unwrap_or(serde_json::Value::Null)),
+            options: row.get::<_, Option<String>>(16)?
+                .and_then(|s| match DataSourceOptions::from_stored(&s) {
+                    Ok(options) => Some(options),
+                    Err(e) => {
+                        let name: String = row.get(2).unwrap_or_default();
+                        eprintln!("Ignoring unreadable options of data source {}: {}", name, e);
+                        None
+                    }
+                }),
         })
     }
 
     pub fn get_data_source(&self, id: i64) -> Result<DataSource> {
         let mut stmt = self.conn.prepare(
             "SELECT id, context_id, name, data_type, host, port, database, username, password,
-                    proxy_type, proxy_config, ssh_config, schema_registry_url, created_at, updated_at
+                    proxy_type, proxy_config, ssh_config, schema_registry_url, created_at, updated_at,
+                    schema_registry_config, options
              FROM data_sources WHERE id = ?1"
         )?;
         
@@ -282,8 +629,9 @@ impl Database {
             "UPDATE data_sources SET
                 context_id = ?1, name = ?2, data_type = ?3, host = ?4, port = ?5,
                 database = ?6, username = ?7, password = ?8, proxy_type = ?9,
-                proxy_config = ?10, ssh_config = ?11, schema_registry_url = ?12, updated_at = ?13
-             WHERE id = ?14",
+                proxy_config = ?10, ssh_config = ?11, schema_registry_url = ?12, updated_at = ?13,
+                schema_registry_config = ?14, options = ?15
+             WHERE id = ?16",
             params![
                 ds.context_id,
                 ds.name,
@@ -294,16 +642,44 @@ impl Database {
                 ds.username,
                 ds.password,
                 ds.proxy_type,
-                ds.proxy_config.as_ref().map(|v| serde_json::to_string(v).unwrap()),
-                ds.ssh_config.as_ref().map(|v| serde_json::to_string(v).unwrap()),
+                ds.proxy_config.as_ref().map(|c| serde_json::to_string(c).unwrap()),
+                ds.ssh_config.as_ref().map(|c| serde_json::to_string(c).unwrap()),
                 ds.schema_registry_url,
                 now.to_rfc3339(),
+                ds.schema_registry_config.as_ref().map(|v| serde_json::to_string(v).unwrap()),
+                ds.options.as_ref().map(|o| o.to_stored()).transpose()?,
                 ds.id
             ],
         )?;
         Ok(())
     }
 
+    /// Marks a data source as used so background refresh picks it up.
+    pub fn touch_data_source(&self, id: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE data_sources SET last_used_at = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_recently_used_data_sources(&self, since: DateTime<Utc>) -> Result<Vec<DataSource>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, context_id, name, data_type, host, port, database, username, password,
+                    proxy_type, proxy_config, ssh_config, schema_registry_url, created_at, updated_at,
+                    schema_registry_config, options
+             FROM data_sources WHERE last_used_at >= ?1 ORDER BY last_used_at DESC"
+        )?;
+
+        let data_sources = stmt.query_map(params![since.to_rfc3339()], |row| {
+            self.readable_data_source(row)
+        })?
+        .filter_map(Result::transpose)
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(data_sources)
+    }
+
     pub fn delete_data_source(&self, id: i64) -> Result<()> {
         self.conn.execute("DELETE FROM data_sources WHERE id = ?1", params![id])?;
         Ok(())
@@ -346,13 +722,6 @@ impl Database {
                 let expires_at = expires_at_str
                     .map(|s| DateTime::parse_from_rfc3339(&s).unwrap().with_timezone(&Utc));
                 
-                // Check if expired
-                if let Some(exp) = expires_at {
-                    if exp < Utc::now() {
-                        return Err(rusqlite::Error::InvalidColumnType(6, "expired".to_string(), rusqlite::types::Type::Text));
-                    }
-                }
-                
                 Ok(MetadataCache {
                     id: row.get(0)?,
                     data_source_id: row.get(1)?,
@@ -367,6 +736,7 @@ impl Database {
             },
         );
         
+        // Expired entries are returned too; callers decide whether stale data is usable
         match result {
             Ok(cache) => Ok(Some(cache)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -374,6 +744,16 @@ impl Database {
         }
     }
 
+    /// Removes rows that expired before `before` and returns how many were deleted.
+    pub fn purge_expired_metadata_cache(&self, before: DateTime<Utc>) -> Result<usize> {
+        // RFC 3339 timestamps in UTC compare correctly as strings
+        let deleted = self.conn.execute(
+            "DELETE FROM metadata_cache WHERE expires_at IS NOT NULL AND expires_at < ?1",
+            params![before.to_rfc3339()],
+        )?;
+        Ok(deleted)
+    }
+
     pub fn delete_metadata_cache(&self, data_source_id: i64, cache_type: Option<&str>) -> Result<()> {
         if let Some(ct) = cache_type {
             self.conn.execute(
@@ -388,28 +768,281 @@ impl Database {
         }
         Ok(())
     }
-}
 
-// Global database instance
-use std::sync::Mutex;
-use once_cell::sync::Lazy;
+    // CachePolicy operations
+    pub fn set_cache_policy(
+        &self,
+        data_source_id: Option<i64>,
+        cache_type: &str,
+        ttl_seconds: Option<i64>,
+    ) -> Result<i64> {
+        let now = Utc::now();
+        let existing: Option<i64> = match self.conn.query_row(
+            "SELECT id FROM cache_policies WHERE data_source_id IS ?1 AND cache_type = ?2",
+            params![data_source_id, cache_type],
+            |row| row.get(0),
+        ) {
+            Ok(id) => Some(id),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e.into()),
+        };
 
-static DB: Lazy<Mutex<Option<Database>>> = Lazy::new(|| Mutex::new(None));
+        if let Some(id) = existing {
+            self.conn.execute(
+                "UPDATE cache_policies SET ttl_seconds = ?1, updated_at = ?2 WHERE id = ?3",
+                params![ttl_seconds, now.to_rfc3339(), id],
+            )?;
+            Ok(id)
+        } else {
+            self.conn.execute(
+                "INSERT INTO cache_policies (data_source_id, cache_type, ttl_seconds, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![data_source_id, cache_type, ttl_seconds, now.to_rfc3339()],
+            )?;
+            Ok(self.conn.last_insert_rowid())
+        }
+    }
 
-pub fn init_db() -> Result<()> {
-    let db = Database::new()?;
-    *DB.lock().unwrap() = Some(db);
-    Ok(())
-}
+    pub fn list_cache_policies(&self, data_source_id: Option<i64>) -> Result<Vec<CachePolicy>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, data_source_id, cache_type, ttl_seconds, updated_at
+             FROM cache_policies
+             WHERE ?1 IS NULL OR data_source_id IS NULL OR data_source_id = ?1
+             ORDER BY data_source_id, cache_type"
+        )?;
+
+        let policies = stmt.query_map(params![data_source_id], |row| {
+            Ok(CachePolicy {
+                id: row.get(0)?,
+                data_source_id: row.get(1)?,
+                cache_type: row.get(2)?,
+                ttl_seconds: row.get(3)?,
+                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                    .unwrap()
+                    .with_timezone(&Utc),
+            })
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
 
-pub fn get_db() -> Result<std::sync::MutexGuard<'static, Option<Database>>> {
-    let guard = DB.lock().unwrap();
-    if guard.is_none() {
-        drop(guard);
-        init_db()?;
-        Ok(DB.lock().unwrap())
-    } else {
-        Ok(guard)
+        Ok(policies)
     }
-}
 
+    /// Resolves the TTL for a cache type, most specific policy first:
+    /// (data source, type), (data source, *), (global, type), (global, *).
+    /// The outer `None` means no policy applies; the inner `None` means never expire.
+    pub fn resolve_cache_ttl(&self, data_source_id: i64, cache_type: &str) -> Result<Option<Option<i64>>> {
+        let result = self.conn.query_row(
+            "SELECT ttl_seconds FROM cache_policies
+             WHERE (data_source_id = ?1 OR data_source_id IS NULL)
+               AND (cache_type = ?2 OR cache_type = '*')
+             ORDER BY data_source_id IS NULL, cache_type = '*'
+             LIMIT 1",
+            params![data_source_id, cache_type],
+            |row| row.get::<_, Option<i64>>(0),
+        );
+
+        match result {
+            Ok(ttl) => Ok(Some(ttl)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(anyhow::anyhow!("Database error: {}", e)),
+        }
+    }
+
+    pub fn delete_cache_policy(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM cache_policies WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    // MetadataChange operations
+    pub fn record_metadata_change(&self, change: &MetadataChangeRecord) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO metadata_changes
+                (data_source_id, cache_type, cache_key, object_type, object_name,
+                 change_type, old_value, new_value, detected_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                change.data_source_id,
+                change.cache_type,
+                change.cache_key,
+                change.object_type,
+                change.object_name,
+                change.change_type,
+                change.old_value,
+                change.new_value,
+                change.detected_at.to_rfc3339()
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn list_metadata_changes(
+        &self,
+        data_source_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<MetadataChangeRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, data_source_id, cache_type, cache_key, object_type, object_name,
+                    change_type, old_value, new_value, detected_at
+             FROM metadata_changes
+             WHERE ?1 IS NULL OR data_source_id = ?1
+             ORDER BY detected_at DESC, id DESC
+             LIMIT ?2"
+        )?;
+
+        let changes = stmt.query_map(params![data_source_id, limit], |row| {
+            Ok(MetadataChangeRecord {
+                id: row.get(0)?,
+                data_source_id: row.get(1)?,
+                cache_type: row.get(2)?,
+                cache_key: row.get(3)?,
+                object_type: row.get(4)?,
+                object_name: row.get(5)?,
+                change_type: row.get(6)?,
+                old_value: row.get(7)?,
+                new_value: row.get(8)?,
+                detected_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(9)?)
+                    .unwrap()
+                    .with_timezone(&Utc),
+            })
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(changes)
+    }
+
+    pub fn clear_metadata_changes(&self, data_source_id: i64) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM metadata_changes WHERE data_source_id = ?1",
+            params![data_source_id],
+        )?;
+        Ok(())
+    }
+
+    // MetadataSnapshot operations
+    pub fn save_metadata_snapshot(&self, snapshot: &MetadataSnapshot) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO metadata_snapshots
+                (data_source_id, cache_type, cache_key, snapshot_data, label, taken_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                snapshot.data_source_id,
+                snapshot.cache_type,
+                snapshot.cache_key,
+                serde_json::to_string(&snapshot.snapshot_data)?,
+                snapshot.label,
+                snapshot.taken_at.to_rfc3339()
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn get_metadata_snapshot(&self, id: i64) -> Result<MetadataSnapshot> {
+        let snapshot = self.conn.query_row(
+            "SELECT id, data_source_id, cache_type, cache_key, snapshot_data, label, taken_at
+             FROM metadata_snapshots WHERE id = ?1",
+            params![id],
+            |row| self.row_to_metadata_snapshot(row),
+        )?;
+        Ok(snapshot)
+    }
+
+    /// Most recent snapshot of an entry taken at or before `at` (or the latest when `None`).
+    pub fn find_metadata_snapshot(
+        &self,
+        data_source_id: i64,
+        cache_type: &str,
+        cache_key: &str,
+        at: Option<DateTime<Utc>>,
+    ) -> Result<Option<MetadataSnapshot>> {
+        let result = self.conn.query_row(
+            "SELECT id, data_source_id, cache_type, cache_key, snapshot_data, label, taken_at
+             FROM metadata_snapshots
+             WHERE data_source_id = ?1 AND cache_type = ?2 AND cache_key = ?3
+               AND (?4 IS NULL OR taken_at <= ?4)
+             ORDER BY taken_at DESC, id DESC
+             LIMIT 1",
+            params![data_source_id, cache_type, cache_key, at.map(|dt| dt.to_rfc3339())],
+            |row| self.row_to_metadata_snapshot(row),
+        );
+
+        match result {
+            Ok(snapshot) => Ok(Some(snapshot)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(anyhow::anyhow!("Database error: {}", e)),
+        }
+    }
+
+    pub fn list_metadata_snapshots(
+        &self,
+        data_source_id: i64,
+        cache_type: Option<&str>,
+    ) -> Result<Vec<MetadataSnapshotInfo>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, data_source_id, cache_type, cache_key,
+                    CASE json_type(snapshot_data) WHEN 'array' THEN json_array_length(snapshot_data) ELSE 1 END,
+                    label, taken_at
+             FROM metadata_snapshots
+             WHERE data_source_id = ?1 AND (?2 IS NULL OR cache_type = ?2)
+             ORDER BY taken_at DESC, id DESC"
+        )?;
+
+        let snapshots = stmt.query_map(params![data_source_id, cache_type], |row| {
+            Ok(MetadataSnapshotInfo {
+                id: row.get(0)?,
+                data_source_id: row.get(1)?,
+                cache_type: row.get(2)?,
+                cache_key: row.get(3)?,
+                object_count: row.get(4)?,
+                label: row.get(5)?,
+                taken_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+                    .unwrap()
+                    .with_timezone(&Utc),
+            })
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(snapshots)
+    }
+
+    pub fn delete_metadata_snapshot(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM metadata_snapshots WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    fn row_to_metadata_snapshot(&self, row: &rusqlite::Row) -> SqliteResult<MetadataSnapshot> {
+        Ok(MetadataSnapshot {
+            id: row.get(0)?,
+            data_source_id: row.get(1)?,
+            cache_type: row.get(2)?,
+            cache_key: row.get(3)?,
+            snapshot_data: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or(serde_json::Value::Null),
+            label: row.get(5)?,
+            taken_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+                .unwrap()
+                .with_timezone(&Utc),
+        })
+    }
+
+    // App settings operations
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let result = self.conn.query_row(
+            "SELECT value FROM app_settings WHERE key = ?1",
+            params![key],
+            |row| row.get::<_, String>(0),
+        );
+
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(anyhow::anyhow!("Database error: {}", e)),
+        }
+    }
+
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO app_settings (key, value, updated_at) VALUES (?1, ?2, ?3)",
+            params![key, value, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+}
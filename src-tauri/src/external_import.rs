@@ -0,0 +1,883 @@
+use crate::connection::DataSourceKind;
+use crate::db::DbPool;
+use crate::secrets::expand_home;
+use crate::yaml_import::{
+    self, ImportMode, ImportReport, ImportSettings, YamlDataSource, YamlImportData,
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalFormat {
+    Dbeaver,         // data-sources.json
+    Datagrip,        // dataSources.xml (+ dataSources.local.xml for user names)
+    Pgpass,          // ~/.pgpass
+    MyCnf,           // ~/.my.cnf
+    KafkaProperties, // Kafka client .properties
+}
+
+impl ExternalFormat {
+    fn detect(path: &Path) -> Result<Self> {
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match file_name.as_str() {
+            "data-sources.json" => Ok(Self::Dbeaver),
+            ".pgpass" | "pgpass.conf" => Ok(Self::Pgpass),
+            ".my.cnf" | "my.cnf" | "my.ini" => Ok(Self::MyCnf),
+            name if name.starts_with("datasources") && name.ends_with(".xml") => Ok(Self::Datagrip),
+            name if name.ends_with(".properties") => Ok(Self::KafkaProperties),
+            _ => Err(anyhow::anyhow!(
+                "Cannot tell the format of {}; pass it explicitly",
+                path.display()
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalImportPreview {
+    pub format: ExternalFormat,
+    pub data_sources: Vec<YamlDataSource>, // Passwords are masked
+    pub warnings: Vec<String>,
+    pub report: ImportReport, // Dry run against the target context
+}
+
+/// Where and how the entries of a file are imported; all optional.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExternalImportOptions {
+    pub format: Option<ExternalFormat>, // Detected from the file name when missing
+    pub context_id: Option<i64>,
+    pub context_name: Option<String>,
+    pub mode: Option<ImportMode>,
+    pub allow_secret_commands: Option<bool>,
+}
+
+impl ExternalImportOptions {
+    fn settings(&self, dry_run: bool) -> ImportSettings {
+        ImportSettings {
+            mode: self.mode.unwrap_or_default(),
+            dry_run,
+            allow_commands: self.allow_secret_commands.unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Parsed {
+    data_sources: Vec<YamlDataSource>,
+    warnings: Vec<String>,
+}
+
+/// Parses a file from another tool and shows what importing it would do.
+#[tauri::command]
+pub async fn preview_external_import(
+    db: tauri::State<'_, DbPool>,
+    file_path: String,
+    options: ExternalImportOptions,
+) -> Result<ExternalImportPreview, String> {
+    let (format, parsed) = parse_file(file_path, options.format)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (context_id, settings) = (options.context_id, options.settings(true));
+    let import_data = YamlImportData {
+        context_id: None,
+        context_name: options.context_name,
+        data_sources: parsed.data_sources.clone(),
+    };
+    let report = db
+        .run(move |db| yaml_import::import_into(db, import_data, context_id, settings, Vec::new()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let data_sources = parsed
+        .data_sources
+        .into_iter()
+        .map(|mut ds| {
+            if !ds.password.is_empty() {
+                ds.password = "********".to_string();
+            }
+            ds
+        })
+        .collect();
+
+    Ok(ExternalImportPreview {
+        format,
+        data_sources,
+        warnings: parsed.warnings,
+        report,
+    })
+}
+
+/// Imports the previewed file; `names` limits the import to the entries picked in the preview.
+#[tauri::command]
+pub async fn import_external_connections(
+    db: tauri::State<'_, DbPool>,
+    file_path: String,
+    options: ExternalImportOptions,
+    names: Option<Vec<String>>,
+) -> Result<ImportReport, String> {
+    let (_, parsed) = parse_file(file_path, options.format)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut data_sources = parsed.data_sources;
+    if let Some(names) = names {
+        let names: HashSet<String> = names.into_iter().collect();
+        data_sources.retain(|ds| names.contains(&ds.name));
+    }
+
+    let (context_id, settings) = (options.context_id, options.settings(false));
+    let import_data = YamlImportData {
+        context_id: None,
+        context_name: options.context_name,
+        data_sources,
+    };
+    db.run(move |db| yaml_import::import_into(db, import_data, context_id, settings, Vec::new()))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn parse_file(
+    file_path: String,
+    format: Option<ExternalFormat>,
+) -> Result<(ExternalFormat, Parsed)> {
+    tokio::task::spawn_blocking(move || {
+        let path = expand_home(&file_path);
+        let format = match format {
+            Some(format) => format,
+            None => ExternalFormat::detect(&path)?,
+        };
+        let content =
+            fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+
+        let mut parsed = match format {
+            ExternalFormat::Dbeaver => parse_dbeaver(&content)?,
+            ExternalFormat::Datagrip => parse_datagrip(&content, &path)?,
+            ExternalFormat::Pgpass => parse_pgpass(&content),
+            ExternalFormat::MyCnf => parse_my_cnf(&content),
+            ExternalFormat::KafkaProperties => parse_kafka_properties(&content, &path),
+        };
+        dedupe_names(&mut parsed.data_sources);
+        Ok((format, parsed))
+    })
+    .await
+    .context("Import task panicked")?
+}
+
+fn parse_dbeaver(content: &str) -> Result<Parsed> {
+    let root: Value = serde_json::from_str(content).context("Invalid DBeaver data-sources.json")?;
+    let connections = root
+        .get("connections")
+        .and_then(|c| c.as_object())
+        .context("No connections found in data-sources.json")?;
+
+    let mut parsed = Parsed::default();
+    for (id, conn) in connections {
+        let name = str_field(conn, "name").unwrap_or(id).to_string();
+        let provider = str_field(conn, "provider").unwrap_or_default();
+        let driver = str_field(conn, "driver").unwrap_or_default();
+        let data_type = match dbeaver_data_type(provider, driver) {
+            Some(data_type) => data_type,
+            None => {
+                parsed.warnings.push(format!(
+                    "{}: skipped, DBeaver provider '{}' is not supported",
+                    name, provider
+                ));
+                continue;
+            }
+        };
+
+        let config = conn.get("configuration").unwrap_or(&Value::Null);
+        let jdbc = str_field(config, "url").and_then(parse_jdbc_url);
+        let host = str_field(config, "host")
+            .map(|h| h.to_string())
+            .or_else(|| jdbc.as_ref().map(|j| j.host.clone()))
+            .unwrap_or_else(|| "localhost".to_string());
+        let port = port_field(config, "port")
+            .or_else(|| jdbc.as_ref().and_then(|j| j.port))
+            .unwrap_or_else(|| default_port(data_type));
+        let database = str_field(config, "database")
+            .map(|d| d.to_string())
+            .or_else(|| jdbc.as_ref().and_then(|j| j.database.clone()));
+        let password = str_field(config, "password")
+            .unwrap_or_default()
+            .to_string();
+        if password.is_empty() && conn.get("save-password").and_then(|v| v.as_bool()) == Some(true)
+        {
+            parsed.warnings.push(format!(
+                "{}: the saved password is encrypted in DBeaver's credentials-config.json and was not imported",
+                name
+            ));
+        }
+
+        let mut ds = new_data_source(
+            name.clone(),
+            data_type,
+            host,
+            port,
+            database.filter(|d| !d.is_empty()),
+            str_field(config, "user").unwrap_or_default().to_string(),
+            password,
+        );
+
+        // Network handlers: SSH tunnels and SOCKS proxies
+        if let Some(handlers) = config.get("handlers").and_then(|h| h.as_object()) {
+            for (handler_id, handler) in handlers {
+                if handler.get("enabled").and_then(|v| v.as_bool()) != Some(true) {
+                    continue;
+                }
+                let props = handler.get("properties").unwrap_or(&Value::Null);
+                match handler_id.as_str() {
+                    "ssh_tunnel" => {
+                        let ssh = json!({
+                            "host": str_field(props, "host").unwrap_or_default(),
+                            "port": port_field(props, "port").unwrap_or(22),
+                            "username": str_field(handler, "user")
+                                .or_else(|| str_field(props, "user"))
+                                .unwrap_or_default(),
+                            "password": str_field(handler, "password"),
+                            "private_key_path": str_field(props, "keyPath"),
+                            // Unset (0) when DBeaver was left to pick a port itself
+                            "local_port": port_field(props, "localPort").unwrap_or(0),
+                        });
+                        ds.proxy_type = Some("ssh".to_string());
+                        ds.ssh_config = serde_yaml::to_value(&ssh).ok();
+                    }
+                    "socks_proxy" => {
+                        let proxy = json!({
+                            "proxy_type": "socks5",
+                            "host": str_field(props, "socks-host").unwrap_or_default(),
+                            "port": port_field(props, "socks-port").unwrap_or(1080),
+                            "username": str_field(handler, "user"),
+                            "password": str_field(handler, "password"),
+                        });
+                        ds.proxy_type = Some("socks5".to_string());
+                        ds.proxy_config = serde_yaml::to_value(&proxy).ok();
+                    }
+                    other => parsed.warnings.push(format!(
+                        "{}: network handler '{}' is not supported and was ignored",
+                        name, other
+                    )),
+                }
+            }
+        }
+
+        parsed.data_sources.push(ds);
+    }
+
+    Ok(parsed)
+}
+
+fn dbeaver_data_type(provider: &str, driver: &str) -> Option<&'static str> {
+    match (provider, driver) {
+        ("mysql", _) | (_, "mariadb") => Some("mysql"),
+        ("postgresql", _) => Some("postgresql"),
+        ("sqlserver", _) | ("mssql", _) => Some("sqlserver"),
+        ("generic", driver) if driver.contains("postgres") => Some("postgresql"),
+        _ => None,
+    }
+}
+
+/// DataGrip keeps user names in `dataSources.local.xml` next to the shared file,
+/// keyed by data source uuid; it is merged in when present.
+fn parse_datagrip(content: &str, path: &Path) -> Result<Parsed> {
+    let doc = roxmltree::Document::parse(content).context("Invalid DataGrip dataSources.xml")?;
+
+    let local_users = path
+        .parent()
+        .map(|dir| dir.join("dataSources.local.xml"))
+        .filter(|local| local.as_path() != path)
+        .and_then(|local| fs::read_to_string(local).ok())
+        .map(|local| datagrip_local_users(&local))
+        .unwrap_or_default();
+
+    let mut parsed = Parsed::default();
+    for node in doc.descendants().filter(|n| n.has_tag_name("data-source")) {
+        let name = node.attribute("name").unwrap_or("datagrip").to_string();
+        let child_text = |tag: &str| {
+            node.children()
+                .find(|c| c.has_tag_name(tag))
+                .and_then(|c| c.text())
+                .map(|t| t.trim().to_string())
+        };
+
+        let url = match child_text("jdbc-url") {
+            Some(url) => url,
+            None => {
+                parsed
+                    .warnings
+                    .push(format!("{}: skipped, no JDBC URL", name));
+                continue;
+            }
+        };
+        let jdbc = match parse_jdbc_url(&url) {
+            Some(jdbc) => jdbc,
+            None => {
+                parsed
+                    .warnings
+                    .push(format!("{}: skipped, unsupported JDBC URL {}", name, url));
+                continue;
+            }
+        };
+
+        let username = child_text("user-name")
+            .or_else(|| {
+                node.attribute("uuid")
+                    .and_then(|uuid| local_users.get(uuid).cloned())
+            })
+            .unwrap_or_default();
+        parsed.warnings.push(format!(
+            "{}: DataGrip keeps passwords in the system keychain; set it after importing",
+            name
+        ));
+
+        parsed.data_sources.push(new_data_source(
+            name,
+            jdbc.data_type,
+            jdbc.host,
+            jdbc.port.unwrap_or_else(|| default_port(jdbc.data_type)),
+            jdbc.database,
+            username,
+            String::new(),
+        ));
+    }
+
+    Ok(parsed)
+}
+
+fn datagrip_local_users(content: &str) -> HashMap<String, String> {
+    let doc = match roxmltree::Document::parse(content) {
+        Ok(doc) => doc,
+        Err(_) => return HashMap::new(),
+    };
+
+    doc.descendants()
+        .filter(|n| n.has_tag_name("data-source"))
+        .filter_map(|n| {
+            let uuid = n.attribute("uuid")?;
+            let user = n.children().find(|c| c.has_tag_name("user-name"))?.text()?;
+            Some((uuid.to_string(), user.trim().to_string()))
+        })
+        .collect()
+}
+
+/// `hostname:port:database:username:password`, with `*` wildcards and `\:` escapes.
+fn parse_pgpass(content: &str) -> Parsed {
+    let mut parsed = Parsed::default();
+
+    for (line_no, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields = split_pgpass_line(line);
+        if fields.len() != 5 {
+            parsed.warnings.push(format!(
+                ".pgpass line {}: expected 5 fields, skipped",
+                line_no + 1
+            ));
+            continue;
+        }
+
+        let (host, port, database, username, password) =
+            (&fields[0], &fields[1], &fields[2], &fields[3], &fields[4]);
+        if host == "*" || username == "*" {
+            parsed.warnings.push(format!(
+                ".pgpass line {}: wildcard host or user cannot be imported, skipped",
+                line_no + 1
+            ));
+            continue;
+        }
+
+        let port = if port == "*" {
+            default_port("postgresql")
+        } else {
+            match port.parse() {
+                Ok(port) => port,
+                Err(_) => {
+                    parsed.warnings.push(format!(
+                        ".pgpass line {}: invalid port '{}', skipped",
+                        line_no + 1,
+                        port
+                    ));
+                    continue;
+                }
+            }
+        };
+        let database = Some(database.clone()).filter(|d| d != "*");
+
+        parsed.data_sources.push(new_data_source(
+            format!(
+                "{}@{}:{}/{}",
+                username,
+                host,
+                port,
+                database.as_deref().unwrap_or("*")
+            ),
+            "postgresql",
+            host.clone(),
+            port,
+            database,
+            username.clone(),
+            password.clone(),
+        ));
+    }
+
+    parsed
+}
+
+fn split_pgpass_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    fields.last_mut().unwrap().push(next);
+                }
+            }
+            ':' => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Client option groups from a MySQL option file. `[client]` applies to every
+/// client, so its values are the defaults for the other groups.
+fn parse_my_cnf(content: &str) -> Parsed {
+    let mut groups: BTreeMap<String, HashMap<String, String>> = BTreeMap::new();
+    let mut current: Option<String> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with(';')
+            || line.starts_with('!')
+        {
+            continue;
+        }
+        if let Some(group) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(group.trim().to_lowercase());
+            continue;
+        }
+        if let (Some(group), Some((key, value))) = (&current, line.split_once('=')) {
+            let key = key.trim().replace('-', "_").to_lowercase();
+            let value = value
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string();
+            groups.entry(group.clone()).or_default().insert(key, value);
+        }
+    }
+
+    let client = groups.get("client").cloned().unwrap_or_default();
+    let mut parsed = Parsed::default();
+    for (group, options) in &groups {
+        // Server groups configure mysqld, not connections
+        if group.starts_with("mysqld") || group.starts_with("server") || group == "mariadb" {
+            continue;
+        }
+        if group != "client" && !options.contains_key("host") && !options.contains_key("user") {
+            continue;
+        }
+
+        let mut merged = client.clone();
+        merged.extend(options.clone());
+
+        let port = match merged.get("port").map(|p| p.parse::<u16>()) {
+            Some(Ok(port)) => port,
+            Some(Err(_)) => {
+                parsed
+                    .warnings
+                    .push(format!("[{}]: invalid port, skipped", group));
+                continue;
+            }
+            None => default_port("mysql"),
+        };
+        if merged.contains_key("socket") && !merged.contains_key("host") {
+            parsed.warnings.push(format!(
+                "[{}]: socket connections are not supported, using localhost",
+                group
+            ));
+        }
+
+        parsed.data_sources.push(new_data_source(
+            format!("my.cnf [{}]", group),
+            "mysql",
+            merged
+                .get("host")
+                .cloned()
+                .unwrap_or_else(|| "localhost".to_string()),
+            port,
+            merged.get("database").cloned(),
+            merged.get("user").cloned().unwrap_or_default(),
+            merged.get("password").cloned().unwrap_or_default(),
+        ));
+    }
+
+    parsed
+}
+
+fn parse_kafka_properties(content: &str, path: &Path) -> Parsed {
+    let props: HashMap<String, String> = content
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with('!'))
+        .filter_map(|l| {
+            let (key, value) = l.split_once('=').or_else(|| l.split_once(':'))?;
+            Some((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+
+    let mut parsed = Parsed::default();
+    let name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("kafka")
+        .to_string();
+
+    let servers: Vec<&str> = props
+        .get("bootstrap.servers")
+        .map(|s| {
+            s.split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    let Some(first) = servers.first() else {
+        parsed
+            .warnings
+            .push(format!("{}: no bootstrap.servers, nothing to import", name));
+        return parsed;
+    };
+    if servers.len() > 1 {
+        parsed.warnings.push(format!(
+            "{}: only the first bootstrap server ({}) is used",
+            name, first
+        ));
+    }
+    let (host, port) = match first.rsplit_once(':') {
+        Some((host, port)) => (
+            host.to_string(),
+            port.parse().unwrap_or(default_port("kafka")),
+        ),
+        None => (first.to_string(), default_port("kafka")),
+    };
+
+    // SASL credentials live in the JAAS config: ... username="x" password="y";
+    let jaas = props
+        .get("sasl.jaas.config")
+        .map(|s| s.as_str())
+        .unwrap_or_default();
+    let mut ds = new_data_source(
+        name,
+        "kafka",
+        host,
+        port,
+        None,
+        jaas_option(jaas, "username").unwrap_or_default(),
+        jaas_option(jaas, "password").unwrap_or_default(),
+    );
+
+    if let Some(url) = props.get("schema.registry.url") {
+        ds.schema_registry_url = Some(url.clone());
+        if let Some((user, password)) = props
+            .get("basic.auth.user.info")
+            .and_then(|u| u.split_once(':'))
+        {
+            ds.schema_registry_config = serde_yaml::to_value(json!({
+                "auth_type": "basic",
+                "username": user,
+                "password": password,
+            }))
+            .ok();
+        }
+    }
+
+    parsed.data_sources.push(ds);
+    parsed
+}
+
+fn jaas_option(jaas: &str, key: &str) -> Option<String> {
+    let start = jaas.find(&format!("{}=\"", key))? + key.len() + 2;
+    let end = jaas[start..].find('"')?;
+    Some(jaas[start..start + end].to_string())
+}
+
+struct JdbcUrl {
+    data_type: &'static str,
+    host: String,
+    port: Option<u16>,
+    database: Option<String>,
+}
+
+/// Handles `jdbc:<driver>://host[:port][/database][?...]` and SQL Server's
+/// `jdbc:sqlserver://host[:port];databaseName=...`.
+fn parse_jdbc_url(url: &str) -> Option<JdbcUrl> {
+    let rest = url.strip_prefix("jdbc:")?;
+    let (driver, rest) = rest.split_once("://")?;
+    let data_type = match driver {
+        "mysql" | "mariadb" => "mysql",
+        "postgresql" => "postgresql",
+        "sqlserver" => "sqlserver",
+        _ => return None,
+    };
+
+    let (address, database) = if data_type == "sqlserver" {
+        let mut parts = rest.split(';');
+        let address = parts.next().unwrap_or_default();
+        let database = parts
+            .filter_map(|p| p.split_once('='))
+            .find(|(k, _)| {
+                k.eq_ignore_ascii_case("databaseName") || k.eq_ignore_ascii_case("database")
+            })
+            .map(|(_, v)| v.to_string());
+        (address, database)
+    } else {
+        let rest = rest.split('?').next().unwrap_or_default();
+        match rest.split_once('/') {
+            Some((address, db)) => (address, Some(db.to_string()).filter(|d| !d.is_empty())),
+            None => (rest, None),
+        }
+    };
+
+    // Only the first host of a multi-host URL is used
+    let address = address.split(',').next().unwrap_or_default();
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().ok()),
+        None => (address, None),
+    };
+    // SQL Server named instances: host\instance
+    let host = host.split('\\').next().unwrap_or(host);
+
+    Some(JdbcUrl {
+        data_type,
+        host: if host.is_empty() {
+            "localhost".to_string()
+        } else {
+            host.to_string()
+        },
+        port,
+        database,
+    })
+}
+
+fn new_data_source(
+    name: String,
+    data_type: &str,
+    host: String,
+    port: u16,
+    database: Option<String>,
+    username: String,
+    password: String,
+) -> YamlDataSource {
+    YamlDataSource {
+        name,
+        data_type: data_type.to_string(),
+        host,
+        port,
+        database,
+        username,
+        password,
+        proxy_type: None,
+        proxy_config: None,
+        ssh_config: None,
+        schema_registry_url: None,
+        schema_registry_config: None,
+        options: None,
+    }
+}
+
+fn default_port(data_type: &str) -> u16 {
+    data_type
+        .parse::<DataSourceKind>()
+        .ok()
+        .and_then(|kind| kind.default_port())
+        .unwrap_or(0)
+}
+
+/// Imports match on name, so names generated from several entries must not collide.
+fn dedupe_names(data_sources: &mut [YamlDataSource]) {
+    let mut seen = HashSet::new();
+    for ds in data_sources.iter_mut() {
+        let base = ds.name.clone();
+        let mut n = 2;
+        while !seen.insert(ds.name.clone()) {
+            ds.name = format!("{} ({})", base, n);
+            n += 1;
+        }
+    }
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+}
+
+// DBeaver writes ports as strings in some versions and numbers in others
+fn port_field(value: &Value, key: &str) -> Option<u16> {
+    match value.get(key)? {
+        Value::Number(n) => n.as_u64().and_then(|n| u16::try_from(n).ok()),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jdbc_urls() {
+        let mysql = parse_jdbc_url("jdbc:mysql://db.example.com:3307/shop?useSSL=true").unwrap();
+        assert_eq!(mysql.data_type, "mysql");
+        assert_eq!(mysql.host, "db.example.com");
+        assert_eq!(mysql.port, Some(3307));
+        assert_eq!(mysql.database.as_deref(), Some("shop"));
+
+        let postgres = parse_jdbc_url("jdbc:postgresql://pg1:5432,pg2:5432/app").unwrap();
+        assert_eq!(postgres.data_type, "postgresql");
+        assert_eq!(postgres.host, "pg1");
+        assert_eq!(postgres.port, Some(5432));
+        assert_eq!(postgres.database.as_deref(), Some("app"));
+
+        let sqlserver =
+            parse_jdbc_url("jdbc:sqlserver://sql\\SQLEXPRESS:1433;encrypt=true;databaseName=crm")
+                .unwrap();
+        assert_eq!(sqlserver.data_type, "sqlserver");
+        assert_eq!(sqlserver.host, "sql");
+        assert_eq!(sqlserver.port, Some(1433));
+        assert_eq!(sqlserver.database.as_deref(), Some("crm"));
+
+        let no_host = parse_jdbc_url("jdbc:mariadb:///").unwrap();
+        assert_eq!(no_host.host, "localhost");
+        assert_eq!(no_host.port, None);
+        assert_eq!(no_host.database, None);
+
+        assert!(parse_jdbc_url("jdbc:oracle:thin:@host:1521:orcl").is_none());
+        assert!(parse_jdbc_url("postgresql://host/db").is_none());
+    }
+
+    #[test]
+    fn pgpass_lines() {
+        assert_eq!(
+            split_pgpass_line("db.local:5432:app:alice:s3cr\\:et"),
+            ["db.local", "5432", "app", "alice", "s3cr:et"]
+        );
+        assert_eq!(
+            split_pgpass_line("h:*:*:u:back\\\\slash"),
+            ["h", "*", "*", "u", "back\\slash"]
+        );
+        assert_eq!(split_pgpass_line("too:few").len(), 2);
+
+        let parsed =
+            parse_pgpass("# comment\n*:5432:app:alice:pw\nh:x:app:alice:pw\nh:*:*:bob:pw\n");
+        assert_eq!(parsed.warnings.len(), 2);
+        assert_eq!(parsed.data_sources.len(), 1);
+        let ds = &parsed.data_sources[0];
+        assert_eq!(ds.port, 5432);
+        assert_eq!(ds.database, None);
+        assert_eq!(ds.username, "bob");
+    }
+
+    #[test]
+    fn my_cnf_groups() {
+        let parsed = parse_my_cnf(
+            "[client]\nuser = app\npassword = \"pw\"\n\n[mysqld]\nport = 3307\n\n[client-reporting]\nhost=reports.local\nport=3308\n",
+        );
+        assert_eq!(parsed.data_sources.len(), 2);
+
+        let client = &parsed.data_sources[0];
+        assert_eq!(client.name, "my.cnf [client]");
+        assert_eq!(client.host, "localhost");
+        assert_eq!(client.port, 3306);
+        assert_eq!(client.password, "pw");
+
+        let reporting = &parsed.data_sources[1];
+        assert_eq!(reporting.host, "reports.local");
+        assert_eq!(reporting.port, 3308);
+        assert_eq!(reporting.username, "app");
+    }
+
+    #[test]
+    fn jaas_options() {
+        let jaas = r#"org.apache.kafka.common.security.plain.PlainLoginModule required username="alice" password="p w";"#;
+        assert_eq!(jaas_option(jaas, "username").as_deref(), Some("alice"));
+        assert_eq!(jaas_option(jaas, "password").as_deref(), Some("p w"));
+        assert_eq!(jaas_option(jaas, "token"), None);
+        assert_eq!(jaas_option("", "username"), None);
+    }
+
+    #[test]
+    fn dbeaver_ssh_tunnel_keeps_the_local_port() {
+        let content = r#"{
+            "connections": {
+                "postgres-1": {
+                    "provider": "postgresql",
+                    "name": "prod",
+                    "configuration": {
+                        "host": "10.0.0.5",
+                        "port": "5432",
+                        "database": "app",
+                        "user": "alice",
+                        "handlers": {
+                            "ssh_tunnel": {
+                                "enabled": true,
+                                "user": "ops",
+                                "properties": { "host": "bastion", "port": 22, "localPort": "15432" }
+                            }
+                        }
+                    }
+                },
+                "postgres-2": {
+                    "provider": "postgresql",
+                    "name": "staging",
+                    "configuration": {
+                        "host": "10.0.0.6",
+                        "port": "5432",
+                        "handlers": {
+                            "ssh_tunnel": {
+                                "enabled": true,
+                                "properties": { "host": "bastion", "port": 22 }
+                            }
+                        }
+                    }
+                }
+            }
+        }"#;
+        let parsed = parse_dbeaver(content).unwrap();
+        let prod = parsed
+            .data_sources
+            .iter()
+            .find(|ds| ds.name == "prod")
+            .unwrap();
+        assert_eq!(prod.proxy_type.as_deref(), Some("ssh"));
+        let ssh = prod.ssh_config.as_ref().unwrap();
+        assert_eq!(ssh["local_port"].as_u64(), Some(15432));
+        assert_eq!(ssh["host"].as_str(), Some("bastion"));
+
+        let staging = parsed
+            .data_sources
+            .iter()
+            .find(|ds| ds.name == "staging")
+            .unwrap();
+        let ssh = staging.ssh_config.as_ref().unwrap();
+        assert_eq!(ssh["local_port"].as_u64(), Some(0));
+    }
+}
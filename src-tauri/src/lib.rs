@@ -1,21 +1,74 @@
 mod db;
 mod connection;
+mod connector;
 mod metadata;
 mod cache;
 mod commands;
 mod yaml_import;
+mod yaml_export;
+mod external_import;
+mod refresh;
+mod snapshot;
+mod offline;
+mod secrets;
 
-use db::init_db;
+use db::{DatabaseLocation, DbPool};
+use tauri::Manager;
+
+/// The app is unusable without its local store, so report the failure and exit
+/// instead of starting with every command failing.
+fn fatal_startup_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Error)
+        .set_title("Unable to start")
+        .set_description(message)
+        .set_buttons(rfd::MessageButtons::Ok)
+        .show();
+    std::process::exit(1);
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialize database
-    if let Err(e) = init_db() {
-        eprintln!("Failed to initialize database: {}", e);
-    }
-
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .setup(|app| {
+            let location = match DatabaseLocation::resolve(app.handle()) {
+                Ok(location) => location,
+                Err(e) => fatal_startup_error(&format!("Failed to locate the local database: {:#}", e)),
+            };
+            if let Some(ref legacy) = location.migrated_from {
+                eprintln!("Migrated database from {} to {}", legacy.display(), location.path.display());
+            }
+
+            let path = location.path.clone();
+            let db = match DbPool::open(location) {
+                Ok(db) => db,
+                Err(e) => fatal_startup_error(&format!(
+                    "Failed to open the local database at {}: {:#}",
+                    path.display(),
+                    e
+                )),
+            };
+
+            match db.get() {
+                Ok(conn) => {
+                    // Drop metadata that expired long enough ago that it is no longer worth serving stale
+                    if let Err(e) = cache::CacheManager::purge_expired(&conn) {
+                        eprintln!("Failed to purge expired metadata cache: {}", e);
+                    }
+                    if let Err(e) = offline::load(&conn) {
+                        eprintln!("Failed to load offline mode setting: {}", e);
+                    }
+                }
+                Err(e) => fatal_startup_error(&format!("Failed to open the local database: {:#}", e)),
+            }
+
+            app.manage(db.clone());
+            cache::CacheManager::init(app.handle().clone());
+            refresh::RefreshScheduler::start(app.handle().clone(), db);
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Context commands
             commands::create_context,
@@ -28,17 +81,54 @@ pub fn run() {
             commands::get_data_source,
             commands::update_data_source,
             commands::delete_data_source,
+            commands::get_data_source_schemas,
             commands::test_connection,
+            commands::get_connector_capabilities,
+            commands::execute_query,
+            // Local store commands
+            commands::get_database_location,
             // Metadata commands
             commands::get_tables,
             commands::get_table_structure,
             commands::get_kafka_topics,
+            commands::list_databases,
+            commands::list_schemas,
+            commands::get_postgres_objects,
+            commands::scan_redis_keys,
+            commands::get_redis_value,
+            commands::get_redis_info,
             commands::get_schema_registry_schemas,
+            commands::get_schema_versions,
+            commands::diff_schema_versions,
+            commands::get_schema_compatibility,
+            commands::test_schema_compatibility,
             commands::refresh_metadata,
+            // Change detection commands
+            commands::detect_metadata_changes,
+            commands::list_metadata_changes,
+            commands::clear_metadata_changes,
+            // Snapshot commands
+            commands::take_metadata_snapshot,
+            commands::list_metadata_snapshots,
+            commands::delete_metadata_snapshot,
+            commands::diff_metadata_snapshots,
+            commands::diff_metadata_since,
+            // Offline mode commands
+            commands::get_offline_mode,
+            commands::set_offline_mode,
+            // Cache policy commands
+            commands::set_cache_policy,
+            commands::list_cache_policies,
+            commands::delete_cache_policy,
+            commands::purge_expired_cache,
             // Comparison command
             commands::compare_tables,
             // YAML import
             yaml_import::import_data_sources_from_yaml,
+            external_import::preview_external_import,
+            external_import::import_external_connections,
+            // Export
+            yaml_export::export_context,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
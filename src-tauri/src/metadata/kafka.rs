@@ -1,10 +1,8 @@
 use crate::connection::kafka::KafkaConnector;
 use crate::db::DataSource;
-use crate::metadata::{KafkaTopicInfo, PartitionInfo, SchemaInfo};
+use crate::metadata::{KafkaTopicInfo, PartitionInfo};
 use anyhow::{Context, Result};
 use std::time::Duration;
-use reqwest::Client as HttpClient;
-use serde_json::Value;
 
 pub struct KafkaMetadata;
 
@@ -69,69 +67,4 @@ impl KafkaMetadata {
         // In production, you'd query Kafka's __consumer_offsets topic or use AdminClient
         Ok(Vec::new())
     }
-
-    pub async fn get_schema_registry_schemas(
-        data_source: &DataSource,
-    ) -> Result<Vec<SchemaInfo>> {
-        let registry_url = data_source.schema_registry_url.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Schema Registry URL not configured"))?;
-        
-        let client = HttpClient::new();
-        let url = format!("{}/subjects", registry_url.trim_end_matches('/'));
-        
-        let response = client
-            .get(&url)
-            .send()
-            .await
-            .context("Failed to fetch subjects from Schema Registry")?;
-        
-        let subjects: Vec<String> = response
-            .json()
-            .await
-            .context("Failed to parse Schema Registry response")?;
-        
-        let mut schemas = Vec::new();
-        
-        for subject in subjects {
-            // Get latest version
-            let version_url = format!("{}/subjects/{}/versions/latest", registry_url.trim_end_matches('/'), subject);
-            let version_response = client
-                .get(&version_url)
-                .send()
-                .await
-                .context(format!("Failed to fetch schema for subject: {}", subject))?;
-            
-            if version_response.status().is_success() {
-                let schema_data: Value = version_response
-                    .json()
-                    .await
-                    .context("Failed to parse schema data")?;
-                
-                let version = schema_data.get("version")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(0) as i32;
-                
-                let schema_type = schema_data.get("schemaType")
-                    .or_else(|| schema_data.get("schema_type"))
-                    .and_then(|s| s.as_str())
-                    .unwrap_or("AVRO")
-                    .to_string();
-                
-                let schema = schema_data.get("schema")
-                    .and_then(|s| s.as_str())
-                    .unwrap_or("")
-                    .to_string();
-                
-                schemas.push(SchemaInfo {
-                    subject,
-                    version,
-                    schema_type,
-                    schema,
-                });
-            }
-        }
-        
-        Ok(schemas)
-    }
 }
-
@@ -1,11 +1,13 @@
+use crate::connector::{connector_for, Capability};
 use crate::db::{DataSource, MetadataCache};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 pub mod mysql;
 pub mod postgresql;
 pub mod sqlserver;
+pub mod schema_registry;
 #[cfg(feature = "kafka")]
 pub mod kafka;
 
@@ -15,6 +17,9 @@ pub struct TableInfo {
     pub schema: Option<String>,
     pub row_count: Option<i64>,
     pub columns: Vec<ColumnInfo>,
+    /// Engine specific attributes, e.g. the engine and sorting key of a ClickHouse table.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +29,9 @@ pub struct ColumnInfo {
     pub is_nullable: bool,
     pub default_value: Option<String>,
     pub constraints: Vec<String>,
+    /// Engine specific attributes, e.g. how often a sampled MongoDB field was present.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +51,15 @@ pub struct StructureDiff {
     pub source2_value: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaChange {
+    pub object_type: String, // table, column, topic
+    pub object_name: String,
+    pub change_type: String, // added, removed, modified
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KafkaTopicInfo {
     pub name: String,
@@ -58,24 +75,131 @@ pub struct PartitionInfo {
     pub isr: Vec<i32>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisKeyInfo {
+    pub key: String,
+    pub key_type: String, // string, hash, list, set, zset, stream or a module type
+    pub ttl_ms: Option<i64>, // None when the key does not expire
+    pub memory_bytes: Option<u64>, // None when MEMORY USAGE is unavailable
+}
+
+/// One SCAN step; a `cursor` of 0 means the whole keyspace has been visited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisKeyPage {
+    pub cursor: u64,
+    pub keys: Vec<RedisKeyInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisValue {
+    pub key: RedisKeyInfo,
+    pub length: Option<u64>, // bytes of a string, entries of anything else
+    pub value: serde_json::Value,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisInfoSection {
+    pub name: String,
+    pub entries: BTreeMap<String, String>,
+}
+
+/// Schema objects of a PostgreSQL database besides its tables.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PostgresObjects {
+    pub enums: Vec<PgEnum>,
+    pub domains: Vec<PgDomain>,
+    pub composite_types: Vec<PgCompositeType>,
+    pub extensions: Vec<PgExtension>,
+    pub sequences: Vec<PgSequence>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PgEnum {
+    pub schema: String,
+    pub name: String,
+    pub labels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PgDomain {
+    pub schema: String,
+    pub name: String,
+    pub base_type: String,
+    pub is_nullable: bool,
+    pub default_value: Option<String>,
+    pub check: Option<String>, // CHECK constraints joined with AND
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PgCompositeType {
+    pub schema: String,
+    pub name: String,
+    pub attributes: Vec<ColumnInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PgExtension {
+    pub name: String,
+    pub version: String,
+    pub schema: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PgSequence {
+    pub schema: String,
+    pub name: String,
+    pub data_type: String,
+    pub start_value: i64,
+    pub min_value: i64,
+    pub max_value: i64,
+    pub increment: i64,
+    pub cycle: bool,
+    pub last_value: Option<i64>, // None until first used, or without USAGE privilege
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchemaInfo {
     pub subject: String,
     pub version: i32,
+    #[serde(default)]
+    pub id: Option<i32>,
     pub schema_type: String,
     pub schema: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    pub subject: String,
+    pub version1: i32,
+    pub version2: i32,
+    pub schema_type: String,
+    pub diffs: Vec<StructureDiff>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompatibilityLevel {
+    pub subject: Option<String>,
+    pub level: String, // BACKWARD, FORWARD, FULL, NONE, *_TRANSITIVE
+    pub is_global_default: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompatibilityCheckResult {
+    pub subject: String,
+    pub version: String,
+    pub is_compatible: bool,
+    pub messages: Vec<String>,
+}
+
 pub struct MetadataFetcher;
 
 impl MetadataFetcher {
     pub async fn get_tables(data_source: &DataSource) -> Result<Vec<TableInfo>> {
-        match data_source.data_type.as_str() {
-            "mysql" => mysql::MySQLMetadata::get_tables(data_source).await,
-            "postgresql" => postgresql::PostgreSQLMetadata::get_tables(data_source).await,
-            "sqlserver" => sqlserver::SQLServerMetadata::get_tables(data_source).await,
-            _ => Err(anyhow::anyhow!("Unsupported data source type for table metadata: {}", data_source.data_type)),
-        }
+        crate::offline::ensure_online()?;
+        connector_for(data_source, Capability::Tables)?
+            .get_tables(data_source)
+            .await
     }
 
     pub async fn get_table_structure(
@@ -83,12 +207,10 @@ impl MetadataFetcher {
         schema: Option<&str>,
         table_name: &str,
     ) -> Result<TableInfo> {
-        match data_source.data_type.as_str() {
-            "mysql" => mysql::MySQLMetadata::get_table_structure(data_source, schema, table_name).await,
-            "postgresql" => postgresql::PostgreSQLMetadata::get_table_structure(data_source, schema, table_name).await,
-            "sqlserver" => sqlserver::SQLServerMetadata::get_table_structure(data_source, schema, table_name).await,
-            _ => Err(anyhow::anyhow!("Unsupported data source type for table structure: {}", data_source.data_type)),
-        }
+        crate::offline::ensure_online()?;
+        connector_for(data_source, Capability::TableStructure)?
+            .get_table_structure(data_source, schema, table_name)
+            .await
     }
 
     pub async fn get_table_row_count(
@@ -96,16 +218,15 @@ impl MetadataFetcher {
         schema: Option<&str>,
         table_name: &str,
     ) -> Result<i64> {
-        match data_source.data_type.as_str() {
-            "mysql" => mysql::MySQLMetadata::get_table_row_count(data_source, schema, table_name).await,
-            "postgresql" => postgresql::PostgreSQLMetadata::get_table_row_count(data_source, schema, table_name).await,
-            "sqlserver" => sqlserver::SQLServerMetadata::get_table_row_count(data_source, schema, table_name).await,
-            _ => Err(anyhow::anyhow!("Unsupported data source type for row count: {}", data_source.data_type)),
-        }
+        crate::offline::ensure_online()?;
+        connector_for(data_source, Capability::RowCount)?
+            .get_table_row_count(data_source, schema, table_name)
+            .await
     }
 
     #[cfg(feature = "kafka")]
     pub async fn get_kafka_topics(data_source: &DataSource) -> Result<Vec<KafkaTopicInfo>> {
+        crate::offline::ensure_online()?;
         kafka::KafkaMetadata::get_topics(data_source).await
     }
 
@@ -116,6 +237,7 @@ impl MetadataFetcher {
 
     #[cfg(feature = "kafka")]
     pub async fn get_kafka_consumer_groups(data_source: &DataSource) -> Result<Vec<String>> {
+        crate::offline::ensure_online()?;
         kafka::KafkaMetadata::get_consumer_groups(data_source).await
     }
 
@@ -124,18 +246,104 @@ impl MetadataFetcher {
         Err(anyhow::anyhow!("Kafka support is not enabled. Build with --features kafka"))
     }
 
-    #[cfg(feature = "kafka")]
+    pub async fn scan_redis_keys(
+        data_source: &DataSource,
+        pattern: Option<&str>,
+        cursor: u64,
+    ) -> Result<RedisKeyPage> {
+        crate::offline::ensure_online()?;
+        connector_for(data_source, Capability::Keys)?
+            .scan_keys(data_source, pattern, cursor)
+            .await
+    }
+
+    pub async fn get_redis_value(
+        data_source: &DataSource,
+        key: &str,
+        limit: usize,
+    ) -> Result<RedisValue> {
+        crate::offline::ensure_online()?;
+        connector_for(data_source, Capability::Keys)?
+            .get_key_value(data_source, key, limit)
+            .await
+    }
+
+    pub async fn get_redis_info(data_source: &DataSource) -> Result<Vec<RedisInfoSection>> {
+        crate::offline::ensure_online()?;
+        connector_for(data_source, Capability::ServerInfo)?
+            .get_server_info(data_source)
+            .await
+    }
+
+    pub async fn list_databases(data_source: &DataSource) -> Result<Vec<String>> {
+        crate::offline::ensure_online()?;
+        connector_for(data_source, Capability::Databases)?
+            .list_databases(data_source)
+            .await
+    }
+
+    pub async fn list_schemas(
+        data_source: &DataSource,
+        database: Option<&str>,
+    ) -> Result<Vec<String>> {
+        crate::offline::ensure_online()?;
+        connector_for(data_source, Capability::Schemas)?
+            .list_schemas(data_source, database)
+            .await
+    }
+
+    pub async fn get_postgres_objects(
+        data_source: &DataSource,
+        schema: Option<&str>,
+    ) -> Result<PostgresObjects> {
+        crate::offline::ensure_online()?;
+        connector_for(data_source, Capability::SchemaObjects)?
+            .get_schema_objects(data_source, schema)
+            .await
+    }
+
     pub async fn get_schema_registry_schemas(
         data_source: &DataSource,
     ) -> Result<Vec<SchemaInfo>> {
-        kafka::KafkaMetadata::get_schema_registry_schemas(data_source).await
+        crate::offline::ensure_online()?;
+        schema_registry::SchemaRegistryMetadata::get_schemas(data_source).await
     }
 
-    #[cfg(not(feature = "kafka"))]
-    pub async fn get_schema_registry_schemas(
-        _data_source: &DataSource,
+    pub async fn get_schema_versions(
+        data_source: &DataSource,
+        subject: &str,
     ) -> Result<Vec<SchemaInfo>> {
-        Err(anyhow::anyhow!("Kafka support is not enabled. Build with --features kafka"))
+        crate::offline::ensure_online()?;
+        schema_registry::SchemaRegistryMetadata::get_subject_versions(data_source, subject).await
+    }
+
+    pub async fn diff_schema_versions(
+        data_source: &DataSource,
+        subject: &str,
+        version1: i32,
+        version2: i32,
+    ) -> Result<SchemaDiff> {
+        crate::offline::ensure_online()?;
+        schema_registry::SchemaRegistryMetadata::diff_versions(data_source, subject, version1, version2).await
+    }
+
+    pub async fn get_schema_compatibility(
+        data_source: &DataSource,
+        subject: Option<&str>,
+    ) -> Result<CompatibilityLevel> {
+        crate::offline::ensure_online()?;
+        schema_registry::SchemaRegistryMetadata::get_compatibility(data_source, subject).await
+    }
+
+    pub async fn test_schema_compatibility(
+        data_source: &DataSource,
+        subject: &str,
+        schema: &str,
+        schema_type: Option<&str>,
+        version: Option<i32>,
+    ) -> Result<CompatibilityCheckResult> {
+        crate::offline::ensure_online()?;
+        schema_registry::SchemaRegistryMetadata::test_compatibility(data_source, subject, schema, schema_type, version).await
     }
 
     pub async fn compare_tables(
@@ -169,6 +377,124 @@ impl MetadataFetcher {
         })
     }
 
+    /// Changes between two table listings of the same data source, `old` first.
+    pub fn diff_table_lists(old: &[TableInfo], new: &[TableInfo]) -> Vec<SchemaChange> {
+        let mut changes = Vec::new();
+
+        let old_tables: HashMap<String, &TableInfo> = old.iter()
+            .map(|t| (Self::qualified_table_name(t), t))
+            .collect();
+        let new_tables: HashMap<String, &TableInfo> = new.iter()
+            .map(|t| (Self::qualified_table_name(t), t))
+            .collect();
+
+        for (name, new_table) in &new_tables {
+            match old_tables.get(name) {
+                None => changes.push(SchemaChange {
+                    object_type: "table".to_string(),
+                    object_name: name.clone(),
+                    change_type: "added".to_string(),
+                    old_value: None,
+                    new_value: Some(format!("{} columns", new_table.columns.len())),
+                }),
+                Some(old_table) => {
+                    for diff in Self::compare_structure(old_table, new_table) {
+                        changes.push(SchemaChange {
+                            object_type: "column".to_string(),
+                            object_name: format!("{}.{}", name, diff.column_name),
+                            change_type: diff.diff_type,
+                            old_value: diff.source1_value,
+                            new_value: diff.source2_value,
+                        });
+                    }
+                }
+            }
+        }
+
+        for (name, old_table) in &old_tables {
+            if !new_tables.contains_key(name) {
+                changes.push(SchemaChange {
+                    object_type: "table".to_string(),
+                    object_name: name.clone(),
+                    change_type: "removed".to_string(),
+                    old_value: Some(format!("{} columns", old_table.columns.len())),
+                    new_value: None,
+                });
+            }
+        }
+
+        changes.sort_by(|a, b| a.object_name.cmp(&b.object_name));
+        changes
+    }
+
+    /// Changes between two topic listings of the same cluster, `old` first.
+    pub fn diff_topic_lists(old: &[KafkaTopicInfo], new: &[KafkaTopicInfo]) -> Vec<SchemaChange> {
+        let mut changes = Vec::new();
+
+        let old_topics: HashMap<&str, &KafkaTopicInfo> = old.iter()
+            .map(|t| (t.name.as_str(), t))
+            .collect();
+        let new_topics: HashMap<&str, &KafkaTopicInfo> = new.iter()
+            .map(|t| (t.name.as_str(), t))
+            .collect();
+
+        for (name, new_topic) in &new_topics {
+            match old_topics.get(name) {
+                None => changes.push(SchemaChange {
+                    object_type: "topic".to_string(),
+                    object_name: name.to_string(),
+                    change_type: "added".to_string(),
+                    old_value: None,
+                    new_value: Some(format!("{} partitions", new_topic.partitions.len())),
+                }),
+                Some(old_topic) if old_topic.partitions.len() != new_topic.partitions.len() => {
+                    changes.push(SchemaChange {
+                        object_type: "topic".to_string(),
+                        object_name: name.to_string(),
+                        change_type: "modified".to_string(),
+                        old_value: Some(format!("{} partitions", old_topic.partitions.len())),
+                        new_value: Some(format!("{} partitions", new_topic.partitions.len())),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        for (name, old_topic) in &old_topics {
+            if !new_topics.contains_key(name) {
+                changes.push(SchemaChange {
+                    object_type: "topic".to_string(),
+                    object_name: name.to_string(),
+                    change_type: "removed".to_string(),
+                    old_value: Some(format!("{} partitions", old_topic.partitions.len())),
+                    new_value: None,
+                });
+            }
+        }
+
+        changes.sort_by(|a, b| a.object_name.cmp(&b.object_name));
+        changes
+    }
+
+    fn qualified_table_name(table: &TableInfo) -> String {
+        match &table.schema {
+            Some(schema) => format!("{}.{}", schema, table.name),
+            None => table.name.clone(),
+        }
+    }
+
+    /// Builds a comparison from already known structures, without row counts.
+    pub fn compare_table_infos(table_name: &str, table1: TableInfo, table2: TableInfo) -> TableComparison {
+        let structure_diff = Self::compare_structure(&table1, &table2);
+        TableComparison {
+            table_name: table_name.to_string(),
+            source1: table1,
+            source2: table2,
+            structure_diff,
+            row_count_diff: None,
+        }
+    }
+
     fn compare_structure(table1: &TableInfo, table2: &TableInfo) -> Vec<StructureDiff> {
         let mut diffs = Vec::new();
         
@@ -3,28 +3,84 @@ use crate::db::DataSource;
 use crate::metadata::{ColumnInfo, TableInfo};
 use anyhow::{Context, Result};
 use sqlx::MySqlPool;
+use std::collections::{BTreeMap, HashMap};
 
 pub struct MySQLMetadata;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerFlavor {
+    MySql,
+    MariaDb,
+}
+
+/// What the server is, which decides the metadata it can report.
+#[derive(Debug, Clone)]
+pub struct ServerInfo {
+    pub flavor: ServerFlavor,
+    pub version: (u32, u32, u32),
+}
+
+impl ServerInfo {
+    /// MySQL 5.7 and MariaDB 10.2 added generated columns and GENERATION_EXPRESSION.
+    fn has_generated_columns(&self) -> bool {
+        match self.flavor {
+            ServerFlavor::MySql => self.version >= (5, 7, 0),
+            ServerFlavor::MariaDb => self.version >= (10, 2, 0),
+        }
+    }
+
+    /// MariaDB 10.3 added sequences; MySQL has none.
+    fn has_sequences(&self) -> bool {
+        self.flavor == ServerFlavor::MariaDb && self.version >= (10, 3, 0)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ColumnRow {
+    table_name: String,
+    column_name: String,
+    data_type: String,
+    column_type: String,
+    is_nullable: String,
+    column_default: Option<String>,
+    column_key: String,
+    extra: String,
+    generation_expression: Option<String>,
+    character_set_name: Option<String>,
+    collation_name: Option<String>,
+    column_comment: String,
+}
+
 impl MySQLMetadata {
+    pub async fn server_info(pool: &MySqlPool) -> Result<ServerInfo> {
+        let (version_string,): (String,) = sqlx::query_as("SELECT CAST(VERSION() AS CHAR)")
+            .fetch_one(pool)
+            .await
+            .context("Failed to read the server version")?;
+
+        let flavor = if version_string.contains("MariaDB") {
+            ServerFlavor::MariaDb
+        } else {
+            ServerFlavor::MySql
+        };
+        // Old replication-compatible MariaDB builds report 5.5.5-10.x.y-MariaDB
+        let version = parse_version(
+            version_string
+                .strip_prefix("5.5.5-")
+                .filter(|_| flavor == ServerFlavor::MariaDb)
+                .unwrap_or(&version_string),
+        );
+
+        Ok(ServerInfo { flavor, version })
+    }
+
     pub async fn get_tables(data_source: &DataSource) -> Result<Vec<TableInfo>> {
         let pool = MySQLConnector::create_pool(data_source).await?;
-        let database = data_source.database.as_deref().unwrap_or("information_schema");
-        
-        let tables: Vec<(String,)> = sqlx::query_as(
-            "SELECT table_name FROM information_schema.tables WHERE table_schema = ? ORDER BY table_name"
-        )
-        .bind(database)
-        .fetch_all(&pool)
-        .await?;
-        
-        let mut result = Vec::new();
-        for (table_name,) in tables {
-            let structure = Self::get_table_structure(data_source, Some(database), &table_name).await?;
-            result.push(structure);
-        }
-        
-        Ok(result)
+        let database = data_source
+            .database
+            .as_deref()
+            .unwrap_or("information_schema");
+        Self::tables(&pool, database, None).await
     }
 
     pub async fn get_table_structure(
@@ -33,48 +89,261 @@ impl MySQLMetadata {
         table_name: &str,
     ) -> Result<TableInfo> {
         let pool = MySQLConnector::create_pool(data_source).await?;
-        let schema = schema.unwrap_or_else(|| data_source.database.as_deref().unwrap_or("information_schema"));
-        
-        let columns: Vec<(String, String, String, Option<String>, String)> = sqlx::query_as(
-            "SELECT 
-                column_name, 
-                data_type, 
-                is_nullable,
-                column_default,
-                column_key
-            FROM information_schema.columns 
-            WHERE table_schema = ? AND table_name = ?
-            ORDER BY ordinal_position"
+        let schema = schema.unwrap_or_else(|| {
+            data_source
+                .database
+                .as_deref()
+                .unwrap_or("information_schema")
+        });
+        Self::tables(&pool, schema, Some(table_name))
+            .await?
+            .pop()
+            .context(format!("Table {}.{} not found", schema, table_name))
+    }
+
+    pub async fn get_databases(data_source: &DataSource) -> Result<Vec<String>> {
+        let pool = MySQLConnector::create_pool(data_source).await?;
+        let databases: Vec<(String,)> = sqlx::query_as(
+            "SELECT CAST(schema_name AS CHAR) FROM information_schema.schemata
+             ORDER BY schema_name",
+        )
+        .fetch_all(&pool)
+        .await?;
+        Ok(databases.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// A MySQL database is its own single schema, so this is `database` if it exists,
+    /// or every database when neither it nor the configured one is given.
+    pub async fn get_schemas(
+        data_source: &DataSource,
+        database: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let Some(database) = database.or(data_source.database.as_deref()) else {
+            return Self::get_databases(data_source).await;
+        };
+        let pool = MySQLConnector::create_pool(data_source).await?;
+        let schemas: Vec<(String,)> = sqlx::query_as(
+            "SELECT CAST(schema_name AS CHAR) FROM information_schema.schemata
+             WHERE schema_name = ?",
         )
-        .bind(schema)
-        .bind(table_name)
+        .bind(database)
         .fetch_all(&pool)
         .await?;
-        
-        let column_infos: Vec<ColumnInfo> = columns
-            .into_iter()
-            .map(|(name, data_type, is_nullable, default_value, column_key)| {
-                let mut constraints = Vec::new();
-                if column_key == "PRI" {
-                    constraints.push("PRIMARY KEY".to_string());
-                }
-                
-                ColumnInfo {
-                    name,
-                    data_type,
-                    is_nullable: is_nullable == "YES",
-                    default_value,
-                    constraints,
-                }
-            })
-            .collect();
-        
-        Ok(TableInfo {
-            name: table_name.to_string(),
-            schema: Some(schema.to_string()),
-            row_count: None,
-            columns: column_infos,
-        })
+        Ok(schemas.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// Tables, views and sequences of a schema with their columns, read in one pass.
+    async fn tables(
+        pool: &MySqlPool,
+        schema: &str,
+        table_name: Option<&str>,
+    ) -> Result<Vec<TableInfo>> {
+        let server = Self::server_info(pool).await?;
+
+        let mut sql = "SELECT CAST(table_name AS CHAR), CAST(table_type AS CHAR),
+                    CAST(engine AS CHAR), CAST(table_collation AS CHAR)
+             FROM information_schema.tables
+             WHERE table_schema = ?"
+            .to_string();
+        if table_name.is_some() {
+            sql.push_str(" AND table_name = ?");
+        }
+        sql.push_str(" ORDER BY table_name");
+        let mut query = sqlx::query_as(&sql).bind(schema);
+        if let Some(table_name) = table_name {
+            query = query.bind(table_name);
+        }
+        let tables: Vec<(String, String, Option<String>, Option<String>)> =
+            query.fetch_all(pool).await?;
+
+        let mut columns = Self::columns(pool, &server, schema, table_name).await?;
+
+        let mut result = Vec::new();
+        for (name, table_type, engine, collation) in tables {
+            let mut properties = BTreeMap::new();
+            properties.insert("table_type".to_string(), table_type.clone());
+            if let Some(engine) = engine {
+                properties.insert("engine".to_string(), engine);
+            }
+            if let Some(collation) = collation {
+                properties.insert("collation".to_string(), collation);
+            }
+            // MariaDB reports system-versioned tables with their own table type
+            if table_type == "SYSTEM VERSIONED" {
+                properties.insert("system_versioned".to_string(), "true".to_string());
+            }
+            if table_type == "SEQUENCE" && server.has_sequences() {
+                properties.extend(Self::sequence_properties(pool, schema, &name).await?);
+            }
+
+            result.push(TableInfo {
+                columns: columns.remove(&name).unwrap_or_default(),
+                name,
+                schema: Some(schema.to_string()),
+                row_count: None,
+                properties,
+            });
+        }
+        Ok(result)
+    }
+
+    async fn columns(
+        pool: &MySqlPool,
+        server: &ServerInfo,
+        schema: &str,
+        table_name: Option<&str>,
+    ) -> Result<HashMap<String, Vec<ColumnInfo>>> {
+        let generation_expression = if server.has_generated_columns() {
+            "CAST(generation_expression AS CHAR)"
+        } else {
+            "NULL"
+        };
+        let mut sql = format!(
+            "SELECT
+                CAST(table_name AS CHAR) AS table_name,
+                CAST(column_name AS CHAR) AS column_name,
+                CAST(data_type AS CHAR) AS data_type,
+                CAST(column_type AS CHAR) AS column_type,
+                CAST(is_nullable AS CHAR) AS is_nullable,
+                CAST(column_default AS CHAR) AS column_default,
+                CAST(column_key AS CHAR) AS column_key,
+                CAST(extra AS CHAR) AS extra,
+                {} AS generation_expression,
+                CAST(character_set_name AS CHAR) AS character_set_name,
+                CAST(collation_name AS CHAR) AS collation_name,
+                CAST(column_comment AS CHAR) AS column_comment
+            FROM information_schema.columns
+            WHERE table_schema = ?",
+            generation_expression
+        );
+        if table_name.is_some() {
+            sql.push_str(" AND table_name = ?");
+        }
+        sql.push_str(" ORDER BY table_name, ordinal_position");
+
+        let mut query = sqlx::query_as::<_, ColumnRow>(&sql).bind(schema);
+        if let Some(table_name) = table_name {
+            query = query.bind(table_name);
+        }
+
+        let mut columns: HashMap<String, Vec<ColumnInfo>> = HashMap::new();
+        for row in query.fetch_all(pool).await? {
+            let (table, column) = Self::column_info(row);
+            columns.entry(table).or_default().push(column);
+        }
+        Ok(columns)
+    }
+
+    fn column_info(row: ColumnRow) -> (String, ColumnInfo) {
+        let ColumnRow {
+            table_name,
+            column_name,
+            data_type,
+            column_type,
+            is_nullable,
+            column_default,
+            column_key,
+            extra,
+            generation_expression,
+            character_set_name,
+            collation_name,
+            column_comment,
+        } = row;
+
+        let mut constraints = Vec::new();
+        match column_key.as_str() {
+            "PRI" => constraints.push("PRIMARY KEY".to_string()),
+            "UNI" => constraints.push("UNIQUE".to_string()),
+            _ => {}
+        }
+
+        let mut properties = BTreeMap::new();
+        properties.insert("column_type".to_string(), column_type);
+        if let Some(charset) = character_set_name {
+            properties.insert("charset".to_string(), charset);
+        }
+        if let Some(collation) = collation_name {
+            properties.insert("collation".to_string(), collation);
+        }
+        if !column_comment.is_empty() {
+            properties.insert("comment".to_string(), column_comment);
+        }
+
+        // EXTRA is a space separated list such as "auto_increment",
+        // "on update current_timestamp()", "STORED GENERATED" or "ROW START INVISIBLE"
+        let extra_upper = extra.to_uppercase();
+        if extra_upper.contains("AUTO_INCREMENT") {
+            constraints.push("AUTO_INCREMENT".to_string());
+        }
+        if let Some(start) = extra_upper.find("ON UPDATE ") {
+            let on_update = &extra[start + "ON UPDATE ".len()..];
+            let end = on_update
+                .find(|c: char| c.is_whitespace())
+                .unwrap_or(on_update.len());
+            properties.insert("on_update".to_string(), on_update[..end].to_string());
+        }
+        if let Some(expression) = generation_expression.filter(|e| !e.is_empty()) {
+            constraints.push("GENERATED".to_string());
+            properties.insert("generation_expression".to_string(), expression);
+            let kind = if extra_upper.contains("STORED") || extra_upper.contains("PERSISTENT") {
+                "stored"
+            } else {
+                "virtual"
+            };
+            properties.insert("generated".to_string(), kind.to_string());
+        }
+        if extra_upper.contains("INVISIBLE") {
+            properties.insert("invisible".to_string(), "true".to_string());
+        }
+        if extra_upper.contains("ROW START") {
+            properties.insert("system_versioning".to_string(), "row_start".to_string());
+        } else if extra_upper.contains("ROW END") {
+            properties.insert("system_versioning".to_string(), "row_end".to_string());
+        }
+        if !extra.is_empty() {
+            properties.insert("extra".to_string(), extra);
+        }
+
+        (
+            table_name,
+            ColumnInfo {
+                name: column_name,
+                data_type,
+                is_nullable: is_nullable == "YES",
+                default_value: column_default,
+                constraints,
+                properties,
+            },
+        )
+    }
+
+    /// A MariaDB sequence is a one-row table holding its current state.
+    async fn sequence_properties(
+        pool: &MySqlPool,
+        schema: &str,
+        name: &str,
+    ) -> Result<BTreeMap<String, String>> {
+        let query = format!(
+            "SELECT start_value, minimum_value, maximum_value, increment,
+                    CAST(cycle_option AS SIGNED), next_not_cached_value
+             FROM {}.{}",
+            quote_ident(schema),
+            quote_ident(name)
+        );
+        let (start, minimum, maximum, increment, cycle, next): (i64, i64, i64, i64, i64, i64) =
+            sqlx::query_as(&query).fetch_one(pool).await?;
+
+        Ok([
+            ("sequence_start", start.to_string()),
+            ("sequence_minimum", minimum.to_string()),
+            ("sequence_maximum", maximum.to_string()),
+            ("sequence_increment", increment.to_string()),
+            ("sequence_cycle", (cycle != 0).to_string()),
+            ("sequence_next_not_cached", next.to_string()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect())
     }
 
     pub async fn get_table_row_count(
@@ -83,12 +352,127 @@ impl MySQLMetadata {
         table_name: &str,
     ) -> Result<i64> {
         let pool = MySQLConnector::create_pool(data_source).await?;
-        let schema = schema.unwrap_or_else(|| data_source.database.as_deref().unwrap_or("information_schema"));
-        
-        let query = format!("SELECT COUNT(*) as count FROM `{}`.`{}`", schema, table_name);
+        let schema = schema.unwrap_or_else(|| {
+            data_source
+                .database
+                .as_deref()
+                .unwrap_or("information_schema")
+        });
+
+        let query = format!(
+            "SELECT COUNT(*) as count FROM {}.{}",
+            quote_ident(schema),
+            quote_ident(table_name)
+        );
         let row: (i64,) = sqlx::query_as(&query).fetch_one(&pool).await?;
-        
+
         Ok(row.0)
     }
 }
 
+fn quote_ident(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+/// The leading `major.minor.patch` of a version string such as `10.11.6-MariaDB-1`.
+fn parse_version(version: &str) -> (u32, u32, u32) {
+    let mut numbers = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|n| n.parse().unwrap_or(0));
+    (
+        numbers.next().unwrap_or(0),
+        numbers.next().unwrap_or(0),
+        numbers.next().unwrap_or(0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A NOT NULL column of `orders` with no key, extra or generation expression.
+    fn column_row(data_type: &str) -> ColumnRow {
+        ColumnRow {
+            table_name: "orders".to_string(),
+            column_name: "col".to_string(),
+            data_type: data_type.to_string(),
+            column_type: format!("{}(11)", data_type),
+            is_nullable: "NO".to_string(),
+            column_default: None,
+            column_key: String::new(),
+            extra: String::new(),
+            generation_expression: None,
+            character_set_name: None,
+            collation_name: None,
+            column_comment: String::new(),
+        }
+    }
+
+    #[test]
+    fn versions() {
+        assert_eq!(parse_version("8.0.36"), (8, 0, 36));
+        assert_eq!(parse_version("8.0.36-0ubuntu0.22.04.1"), (8, 0, 36));
+        assert_eq!(
+            parse_version("10.11.6-MariaDB-1:10.11.6+maria~ubu2204"),
+            (10, 11, 6)
+        );
+        assert_eq!(parse_version("5.7"), (5, 7, 0));
+        assert_eq!(parse_version(""), (0, 0, 0));
+    }
+
+    #[test]
+    fn auto_increment_primary_key() {
+        let (table, column) = MySQLMetadata::column_info(ColumnRow {
+            column_key: "PRI".to_string(),
+            extra: "auto_increment".to_string(),
+            ..column_row("int")
+        });
+        assert_eq!(table, "orders");
+        assert_eq!(column.constraints, ["PRIMARY KEY", "AUTO_INCREMENT"]);
+        assert_eq!(column.properties["column_type"], "int(11)");
+        assert!(!column.is_nullable);
+    }
+
+    #[test]
+    fn generated_and_invisible_columns() {
+        let (_, stored) = MySQLMetadata::column_info(ColumnRow {
+            extra: "STORED GENERATED INVISIBLE".to_string(),
+            generation_expression: Some("`a` + 1".to_string()),
+            ..column_row("int")
+        });
+        assert_eq!(stored.constraints, ["GENERATED"]);
+        assert_eq!(stored.properties["generated"], "stored");
+        assert_eq!(stored.properties["generation_expression"], "`a` + 1");
+        assert_eq!(stored.properties["invisible"], "true");
+
+        let (_, virtual_column) = MySQLMetadata::column_info(ColumnRow {
+            column_key: "UNI".to_string(),
+            extra: "VIRTUAL GENERATED".to_string(),
+            generation_expression: Some("a * 2".to_string()),
+            ..column_row("int")
+        });
+        assert_eq!(virtual_column.constraints, ["UNIQUE", "GENERATED"]);
+        assert_eq!(virtual_column.properties["generated"], "virtual");
+    }
+
+    #[test]
+    fn on_update_and_system_versioning() {
+        let (_, updated) = MySQLMetadata::column_info(ColumnRow {
+            extra: "DEFAULT_GENERATED on update CURRENT_TIMESTAMP".to_string(),
+            ..column_row("timestamp")
+        });
+        assert_eq!(updated.properties["on_update"], "CURRENT_TIMESTAMP");
+        assert!(!updated.properties.contains_key("generated"));
+
+        let (_, row_start) = MySQLMetadata::column_info(ColumnRow {
+            extra: "ROW START INVISIBLE".to_string(),
+            ..column_row("timestamp")
+        });
+        assert_eq!(row_start.properties["system_versioning"], "row_start");
+        let (_, row_end) = MySQLMetadata::column_info(ColumnRow {
+            extra: "ROW END".to_string(),
+            ..column_row("timestamp")
+        });
+        assert_eq!(row_end.properties["system_versioning"], "row_end");
+    }
+}
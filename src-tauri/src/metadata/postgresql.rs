@@ -1,32 +1,55 @@
 use crate::connection::postgresql::PostgreSQLConnector;
 use crate::db::DataSource;
-use crate::metadata::{ColumnInfo, TableInfo};
+use crate::metadata::{
+    ColumnInfo, PgCompositeType, PgDomain, PgEnum, PgExtension, PgSequence, PostgresObjects,
+    TableInfo,
+};
 use anyhow::{Context, Result};
 use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
 
 pub struct PostgreSQLMetadata;
 
+// PostgreSQL 10 added declarative partitioning and the pg_sequences view
+const PG_10: i32 = 100000;
+
+#[derive(sqlx::FromRow)]
+struct ColumnRow {
+    table_name: String,
+    column_name: String,
+    data_type: String,
+    udt_schema: String,
+    udt_name: String,
+    domain_schema: Option<String>,
+    domain_name: Option<String>,
+    type_kind: Option<String>, // pg_type.typtype
+    enum_labels: Option<String>,
+    is_nullable: String,
+    column_default: Option<String>,
+    constraints: Option<String>,
+}
+
+// (name, relkind, partition key, partition bound, parents, children, is a partition)
+type TableRow = (
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    bool,
+);
+
+// (name, base type, nullable, default, check constraints)
+type DomainRow = (String, String, bool, Option<String>, Option<String>);
+
+// (name, data type, start, min, max, increment, cycles, last value)
+type SequenceRow = (String, String, i64, i64, i64, i64, bool, Option<i64>);
+
 impl PostgreSQLMetadata {
     pub async fn get_tables(data_source: &DataSource) -> Result<Vec<TableInfo>> {
         let pool = PostgreSQLConnector::create_pool(data_source).await?;
-        let schema = data_source.database.as_deref().unwrap_or("public");
-        
-        let tables: Vec<(String,)> = sqlx::query_as(
-            "SELECT table_name FROM information_schema.tables 
-             WHERE table_schema = $1 AND table_type = 'BASE TABLE'
-             ORDER BY table_name"
-        )
-        .bind(schema)
-        .fetch_all(&pool)
-        .await?;
-        
-        let mut result = Vec::new();
-        for (table_name,) in tables {
-            let structure = Self::get_table_structure(data_source, Some(schema), &table_name).await?;
-            result.push(structure);
-        }
-        
-        Ok(result)
+        Self::tables(&pool, &Self::default_schema(data_source), None).await
     }
 
     pub async fn get_table_structure(
@@ -35,52 +58,442 @@ impl PostgreSQLMetadata {
         table_name: &str,
     ) -> Result<TableInfo> {
         let pool = PostgreSQLConnector::create_pool(data_source).await?;
-        let schema = schema.unwrap_or_else(|| data_source.database.as_deref().unwrap_or("public"));
-        
-        let columns: Vec<(String, String, String, Option<String>, Option<String>)> = sqlx::query_as(
-            "SELECT 
-                column_name, 
-                data_type, 
-                is_nullable,
-                column_default,
-                (SELECT string_agg(constraint_type, ', ')
+        let default_schema = Self::default_schema(data_source);
+        let schema = schema.unwrap_or(&default_schema);
+        Self::tables(&pool, schema, Some(table_name))
+            .await?
+            .pop()
+            .context(format!("Table {}.{} not found", schema, table_name))
+    }
+
+    /// Databases that accept connections, templates excluded.
+    pub async fn get_databases(data_source: &DataSource) -> Result<Vec<String>> {
+        let pool = PostgreSQLConnector::create_pool(data_source).await?;
+        let databases: Vec<(String,)> = sqlx::query_as(
+            "SELECT datname::text FROM pg_database
+             WHERE datallowconn AND NOT datistemplate
+             ORDER BY datname",
+        )
+        .fetch_all(&pool)
+        .await?;
+        Ok(databases.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// Schemas of `database`, or of the configured database, leaving out the
+    /// catalog, TOAST and temporary schemas.
+    pub async fn get_schemas(
+        data_source: &DataSource,
+        database: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let mut data_source = data_source.clone();
+        if let Some(database) = database {
+            data_source.database = Some(database.to_string());
+        }
+        let pool = PostgreSQLConnector::create_pool(&data_source).await?;
+        let schemas: Vec<(String,)> = sqlx::query_as(
+            "SELECT nspname::text FROM pg_namespace
+             WHERE nspname NOT LIKE 'pg\\_%' AND nspname <> 'information_schema'
+             ORDER BY nspname",
+        )
+        .fetch_all(&pool)
+        .await?;
+        Ok(schemas.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// `data_source.database` names the database; the schema browsed comes from the
+    /// options.
+    fn default_schema(data_source: &DataSource) -> String {
+        data_source
+            .postgres_options()
+            .schema
+            .unwrap_or_else(|| "public".to_string())
+    }
+
+    async fn server_version(pool: &PgPool) -> Result<i32> {
+        let (version,): (i32,) =
+            sqlx::query_as("SELECT current_setting('server_version_num')::int")
+                .fetch_one(pool)
+                .await?;
+        Ok(version)
+    }
+
+    /// Plain and partitioned tables of a schema with their columns, partitioning
+    /// and inheritance.
+    async fn tables(
+        pool: &PgPool,
+        schema: &str,
+        table_name: Option<&str>,
+    ) -> Result<Vec<TableInfo>> {
+        let declarative_partitioning = Self::server_version(pool).await? >= PG_10;
+        let partitioning = if declarative_partitioning {
+            "CASE WHEN c.relkind = 'p' THEN pg_get_partkeydef(c.oid) END,
+             CASE WHEN c.relispartition THEN pg_get_expr(c.relpartbound, c.oid) END,"
+        } else {
+            "NULL::text, NULL::text,"
+        };
+        let is_partition = if declarative_partitioning {
+            "c.relispartition"
+        } else {
+            "false"
+        };
+        let mut sql = format!(
+            "SELECT
+                c.relname::text,
+                c.relkind::text,
+                {}
+                (SELECT string_agg(format('%I.%I', pn.nspname, p.relname), ', ' ORDER BY i.inhseqno)
+                 FROM pg_inherits i
+                 JOIN pg_class p ON p.oid = i.inhparent
+                 JOIN pg_namespace pn ON pn.oid = p.relnamespace
+                 WHERE i.inhrelid = c.oid),
+                (SELECT string_agg(format('%I.%I', cn.nspname, ch.relname), ', ' ORDER BY ch.relname)
+                 FROM pg_inherits i
+                 JOIN pg_class ch ON ch.oid = i.inhrelid
+                 JOIN pg_namespace cn ON cn.oid = ch.relnamespace
+                 WHERE i.inhparent = c.oid),
+                {}
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = $1 AND c.relkind IN ('r', 'p')",
+            partitioning, is_partition
+        );
+        if table_name.is_some() {
+            sql.push_str(" AND c.relname = $2");
+        }
+        sql.push_str(" ORDER BY c.relname");
+
+        let mut query = sqlx::query_as::<_, TableRow>(&sql).bind(schema);
+        if let Some(table_name) = table_name {
+            query = query.bind(table_name);
+        }
+        let tables = query.fetch_all(pool).await?;
+        let mut columns = Self::columns(pool, schema, table_name).await?;
+
+        Ok(tables
+            .into_iter()
+            .map(
+                |(
+                    name,
+                    relkind,
+                    partition_key,
+                    partition_bound,
+                    parents,
+                    children,
+                    is_partition,
+                )| {
+                    let mut properties = BTreeMap::new();
+                    if relkind == "p" {
+                        properties.insert("table_kind".to_string(), "partitioned".to_string());
+                    }
+                    if let Some(partition_key) = partition_key {
+                        properties.insert("partition_key".to_string(), partition_key);
+                    }
+                    if let Some(partition_bound) = partition_bound {
+                        properties.insert("partition_bound".to_string(), partition_bound);
+                    }
+                    // A partition's only parent is its partitioned table
+                    if let Some(parents) = parents {
+                        let key = if is_partition {
+                            "partition_of"
+                        } else {
+                            "inherits"
+                        };
+                        properties.insert(key.to_string(), parents);
+                    }
+                    if let Some(children) = children {
+                        let key = if relkind == "p" {
+                            "partitions"
+                        } else {
+                            "inherited_by"
+                        };
+                        properties.insert(key.to_string(), children);
+                    }
+
+                    TableInfo {
+                        columns: columns.remove(&name).unwrap_or_default(),
+                        name,
+                        schema: Some(schema.to_string()),
+                        row_count: None,
+                        properties,
+                    }
+                },
+            )
+            .collect())
+    }
+
+    async fn columns(
+        pool: &PgPool,
+        schema: &str,
+        table_name: Option<&str>,
+    ) -> Result<HashMap<String, Vec<ColumnInfo>>> {
+        let mut sql = "SELECT
+                c.table_name::text AS table_name,
+                c.column_name::text AS column_name,
+                c.data_type::text AS data_type,
+                c.udt_schema::text AS udt_schema,
+                c.udt_name::text AS udt_name,
+                c.domain_schema::text AS domain_schema,
+                c.domain_name::text AS domain_name,
+                t.typtype::text AS type_kind,
+                (SELECT string_agg(e.enumlabel::text, ', ' ORDER BY e.enumsortorder)
+                 FROM pg_enum e WHERE e.enumtypid = t.oid) AS enum_labels,
+                c.is_nullable::text AS is_nullable,
+                c.column_default::text AS column_default,
+                (SELECT string_agg(tc.constraint_type, ', ')
                  FROM information_schema.table_constraints tc
-                 JOIN information_schema.key_column_usage kcu 
+                 JOIN information_schema.key_column_usage kcu
                    ON tc.constraint_name = kcu.constraint_name
-                 WHERE tc.table_schema = $1 
-                   AND tc.table_name = $2
-                   AND kcu.column_name = c.column_name) as constraints
+                  AND tc.constraint_schema = kcu.constraint_schema
+                 WHERE tc.table_schema = c.table_schema
+                   AND tc.table_name = c.table_name
+                   AND kcu.column_name = c.column_name) AS constraints
             FROM information_schema.columns c
-            WHERE table_schema = $1 AND table_name = $2
-            ORDER BY ordinal_position"
+            LEFT JOIN pg_namespace tn ON tn.nspname = c.udt_schema
+            LEFT JOIN pg_type t ON t.typnamespace = tn.oid AND t.typname = c.udt_name
+            WHERE c.table_schema = $1"
+            .to_string();
+        if table_name.is_some() {
+            sql.push_str(" AND c.table_name = $2");
+        }
+        sql.push_str(" ORDER BY c.table_name, c.ordinal_position");
+
+        let mut query = sqlx::query_as::<_, ColumnRow>(&sql).bind(schema);
+        if let Some(table_name) = table_name {
+            query = query.bind(table_name);
+        }
+
+        let mut columns: HashMap<String, Vec<ColumnInfo>> = HashMap::new();
+        for row in query.fetch_all(pool).await? {
+            let (table, column) = Self::column_info(row);
+            columns.entry(table).or_default().push(column);
+        }
+        Ok(columns)
+    }
+
+    /// `data_type` is only USER-DEFINED or ARRAY for enums, composites and arrays, so
+    /// those are resolved through `udt_name`, and domains are reported by name.
+    fn column_info(row: ColumnRow) -> (String, ColumnInfo) {
+        let ColumnRow {
+            table_name,
+            column_name,
+            data_type,
+            udt_schema,
+            udt_name,
+            domain_schema,
+            domain_name,
+            type_kind,
+            enum_labels,
+            is_nullable,
+            column_default,
+            constraints,
+        } = row;
+
+        let mut properties = BTreeMap::new();
+        let resolved_type = match data_type.as_str() {
+            // Array types are the element type prefixed with an underscore
+            "ARRAY" => format!("{}[]", udt_name.trim_start_matches('_')),
+            "USER-DEFINED" => qualified(&udt_schema, &udt_name),
+            _ => data_type.clone(),
+        };
+        let data_type = match (domain_schema, domain_name) {
+            (Some(domain_schema), Some(domain_name)) => {
+                properties.insert("base_type".to_string(), resolved_type);
+                qualified(&domain_schema, &domain_name)
+            }
+            _ => resolved_type,
+        };
+        properties.insert("udt_name".to_string(), udt_name);
+        match type_kind.as_deref() {
+            Some("e") => {
+                properties.insert("type_kind".to_string(), "enum".to_string());
+            }
+            Some("c") => {
+                properties.insert("type_kind".to_string(), "composite".to_string());
+            }
+            Some("r") | Some("m") => {
+                properties.insert("type_kind".to_string(), "range".to_string());
+            }
+            _ => {}
+        }
+        if let Some(enum_labels) = enum_labels {
+            properties.insert("enum_labels".to_string(), enum_labels);
+        }
+
+        (
+            table_name,
+            ColumnInfo {
+                name: column_name,
+                data_type,
+                is_nullable: is_nullable == "YES",
+                default_value: column_default,
+                constraints: constraints
+                    .map(|s| s.split(',').map(|x| x.trim().to_string()).collect())
+                    .unwrap_or_default(),
+                properties,
+            },
+        )
+    }
+
+    /// Enums, domains, composite types, sequences and the extensions installed in the
+    /// database; all but extensions are limited to `schema`.
+    pub async fn get_objects(
+        data_source: &DataSource,
+        schema: Option<&str>,
+    ) -> Result<PostgresObjects> {
+        let pool = PostgreSQLConnector::create_pool(data_source).await?;
+        let default_schema = Self::default_schema(data_source);
+        let schema = schema.unwrap_or(&default_schema);
+        let version = Self::server_version(&pool).await?;
+
+        let enums: Vec<(String, Vec<String>)> = sqlx::query_as(
+            "SELECT t.typname::text,
+                    array_agg(e.enumlabel::text ORDER BY e.enumsortorder)
+             FROM pg_type t
+             JOIN pg_namespace n ON n.oid = t.typnamespace
+             JOIN pg_enum e ON e.enumtypid = t.oid
+             WHERE n.nspname = $1
+             GROUP BY t.typname
+             ORDER BY t.typname",
         )
         .bind(schema)
-        .bind(table_name)
         .fetch_all(&pool)
-        .await?;
-        
-        let column_infos: Vec<ColumnInfo> = columns
-            .into_iter()
-            .map(|(name, data_type, is_nullable, default_value, constraints_str)| {
-                let constraints = constraints_str
-                    .map(|s| s.split(',').map(|x| x.trim().to_string()).collect())
-                    .unwrap_or_default();
-                
-                ColumnInfo {
+        .await
+        .context("Failed to read enum types")?;
+
+        let domains: Vec<DomainRow> = sqlx::query_as(
+            "SELECT t.typname::text,
+                        format_type(t.typbasetype, t.typtypmod),
+                        NOT t.typnotnull,
+                        t.typdefault,
+                        (SELECT string_agg(pg_get_constraintdef(c.oid), ' AND ' ORDER BY c.conname)
+                         FROM pg_constraint c WHERE c.contypid = t.oid)
+                 FROM pg_type t
+                 JOIN pg_namespace n ON n.oid = t.typnamespace
+                 WHERE n.nspname = $1 AND t.typtype = 'd'
+                 ORDER BY t.typname",
+        )
+        .bind(schema)
+        .fetch_all(&pool)
+        .await
+        .context("Failed to read domains")?;
+
+        // Composite types declared with CREATE TYPE; tables have implicit ones as well
+        let attributes: Vec<(String, String, String, bool)> = sqlx::query_as(
+            "SELECT t.typname::text, a.attname::text,
+                    format_type(a.atttypid, a.atttypmod), NOT a.attnotnull
+             FROM pg_type t
+             JOIN pg_namespace n ON n.oid = t.typnamespace
+             JOIN pg_class c ON c.oid = t.typrelid AND c.relkind = 'c'
+             JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped
+             WHERE n.nspname = $1 AND t.typtype = 'c'
+             ORDER BY t.typname, a.attnum",
+        )
+        .bind(schema)
+        .fetch_all(&pool)
+        .await
+        .context("Failed to read composite types")?;
+
+        let extensions: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT e.extname::text, e.extversion, n.nspname::text
+             FROM pg_extension e
+             JOIN pg_namespace n ON n.oid = e.extnamespace
+             ORDER BY e.extname",
+        )
+        .fetch_all(&pool)
+        .await
+        .context("Failed to read extensions")?;
+
+        // last_value is NULL until nextval was called or without USAGE on the sequence
+        let sequences_sql = if version >= PG_10 {
+            "SELECT sequencename::text, data_type::text, start_value, min_value, max_value,
+                    increment_by, cycle, last_value
+             FROM pg_sequences
+             WHERE schemaname = $1
+             ORDER BY sequencename"
+        } else {
+            "SELECT sequence_name::text, data_type::text, start_value::bigint,
+                    minimum_value::bigint, maximum_value::bigint, increment::bigint,
+                    cycle_option = 'YES', NULL::bigint
+             FROM information_schema.sequences
+             WHERE sequence_schema = $1
+             ORDER BY sequence_name"
+        };
+        let sequences: Vec<SequenceRow> = sqlx::query_as(sequences_sql)
+            .bind(schema)
+            .fetch_all(&pool)
+            .await
+            .context("Failed to read sequences")?;
+
+        let mut composite_types: Vec<PgCompositeType> = Vec::new();
+        for (type_name, name, data_type, is_nullable) in attributes {
+            if composite_types.last().map(|t| &t.name) != Some(&type_name) {
+                composite_types.push(PgCompositeType {
+                    schema: schema.to_string(),
+                    name: type_name,
+                    attributes: Vec::new(),
+                });
+            }
+            if let Some(composite) = composite_types.last_mut() {
+                composite.attributes.push(ColumnInfo {
                     name,
                     data_type,
-                    is_nullable: is_nullable == "YES",
-                    default_value,
-                    constraints,
-                }
-            })
-            .collect();
-        
-        Ok(TableInfo {
-            name: table_name.to_string(),
-            schema: Some(schema.to_string()),
-            row_count: None,
-            columns: column_infos,
+                    is_nullable,
+                    default_value: None,
+                    constraints: Vec::new(),
+                    properties: Default::default(),
+                });
+            }
+        }
+
+        Ok(PostgresObjects {
+            enums: enums
+                .into_iter()
+                .map(|(name, labels)| PgEnum {
+                    schema: schema.to_string(),
+                    name,
+                    labels,
+                })
+                .collect(),
+            domains: domains
+                .into_iter()
+                .map(
+                    |(name, base_type, is_nullable, default_value, check)| PgDomain {
+                        schema: schema.to_string(),
+                        name,
+                        base_type,
+                        is_nullable,
+                        default_value,
+                        check,
+                    },
+                )
+                .collect(),
+            composite_types,
+            extensions: extensions
+                .into_iter()
+                .map(|(name, version, schema)| PgExtension {
+                    name,
+                    version,
+                    schema,
+                })
+                .collect(),
+            sequences: sequences
+                .into_iter()
+                .map(
+                    |(name, data_type, start, minimum, maximum, increment, cycle, last_value)| {
+                        PgSequence {
+                            schema: schema.to_string(),
+                            name,
+                            data_type,
+                            start_value: start,
+                            min_value: minimum,
+                            max_value: maximum,
+                            increment,
+                            cycle,
+                            last_value,
+                        }
+                    },
+                )
+                .collect(),
         })
     }
 
@@ -90,12 +503,103 @@ impl PostgreSQLMetadata {
         table_name: &str,
     ) -> Result<i64> {
         let pool = PostgreSQLConnector::create_pool(data_source).await?;
-        let schema = schema.unwrap_or_else(|| data_source.database.as_deref().unwrap_or("public"));
-        
-        let query = format!("SELECT COUNT(*) as count FROM \"{}\".\"{}\"", schema, table_name);
+        let default_schema = Self::default_schema(data_source);
+        let schema = schema.unwrap_or(&default_schema);
+
+        let query = format!(
+            "SELECT COUNT(*) as count FROM {}.{}",
+            quote_ident(schema),
+            quote_ident(table_name)
+        );
         let row: (i64,) = sqlx::query_as(&query).fetch_one(&pool).await?;
-        
+
         Ok(row.0)
     }
 }
 
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Types outside the default search path are shown with their schema.
+fn qualified(schema: &str, name: &str) -> String {
+    if schema == "pg_catalog" || schema == "public" {
+        name.to_string()
+    } else {
+        format!("{}.{}", schema, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A nullable column of `events` without constraints.
+    fn column_row(data_type: &str, udt_schema: &str, udt_name: &str) -> ColumnRow {
+        ColumnRow {
+            table_name: "events".to_string(),
+            column_name: "col".to_string(),
+            data_type: data_type.to_string(),
+            udt_schema: udt_schema.to_string(),
+            udt_name: udt_name.to_string(),
+            domain_schema: None,
+            domain_name: None,
+            type_kind: None,
+            enum_labels: None,
+            is_nullable: "YES".to_string(),
+            column_default: None,
+            constraints: None,
+        }
+    }
+
+    #[test]
+    fn qualified_type_names() {
+        assert_eq!(qualified("public", "mood"), "mood");
+        assert_eq!(qualified("pg_catalog", "int4"), "int4");
+        assert_eq!(qualified("billing", "currency"), "billing.currency");
+    }
+
+    #[test]
+    fn builtin_and_array_columns() {
+        let (table, column) =
+            PostgreSQLMetadata::column_info(column_row("integer", "pg_catalog", "int4"));
+        assert_eq!(table, "events");
+        assert_eq!(column.data_type, "integer");
+        assert!(column.is_nullable);
+        assert!(column.constraints.is_empty());
+
+        let (_, array) =
+            PostgreSQLMetadata::column_info(column_row("ARRAY", "pg_catalog", "_text"));
+        assert_eq!(array.data_type, "text[]");
+        assert_eq!(array.properties["udt_name"], "_text");
+    }
+
+    #[test]
+    fn enum_columns() {
+        let enum_row = ColumnRow {
+            type_kind: Some("e".to_string()),
+            enum_labels: Some("open, paid".to_string()),
+            constraints: Some("PRIMARY KEY, FOREIGN KEY".to_string()),
+            ..column_row("USER-DEFINED", "billing", "status")
+        };
+
+        let (_, column) = PostgreSQLMetadata::column_info(enum_row);
+        assert_eq!(column.data_type, "billing.status");
+        assert_eq!(column.properties["type_kind"], "enum");
+        assert_eq!(column.properties["enum_labels"], "open, paid");
+        assert_eq!(column.constraints, ["PRIMARY KEY", "FOREIGN KEY"]);
+    }
+
+    #[test]
+    fn domain_columns() {
+        let domain_row = ColumnRow {
+            domain_schema: Some("public".to_string()),
+            domain_name: Some("email".to_string()),
+            ..column_row("text", "pg_catalog", "text")
+        };
+
+        let (_, column) = PostgreSQLMetadata::column_info(domain_row);
+        assert_eq!(column.data_type, "email");
+        assert_eq!(column.properties["base_type"], "text");
+    }
+}
@@ -0,0 +1,435 @@
+use crate::connection::schema_registry::SchemaRegistryClient;
+use crate::db::DataSource;
+use crate::metadata::{
+    CompatibilityCheckResult, CompatibilityLevel, SchemaDiff, SchemaInfo, StructureDiff,
+};
+use anyhow::{Context, Result};
+use futures::{StreamExt, TryStreamExt};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+pub struct SchemaRegistryMetadata;
+
+impl SchemaRegistryMetadata {
+    /// Latest version of every subject, fetched concurrently.
+    pub async fn get_schemas(data_source: &DataSource) -> Result<Vec<SchemaInfo>> {
+        let client = SchemaRegistryClient::new(data_source)?;
+        let subjects: Vec<String> = client.get(&["subjects"]).await?;
+
+        let mut schemas: Vec<SchemaInfo> = futures::stream::iter(subjects)
+            .map(|subject| {
+                let client = &client;
+                async move { Self::fetch_version(client, &subject, "latest").await }
+            })
+            .buffer_unordered(client.max_concurrency())
+            .try_collect()
+            .await?;
+
+        schemas.sort_by(|a, b| a.subject.cmp(&b.subject));
+        Ok(schemas)
+    }
+
+    /// Every registered version of a subject, oldest first.
+    pub async fn get_subject_versions(
+        data_source: &DataSource,
+        subject: &str,
+    ) -> Result<Vec<SchemaInfo>> {
+        let client = SchemaRegistryClient::new(data_source)?;
+        let versions: Vec<i32> = client.get(&["subjects", subject, "versions"]).await?;
+
+        let mut schemas: Vec<SchemaInfo> = futures::stream::iter(versions)
+            .map(|version| {
+                let client = &client;
+                async move { Self::fetch_version(client, subject, &version.to_string()).await }
+            })
+            .buffer_unordered(client.max_concurrency())
+            .try_collect()
+            .await?;
+
+        schemas.sort_by_key(|s| s.version);
+        Ok(schemas)
+    }
+
+    pub async fn diff_versions(
+        data_source: &DataSource,
+        subject: &str,
+        version1: i32,
+        version2: i32,
+    ) -> Result<SchemaDiff> {
+        let client = SchemaRegistryClient::new(data_source)?;
+        let (v1, v2) = (version1.to_string(), version2.to_string());
+        let (schema1, schema2) = futures::try_join!(
+            Self::fetch_version(&client, subject, &v1),
+            Self::fetch_version(&client, subject, &v2),
+        )?;
+
+        let diffs = Self::compare_schemas(&schema1.schema_type, &schema1.schema, &schema2.schema);
+
+        Ok(SchemaDiff {
+            subject: subject.to_string(),
+            version1,
+            version2,
+            schema_type: schema1.schema_type,
+            diffs,
+        })
+    }
+
+    /// Compatibility level for a subject, falling back to the global level
+    /// when the subject has none of its own. `None` returns the global level.
+    pub async fn get_compatibility(
+        data_source: &DataSource,
+        subject: Option<&str>,
+    ) -> Result<CompatibilityLevel> {
+        let client = SchemaRegistryClient::new(data_source)?;
+
+        if let Some(subject) = subject {
+            let subject_config: Option<Value> =
+                client.get_optional(&["config", subject], &[]).await?;
+            if let Some(level) = subject_config
+                .as_ref()
+                .and_then(Self::compatibility_from_config)
+            {
+                return Ok(CompatibilityLevel {
+                    subject: Some(subject.to_string()),
+                    level,
+                    is_global_default: false,
+                });
+            }
+        }
+
+        let global_config: Value = client.get(&["config"]).await?;
+        let level = Self::compatibility_from_config(&global_config).ok_or_else(|| {
+            anyhow::anyhow!("Schema Registry did not report a compatibility level")
+        })?;
+
+        Ok(CompatibilityLevel {
+            subject: subject.map(|s| s.to_string()),
+            level,
+            is_global_default: true,
+        })
+    }
+
+    /// Checks a candidate schema against a subject version ("latest" when `version` is `None`).
+    pub async fn test_compatibility(
+        data_source: &DataSource,
+        subject: &str,
+        schema: &str,
+        schema_type: Option<&str>,
+        version: Option<i32>,
+    ) -> Result<CompatibilityCheckResult> {
+        let client = SchemaRegistryClient::new(data_source)?;
+        let version = version
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "latest".to_string());
+
+        let mut body = json!({ "schema": schema });
+        // The registry rejects schemaType=AVRO on older versions, so only send non-default types
+        if let Some(schema_type) = schema_type.filter(|t| !t.eq_ignore_ascii_case("AVRO")) {
+            body["schemaType"] = json!(schema_type.to_uppercase());
+        }
+
+        let response: Value = client
+            .post(
+                &["compatibility", "subjects", subject, "versions", &version],
+                &[("verbose", "true")],
+                &body,
+            )
+            .await?;
+
+        let is_compatible = response
+            .get("is_compatible")
+            .and_then(|v| v.as_bool())
+            .context("Schema Registry response is missing is_compatible")?;
+
+        let messages = response
+            .get("messages")
+            .and_then(|m| m.as_array())
+            .map(|m| {
+                m.iter()
+                    .filter_map(|x| x.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(CompatibilityCheckResult {
+            subject: subject.to_string(),
+            version,
+            is_compatible,
+            messages,
+        })
+    }
+
+    async fn fetch_version(
+        client: &SchemaRegistryClient,
+        subject: &str,
+        version: &str,
+    ) -> Result<SchemaInfo> {
+        let schema_data: Value = client
+            .get(&["subjects", subject, "versions", version])
+            .await
+            .context(format!("Failed to fetch schema for subject: {}", subject))?;
+
+        let version = schema_data
+            .get("version")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0) as i32;
+
+        let id = schema_data
+            .get("id")
+            .and_then(|v| v.as_i64())
+            .map(|v| v as i32);
+
+        let schema_type = schema_data
+            .get("schemaType")
+            .or_else(|| schema_data.get("schema_type"))
+            .and_then(|s| s.as_str())
+            .unwrap_or("AVRO")
+            .to_string();
+
+        let schema = schema_data
+            .get("schema")
+            .and_then(|s| s.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        Ok(SchemaInfo {
+            subject: subject.to_string(),
+            version,
+            id,
+            schema_type,
+            schema,
+        })
+    }
+
+    fn compatibility_from_config(config: &Value) -> Option<String> {
+        config
+            .get("compatibilityLevel")
+            .or_else(|| config.get("compatibility"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    }
+
+    /// Field-level diff for AVRO and JSON schemas, line-level for anything else
+    /// (PROTOBUF, or schemas that fail to parse).
+    pub fn compare_schemas(schema_type: &str, schema1: &str, schema2: &str) -> Vec<StructureDiff> {
+        let parsed = match (
+            serde_json::from_str::<Value>(schema1),
+            serde_json::from_str::<Value>(schema2),
+        ) {
+            (Ok(v1), Ok(v2)) => Some((v1, v2)),
+            _ => None,
+        };
+
+        let fields = match (schema_type.to_uppercase().as_str(), parsed) {
+            ("AVRO", Some((v1, v2))) => {
+                let mut f1 = BTreeMap::new();
+                let mut f2 = BTreeMap::new();
+                Self::flatten_avro(&v1, "", &mut f1);
+                Self::flatten_avro(&v2, "", &mut f2);
+                Some((f1, f2))
+            }
+            ("JSON", Some((v1, v2))) => {
+                let mut f1 = BTreeMap::new();
+                let mut f2 = BTreeMap::new();
+                Self::flatten_json_schema(&v1, "", &mut f1);
+                Self::flatten_json_schema(&v2, "", &mut f2);
+                Some((f1, f2))
+            }
+            _ => None,
+        };
+
+        match fields {
+            Some((f1, f2)) => Self::diff_field_maps(&f1, &f2),
+            None => Self::diff_lines(schema1, schema2),
+        }
+    }
+
+    fn flatten_avro(schema: &Value, prefix: &str, out: &mut BTreeMap<String, String>) {
+        let fields = match schema.get("fields").and_then(|f| f.as_array()) {
+            Some(fields) => fields,
+            None => return,
+        };
+
+        for field in fields {
+            let name = match field.get("name").and_then(|n| n.as_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+            let path = if prefix.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}.{}", prefix, name)
+            };
+            let field_type = field.get("type").cloned().unwrap_or(Value::Null);
+
+            let mut description = Self::avro_type_name(&field_type);
+            if let Some(default) = field.get("default") {
+                description.push_str(&format!(" default={}", default));
+            }
+            out.insert(path.clone(), description);
+
+            // Recurse into nested records, including ones wrapped in a nullable union
+            let nested: Vec<&Value> = match &field_type {
+                Value::Array(branches) => branches.iter().collect(),
+                other => vec![other],
+            };
+            for branch in nested {
+                if branch.get("type").and_then(|t| t.as_str()) == Some("record") {
+                    Self::flatten_avro(branch, &path, out);
+                }
+            }
+        }
+    }
+
+    fn avro_type_name(field_type: &Value) -> String {
+        match field_type {
+            Value::String(s) => s.clone(),
+            Value::Array(branches) => {
+                let names: Vec<String> = branches.iter().map(Self::avro_type_name).collect();
+                format!("[{}]", names.join(", "))
+            }
+            Value::Object(obj) => {
+                let base = obj
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("unknown");
+                match base {
+                    "record" | "enum" | "fixed" => obj
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .map(|n| format!("{}:{}", base, n))
+                        .unwrap_or_else(|| base.to_string()),
+                    "array" => format!(
+                        "array<{}>",
+                        Self::avro_type_name(obj.get("items").unwrap_or(&Value::Null))
+                    ),
+                    "map" => format!(
+                        "map<{}>",
+                        Self::avro_type_name(obj.get("values").unwrap_or(&Value::Null))
+                    ),
+                    _ => match obj.get("logicalType").and_then(|l| l.as_str()) {
+                        Some(logical) => format!("{}({})", base, logical),
+                        None => base.to_string(),
+                    },
+                }
+            }
+            _ => "null".to_string(),
+        }
+    }
+
+    fn flatten_json_schema(schema: &Value, prefix: &str, out: &mut BTreeMap<String, String>) {
+        let properties = match schema.get("properties").and_then(|p| p.as_object()) {
+            Some(properties) => properties,
+            None => return,
+        };
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|x| x.as_str()).collect())
+            .unwrap_or_default();
+
+        for (name, property) in properties {
+            let path = if prefix.is_empty() {
+                name.clone()
+            } else {
+                format!("{}.{}", prefix, name)
+            };
+            let property_type = match property.get("type") {
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => property
+                    .get("$ref")
+                    .and_then(|r| r.as_str())
+                    .unwrap_or("any")
+                    .to_string(),
+            };
+
+            let mut description = property_type;
+            if required.contains(&name.as_str()) {
+                description.push_str(" required");
+            }
+            out.insert(path.clone(), description);
+
+            Self::flatten_json_schema(property, &path, out);
+            if let Some(items) = property.get("items") {
+                Self::flatten_json_schema(items, &format!("{}[]", path), out);
+            }
+        }
+    }
+
+    fn diff_field_maps(
+        fields1: &BTreeMap<String, String>,
+        fields2: &BTreeMap<String, String>,
+    ) -> Vec<StructureDiff> {
+        let mut diffs = Vec::new();
+
+        for (name, value2) in fields2 {
+            match fields1.get(name) {
+                None => diffs.push(StructureDiff {
+                    column_name: name.clone(),
+                    diff_type: "added".to_string(),
+                    source1_value: None,
+                    source2_value: Some(value2.clone()),
+                }),
+                Some(value1) if value1 != value2 => diffs.push(StructureDiff {
+                    column_name: name.clone(),
+                    diff_type: "modified".to_string(),
+                    source1_value: Some(value1.clone()),
+                    source2_value: Some(value2.clone()),
+                }),
+                _ => {}
+            }
+        }
+
+        for (name, value1) in fields1 {
+            if !fields2.contains_key(name) {
+                diffs.push(StructureDiff {
+                    column_name: name.clone(),
+                    diff_type: "removed".to_string(),
+                    source1_value: Some(value1.clone()),
+                    source2_value: None,
+                });
+            }
+        }
+
+        diffs
+    }
+
+    fn diff_lines(schema1: &str, schema2: &str) -> Vec<StructureDiff> {
+        let lines1: Vec<&str> = schema1
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .collect();
+        let lines2: Vec<&str> = schema2
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .collect();
+        let mut diffs = Vec::new();
+
+        for (i, line) in lines2.iter().enumerate() {
+            if !lines1.contains(line) {
+                diffs.push(StructureDiff {
+                    column_name: format!("line {}", i + 1),
+                    diff_type: "added".to_string(),
+                    source1_value: None,
+                    source2_value: Some(line.to_string()),
+                });
+            }
+        }
+
+        for (i, line) in lines1.iter().enumerate() {
+            if !lines2.contains(line) {
+                diffs.push(StructureDiff {
+                    column_name: format!("line {}", i + 1),
+                    diff_type: "removed".to_string(),
+                    source1_value: Some(line.to_string()),
+                    source2_value: None,
+                });
+            }
+        }
+
+        diffs
+    }
+}
@@ -2,48 +2,26 @@ use crate::connection::sqlserver::SQLServerConnector;
 use crate::db::DataSource;
 use crate::metadata::{ColumnInfo, TableInfo};
 use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
 use tiberius::{Client, Query, Row};
-use tokio_util::compat::TokioAsyncWriteCompatExt;
 use tokio::net::TcpStream;
-use futures::StreamExt;
+use tokio_util::compat::Compat;
 
+type SqlServerClient = Client<Compat<TcpStream>>;
+
+const DEFAULT_SCHEMA: &str = "dbo";
+// Schemas of the fixed database roles (db_owner, ...) start at this id
+const FIRST_ROLE_SCHEMA_ID: i32 = 16384;
+
+/// `data_source.database` is the database connected to; schemas within it are
+/// browsed separately, all of them unless the options name one.
 pub struct SQLServerMetadata;
 
 impl SQLServerMetadata {
     pub async fn get_tables(data_source: &DataSource) -> Result<Vec<TableInfo>> {
         let mut client = SQLServerConnector::create_client(data_source).await?;
-        let schema = data_source.database.as_deref().unwrap_or("dbo");
-        
-        let query = format!(
-            "SELECT table_name FROM information_schema.tables 
-             WHERE table_schema = '{}' AND table_type = 'BASE TABLE'
-             ORDER BY table_name",
-            schema
-        );
-        
-        let mut stream = Query::new(query).query(&mut client).await?;
-        let mut table_names = Vec::new();
-        
-        while let Some(item) = stream.next().await {
-            match item? {
-                tiberius::QueryItem::Row(row) => {
-                    if let Some(table_name) = row.get::<&str, _>(0) {
-                        table_names.push(table_name.to_string());
-                    }
-                }
-                _ => {}
-            }
-        }
-        
-        // Get structure for each table
-        let mut tables = Vec::new();
-        for table_name in &table_names {
-            if let Ok(structure) = Self::get_table_structure(data_source, Some(schema), table_name).await {
-                tables.push(structure);
-            }
-        }
-        
-        Ok(tables)
+        let schema = data_source.sqlserver_options().schema;
+        Self::tables(&mut client, schema.as_deref(), None).await
     }
 
     pub async fn get_table_structure(
@@ -52,61 +30,261 @@ impl SQLServerMetadata {
         table_name: &str,
     ) -> Result<TableInfo> {
         let mut client = SQLServerConnector::create_client(data_source).await?;
-        let schema = schema.unwrap_or_else(|| data_source.database.as_deref().unwrap_or("dbo"));
-        
-        let query = format!(
-            "SELECT 
-                c.column_name, 
-                c.data_type, 
-                c.is_nullable,
-                c.column_default,
-                (SELECT STRING_AGG(tc.constraint_type, ', ')
-                 FROM information_schema.table_constraints tc
-                 JOIN information_schema.key_column_usage kcu 
-                   ON tc.constraint_name = kcu.constraint_name
-                 WHERE tc.table_schema = '{}' 
-                   AND tc.table_name = '{}'
-                   AND kcu.column_name = c.column_name) as constraints
-            FROM information_schema.columns c
-            WHERE c.table_schema = '{}' AND c.table_name = '{}'
-            ORDER BY c.ordinal_position",
-            schema, table_name, schema, table_name
+        let schema = schema
+            .map(String::from)
+            .or(data_source.sqlserver_options().schema)
+            .unwrap_or_else(|| DEFAULT_SCHEMA.to_string());
+        Self::tables(&mut client, Some(&schema), Some(table_name))
+            .await?
+            .pop()
+            .context(format!("Table {}.{} not found", schema, table_name))
+    }
+
+    /// Databases that are online; the rest cannot be browsed.
+    pub async fn get_databases(data_source: &DataSource) -> Result<Vec<String>> {
+        let mut client = SQLServerConnector::create_client(data_source).await?;
+        let rows = Query::new("SELECT name FROM sys.databases WHERE state = 0 ORDER BY name")
+            .query(&mut client)
+            .await?
+            .into_first_result()
+            .await?;
+        Ok(rows.iter().map(|row| text(row, 0)).collect())
+    }
+
+    /// User schemas of `database`, or of the configured database.
+    pub async fn get_schemas(
+        data_source: &DataSource,
+        database: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let mut data_source = data_source.clone();
+        if let Some(database) = database {
+            data_source.database = Some(database.to_string());
+        }
+        let mut client = SQLServerConnector::create_client(&data_source).await?;
+
+        let mut query = Query::new(
+            "SELECT name FROM sys.schemas
+             WHERE schema_id < @P1 AND name NOT IN ('sys', 'INFORMATION_SCHEMA')
+             ORDER BY name",
         );
-        
-        let mut stream = Query::new(query).query(&mut client).await?;
-        let mut columns = Vec::new();
-        
-        while let Some(item) = stream.next().await {
-            match item? {
-                tiberius::QueryItem::Row(row) => {
-                    let name: String = row.get::<&str, _>(0).unwrap_or_default().to_string();
-                    let data_type: String = row.get::<&str, _>(1).unwrap_or_default().to_string();
-                    let is_nullable: String = row.get::<&str, _>(2).unwrap_or_default().to_string();
-                    let default_value: Option<String> = row.get::<&str, _>(3).map(|s| s.to_string());
-                    let constraints_str: Option<String> = row.get::<&str, _>(4).map(|s| s.to_string());
-                    
-                    let constraints = constraints_str
-                        .map(|s| s.split(',').map(|x| x.trim().to_string()).collect())
-                        .unwrap_or_default();
-                    
-                    columns.push(ColumnInfo {
-                        name,
-                        data_type,
-                        is_nullable: is_nullable == "YES",
-                        default_value,
-                        constraints,
-                    });
+        query.bind(FIRST_ROLE_SCHEMA_ID);
+        let rows = query.query(&mut client).await?.into_first_result().await?;
+        Ok(rows.iter().map(|row| text(row, 0)).collect())
+    }
+
+    /// SQL Server 2016 added temporal tables; Azure SQL always has them whatever
+    /// version it reports.
+    async fn supports_temporal_tables(client: &mut SqlServerClient) -> Result<bool> {
+        let rows = Query::new(
+            "SELECT CAST(SERVERPROPERTY('ProductVersion') AS nvarchar(128)),
+                    CAST(SERVERPROPERTY('EngineEdition') AS int)",
+        )
+        .query(client)
+        .await?
+        .into_first_result()
+        .await?;
+        let Some(row) = rows.first() else {
+            return Ok(false);
+        };
+        let major: u32 = text(row, 0)
+            .split('.')
+            .next()
+            .and_then(|m| m.parse().ok())
+            .unwrap_or(0);
+        let edition = row.get::<i32, _>(1).unwrap_or(0);
+        Ok(major >= 13 || edition == 5 || edition == 8)
+    }
+
+    async fn tables(
+        client: &mut SqlServerClient,
+        schema: Option<&str>,
+        table_name: Option<&str>,
+    ) -> Result<Vec<TableInfo>> {
+        let temporal = Self::supports_temporal_tables(client).await?;
+        let (temporal_type, history_table) = if temporal {
+            (
+                "t.temporal_type_desc",
+                "(SELECT hs.name + '.' + h.name FROM sys.tables h
+                  JOIN sys.schemas hs ON hs.schema_id = h.schema_id
+                  WHERE h.object_id = t.history_table_id)",
+            )
+        } else {
+            ("CAST(NULL AS nvarchar(60))", "CAST(NULL AS nvarchar(256))")
+        };
+        let (filter, params) = filter(schema, table_name);
+
+        let mut query = Query::new(format!(
+            "SELECT s.name, t.name, {}, {}
+             FROM sys.tables t
+             JOIN sys.schemas s ON s.schema_id = t.schema_id
+             WHERE t.is_ms_shipped = 0{}
+             ORDER BY s.name, t.name",
+            temporal_type, history_table, filter
+        ));
+        for param in &params {
+            query.bind(param.clone());
+        }
+        let rows = query.query(client).await?.into_first_result().await?;
+        let mut columns = Self::columns(client, temporal, schema, table_name).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let schema = text(row, 0);
+                let name = text(row, 1);
+                let mut properties = BTreeMap::new();
+                match row.get::<&str, _>(2) {
+                    Some("SYSTEM_VERSIONED_TEMPORAL_TABLE") => {
+                        properties
+                            .insert("temporal_type".to_string(), "system_versioned".to_string());
+                    }
+                    Some("HISTORY_TABLE") => {
+                        properties.insert("temporal_type".to_string(), "history".to_string());
+                    }
+                    _ => {}
+                }
+                if let Some(history_table) = row.get::<&str, _>(3) {
+                    properties.insert("history_table".to_string(), history_table.to_string());
                 }
-                _ => {}
+
+                TableInfo {
+                    columns: columns
+                        .remove(&(schema.clone(), name.clone()))
+                        .unwrap_or_default(),
+                    name,
+                    schema: Some(schema),
+                    row_count: None,
+                    properties,
+                }
+            })
+            .collect())
+    }
+
+    /// Columns keyed by (schema, table), read from the catalog views since
+    /// information_schema has no identity, computed or temporal details.
+    async fn columns(
+        client: &mut SqlServerClient,
+        temporal: bool,
+        schema: Option<&str>,
+        table_name: Option<&str>,
+    ) -> Result<HashMap<(String, String), Vec<ColumnInfo>>> {
+        let period = if temporal {
+            "c.generated_always_type_desc, c.is_hidden"
+        } else {
+            "CAST(NULL AS nvarchar(60)), CAST(0 AS bit)"
+        };
+        let (filter, params) = filter(schema, table_name);
+
+        let mut query = Query::new(format!(
+            "SELECT
+                s.name, t.name, c.name, ty.name,
+                CAST(c.max_length AS int), CAST(c.precision AS int), CAST(c.scale AS int),
+                c.is_nullable, dc.definition, c.collation_name,
+                c.is_identity,
+                CAST(ic.seed_value AS nvarchar(40)), CAST(ic.increment_value AS nvarchar(40)),
+                cc.definition, cc.is_persisted,
+                {},
+                CAST(CASE WHEN EXISTS (
+                    SELECT 1 FROM sys.index_columns xc
+                    JOIN sys.indexes i ON i.object_id = xc.object_id AND i.index_id = xc.index_id
+                    WHERE xc.object_id = c.object_id AND xc.column_id = c.column_id
+                      AND i.is_primary_key = 1) THEN 1 ELSE 0 END AS bit),
+                CAST(CASE WHEN EXISTS (
+                    SELECT 1 FROM sys.index_columns xc
+                    JOIN sys.indexes i ON i.object_id = xc.object_id AND i.index_id = xc.index_id
+                    WHERE xc.object_id = c.object_id AND xc.column_id = c.column_id
+                      AND i.is_unique_constraint = 1) THEN 1 ELSE 0 END AS bit),
+                CAST(CASE WHEN EXISTS (
+                    SELECT 1 FROM sys.foreign_key_columns fc
+                    WHERE fc.parent_object_id = c.object_id AND fc.parent_column_id = c.column_id)
+                    THEN 1 ELSE 0 END AS bit)
+             FROM sys.columns c
+             JOIN sys.tables t ON t.object_id = c.object_id
+             JOIN sys.schemas s ON s.schema_id = t.schema_id
+             JOIN sys.types ty ON ty.user_type_id = c.user_type_id
+             LEFT JOIN sys.default_constraints dc ON dc.object_id = c.default_object_id
+             LEFT JOIN sys.identity_columns ic
+               ON ic.object_id = c.object_id AND ic.column_id = c.column_id
+             LEFT JOIN sys.computed_columns cc
+               ON cc.object_id = c.object_id AND cc.column_id = c.column_id
+             WHERE t.is_ms_shipped = 0{}
+             ORDER BY s.name, t.name, c.column_id",
+            period, filter
+        ));
+        for param in &params {
+            query.bind(param.clone());
+        }
+        let rows = query.query(client).await?.into_first_result().await?;
+
+        let mut columns: HashMap<(String, String), Vec<ColumnInfo>> = HashMap::new();
+        for row in &rows {
+            columns
+                .entry((text(row, 0), text(row, 1)))
+                .or_default()
+                .push(Self::column_info(row));
+        }
+        Ok(columns)
+    }
+
+    fn column_info(row: &Row) -> ColumnInfo {
+        let data_type = text(row, 3);
+        let flag = |index: usize| row.get::<bool, _>(index).unwrap_or(false);
+
+        let mut constraints = Vec::new();
+        if flag(17) {
+            constraints.push("PRIMARY KEY".to_string());
+        }
+        if flag(18) {
+            constraints.push("UNIQUE".to_string());
+        }
+        if flag(19) {
+            constraints.push("FOREIGN KEY".to_string());
+        }
+
+        let mut properties = BTreeMap::new();
+        properties.insert(
+            "column_type".to_string(),
+            column_type(
+                &data_type,
+                row.get::<i32, _>(4).unwrap_or(0),
+                row.get::<i32, _>(5).unwrap_or(0),
+                row.get::<i32, _>(6).unwrap_or(0),
+            ),
+        );
+        if let Some(collation) = row.get::<&str, _>(9) {
+            properties.insert("collation".to_string(), collation.to_string());
+        }
+        if flag(10) {
+            constraints.push("IDENTITY".to_string());
+            properties.insert("identity_seed".to_string(), text(row, 11));
+            properties.insert("identity_increment".to_string(), text(row, 12));
+        }
+        if let Some(definition) = row.get::<&str, _>(13) {
+            constraints.push("COMPUTED".to_string());
+            properties.insert("computed_definition".to_string(), definition.to_string());
+            properties.insert("persisted".to_string(), flag(14).to_string());
+        }
+        match row.get::<&str, _>(15) {
+            Some("AS_ROW_START") => {
+                properties.insert("system_versioning".to_string(), "row_start".to_string());
+            }
+            Some("AS_ROW_END") => {
+                properties.insert("system_versioning".to_string(), "row_end".to_string());
             }
+            _ => {}
+        }
+        if flag(16) {
+            properties.insert("hidden".to_string(), "true".to_string());
+        }
+
+        ColumnInfo {
+            name: text(row, 2),
+            data_type,
+            is_nullable: flag(7),
+            default_value: row.get::<&str, _>(8).map(String::from),
+            constraints,
+            properties,
         }
-        
-        Ok(TableInfo {
-            name: table_name.to_string(),
-            schema: Some(schema.to_string()),
-            row_count: None,
-            columns,
-        })
     }
 
     pub async fn get_table_row_count(
@@ -115,26 +293,100 @@ impl SQLServerMetadata {
         table_name: &str,
     ) -> Result<i64> {
         let mut client = SQLServerConnector::create_client(data_source).await?;
-        let schema = schema.unwrap_or_else(|| data_source.database.as_deref().unwrap_or("dbo"));
-        
+        let schema = schema
+            .map(String::from)
+            .or(data_source.sqlserver_options().schema)
+            .unwrap_or_else(|| DEFAULT_SCHEMA.to_string());
+
         let query = format!(
-            "SELECT COUNT(*) as count FROM [{}].[{}]",
-            schema, table_name
+            "SELECT COUNT_BIG(*) FROM {}.{}",
+            quote_ident(&schema),
+            quote_ident(table_name)
         );
-        
-        let mut stream = Query::new(query).query(&mut client).await?;
-        if let Some(item) = stream.next().await {
-            match item? {
-                tiberius::QueryItem::Row(row) => {
-                    if let Some(count) = row.get::<i32, _>(0) {
-                        return Ok(count as i64);
-                    }
-                }
-                _ => {}
-            }
+        let rows = Query::new(query)
+            .query(&mut client)
+            .await?
+            .into_first_result()
+            .await?;
+        Ok(rows
+            .first()
+            .and_then(|row| row.get::<i64, _>(0))
+            .unwrap_or(0))
+    }
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("[{}]", name.replace(']', "]]"))
+}
+
+/// The WHERE clause additions limiting a catalog query to a schema and table, with
+/// the values to bind in order.
+fn filter(schema: Option<&str>, table_name: Option<&str>) -> (String, Vec<String>) {
+    let mut filter = String::new();
+    let mut params = Vec::new();
+    if let Some(schema) = schema {
+        params.push(schema.to_string());
+        filter.push_str(&format!(" AND s.name = @P{}", params.len()));
+    }
+    if let Some(table_name) = table_name {
+        params.push(table_name.to_string());
+        filter.push_str(&format!(" AND t.name = @P{}", params.len()));
+    }
+    (filter, params)
+}
+
+/// The declared type, e.g. `nvarchar(50)`, `varbinary(max)` or `decimal(18,2)`.
+fn column_type(data_type: &str, max_length: i32, precision: i32, scale: i32) -> String {
+    match data_type {
+        "char" | "varchar" | "binary" | "varbinary" if max_length == -1 => {
+            format!("{}(max)", data_type)
         }
-        
-        Ok(0)
+        "char" | "varchar" | "binary" | "varbinary" => format!("{}({})", data_type, max_length),
+        // Lengths of the Unicode types are in bytes, two per character
+        "nchar" | "nvarchar" if max_length == -1 => format!("{}(max)", data_type),
+        "nchar" | "nvarchar" => format!("{}({})", data_type, max_length / 2),
+        "decimal" | "numeric" => format!("{}({},{})", data_type, precision, scale),
+        "datetime2" | "datetimeoffset" | "time" => format!("{}({})", data_type, scale),
+        _ => data_type.to_string(),
     }
 }
 
+fn text(row: &Row, index: usize) -> String {
+    row.get::<&str, _>(index).unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_number_their_parameters() {
+        assert_eq!(filter(None, None), (String::new(), Vec::new()));
+        assert_eq!(
+            filter(Some("sales"), None),
+            (" AND s.name = @P1".to_string(), vec!["sales".to_string()])
+        );
+        assert_eq!(
+            filter(None, Some("orders")),
+            (" AND t.name = @P1".to_string(), vec!["orders".to_string()])
+        );
+        assert_eq!(
+            filter(Some("sales"), Some("orders")),
+            (
+                " AND s.name = @P1 AND t.name = @P2".to_string(),
+                vec!["sales".to_string(), "orders".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn declared_column_types() {
+        assert_eq!(column_type("varchar", 50, 0, 0), "varchar(50)");
+        assert_eq!(column_type("varbinary", -1, 0, 0), "varbinary(max)");
+        assert_eq!(column_type("nvarchar", 100, 0, 0), "nvarchar(50)");
+        assert_eq!(column_type("nvarchar", -1, 0, 0), "nvarchar(max)");
+        assert_eq!(column_type("decimal", 9, 18, 2), "decimal(18,2)");
+        assert_eq!(column_type("datetime2", 8, 27, 7), "datetime2(7)");
+        assert_eq!(column_type("int", 4, 10, 0), "int");
+    }
+}
@@ -0,0 +1,35 @@
+use crate::db::{Database, DbPool};
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// When set, nothing reaches the network and metadata is served from the cache only
+static OFFLINE_MODE: AtomicBool = AtomicBool::new(false);
+
+const OFFLINE_MODE_SETTING: &str = "offline_mode";
+
+pub fn is_enabled() -> bool {
+    OFFLINE_MODE.load(Ordering::Relaxed)
+}
+
+pub async fn set_enabled(db: &DbPool, enabled: bool) -> Result<()> {
+    db.run(move |db| db.set_setting(OFFLINE_MODE_SETTING, if enabled { "true" } else { "false" }))
+        .await?;
+    OFFLINE_MODE.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Restores the persisted offline flag; called once at startup.
+pub fn load(db: &Database) -> Result<()> {
+    let enabled = db.get_setting(OFFLINE_MODE_SETTING)?.as_deref() == Some("true");
+    OFFLINE_MODE.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn ensure_online() -> Result<()> {
+    if is_enabled() {
+        return Err(anyhow::anyhow!(
+            "Offline mode is enabled; only cached metadata is available"
+        ));
+    }
+    Ok(())
+}
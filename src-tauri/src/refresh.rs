@@ -0,0 +1,195 @@
+use crate::cache::CacheManager;
+use crate::connection::DataSourceKind;
+use crate::db::{DataSource, DbPool, MetadataChangeRecord};
+use crate::metadata::{MetadataFetcher, SchemaChange};
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::time::MissedTickBehavior;
+
+pub const METADATA_CHANGED_EVENT: &str = "metadata-changed";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataChangedEvent {
+    pub data_source_id: i64,
+    pub data_source_name: String,
+    pub cache_type: String,
+    pub cache_key: String,
+    pub changes: Vec<SchemaChange>,
+    pub detected_at: DateTime<Utc>,
+}
+
+pub struct RefreshScheduler;
+
+impl RefreshScheduler {
+    const INTERVAL_MINUTES: u64 = 15;
+    // Only data sources browsed within this window are refreshed
+    const RECENT_USE_HOURS: i64 = 24;
+
+    pub fn start(app: AppHandle, db: DbPool) {
+        tauri::async_runtime::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(Self::INTERVAL_MINUTES * 60));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            // The first tick completes immediately; skip it so startup stays quiet
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = Self::refresh_recent(&app, &db).await {
+                    eprintln!("Background metadata refresh failed: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn refresh_recent(app: &AppHandle, db: &DbPool) -> Result<()> {
+        if crate::offline::is_enabled() {
+            return Ok(());
+        }
+
+        let since = Utc::now() - ChronoDuration::hours(Self::RECENT_USE_HOURS);
+        let data_sources = db
+            .run(move |db| db.list_recently_used_data_sources(since))
+            .await?;
+
+        for data_source in data_sources {
+            match Self::detect_changes(db, &data_source).await {
+                Ok(Some(event)) => Self::emit(app, &event),
+                Ok(None) => {}
+                Err(e) => eprintln!(
+                    "Background refresh failed for data source {}: {}",
+                    data_source.name, e
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn emit(app: &AppHandle, event: &MetadataChangedEvent) {
+        if let Err(e) = app.emit(METADATA_CHANGED_EVENT, event) {
+            eprintln!("Failed to emit {} event: {}", METADATA_CHANGED_EVENT, e);
+        }
+    }
+
+    /// Re-fetches the cached table or topic list of a data source, stores the fresh
+    /// copy and records what changed. None when nothing changed, or when the list was
+    /// never cached since there is nothing to compare.
+    pub async fn detect_changes(
+        db: &DbPool,
+        data_source: &DataSource,
+    ) -> Result<Option<MetadataChangedEvent>> {
+        match data_source.data_type {
+            DataSourceKind::Kafka => {
+                Self::refresh_entry(
+                    db,
+                    data_source,
+                    "topics",
+                    "topics",
+                    MetadataFetcher::get_kafka_topics(data_source),
+                    MetadataFetcher::diff_topic_lists,
+                )
+                .await
+            }
+            _ => {
+                let cache_key = CacheManager::tables_cache_key(data_source);
+                Self::refresh_entry(
+                    db,
+                    data_source,
+                    "tables",
+                    &cache_key,
+                    MetadataFetcher::get_tables(data_source),
+                    MetadataFetcher::diff_table_lists,
+                )
+                .await
+            }
+        }
+    }
+
+    async fn refresh_entry<T, F>(
+        db: &DbPool,
+        data_source: &DataSource,
+        cache_type: &str,
+        cache_key: &str,
+        fetch: F,
+        diff: fn(&[T], &[T]) -> Vec<SchemaChange>,
+    ) -> Result<Option<MetadataChangedEvent>>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<Vec<T>>>,
+    {
+        let baseline: Vec<T> =
+            match Self::baseline(db, data_source.id, cache_type, cache_key).await? {
+                Some(baseline) => baseline,
+                None => return Ok(None),
+            };
+
+        let fresh = fetch.await?;
+        let changes = diff(&baseline, &fresh);
+        CacheManager::store(
+            db,
+            data_source.id,
+            cache_type,
+            cache_key,
+            serde_json::to_value(&fresh)?,
+        )
+        .await?;
+
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        let detected_at = Utc::now();
+        let records: Vec<MetadataChangeRecord> = changes
+            .iter()
+            .map(|change| MetadataChangeRecord {
+                id: 0,
+                data_source_id: data_source.id,
+                cache_type: cache_type.to_string(),
+                cache_key: cache_key.to_string(),
+                object_type: change.object_type.clone(),
+                object_name: change.object_name.clone(),
+                change_type: change.change_type.clone(),
+                old_value: change.old_value.clone(),
+                new_value: change.new_value.clone(),
+                detected_at,
+            })
+            .collect();
+        db.run(move |db| {
+            for record in &records {
+                db.record_metadata_change(record)?;
+            }
+            Ok(())
+        })
+        .await?;
+
+        Ok(Some(MetadataChangedEvent {
+            data_source_id: data_source.id,
+            data_source_name: data_source.name.clone(),
+            cache_type: cache_type.to_string(),
+            cache_key: cache_key.to_string(),
+            changes,
+            detected_at,
+        }))
+    }
+
+    /// Last cached copy of an entry, ignoring expiry.
+    async fn baseline<T: DeserializeOwned>(
+        db: &DbPool,
+        data_source_id: i64,
+        cache_type: &str,
+        cache_key: &str,
+    ) -> Result<Option<T>> {
+        let (cache_type, cache_key) = (cache_type.to_string(), cache_key.to_string());
+        let entry = db
+            .run(move |db| db.get_metadata_cache(data_source_id, &cache_type, &cache_key))
+            .await?;
+
+        Ok(entry.and_then(|entry| serde_json::from_value(entry.cache_data).ok()))
+    }
+}
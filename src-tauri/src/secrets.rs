@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Resolves one kind of secret reference, selected by its scheme, e.g. the `cmd` in
+/// `${cmd:pass show db/prod}`.
+pub trait SecretResolver: Send + Sync {
+    fn scheme(&self) -> &'static str;
+    fn resolve(&self, reference: &str) -> Result<String>;
+}
+
+pub struct EnvResolver;
+
+impl SecretResolver for EnvResolver {
+    fn scheme(&self) -> &'static str {
+        "env"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<String> {
+        std::env::var(reference)
+            .map_err(|_| anyhow::anyhow!("environment variable {} is not set", reference))
+    }
+}
+
+pub struct FileResolver;
+
+impl SecretResolver for FileResolver {
+    fn scheme(&self) -> &'static str {
+        "file"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<String> {
+        let path = expand_home(reference);
+        let content =
+            std::fs::read_to_string(&path).context(format!("cannot read {}", path.display()))?;
+        // Secret files almost always end with a newline that is not part of the secret
+        Ok(content.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+/// Runs a shell command and uses its first line of output, e.g. `pass show db/prod`.
+pub struct CommandResolver;
+
+impl SecretResolver for CommandResolver {
+    fn scheme(&self) -> &'static str {
+        "cmd"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<String> {
+        let output = if cfg!(windows) {
+            Command::new("cmd").args(["/C", reference]).output()
+        } else {
+            Command::new("sh").args(["-c", reference]).output()
+        }
+        .context(format!("cannot run `{}`", reference))?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "`{}` exited with {}: {}",
+                reference,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout)
+            .context(format!("`{}` printed invalid UTF-8", reference))?;
+        Ok(stdout.lines().next().unwrap_or_default().to_string())
+    }
+}
+
+/// Interpolates secret references in configuration strings:
+/// - `${NAME}` or `${env:NAME}`: environment variable
+/// - `${file:path}` or a whole value of `file:path`: file contents
+/// - `${cmd:command}`: first line printed by a shell command, only when enabled
+/// - `$${` escapes a literal `${`, and `\file:` a leading `file:`
+pub struct SecretResolvers {
+    resolvers: Vec<Box<dyn SecretResolver>>,
+}
+
+impl SecretResolvers {
+    /// Environment and file references only; command references must be opted into
+    /// since imported files are often shared.
+    pub fn new(allow_commands: bool) -> Self {
+        let mut resolvers = Self {
+            resolvers: vec![Box::new(EnvResolver), Box::new(FileResolver)],
+        };
+        if allow_commands {
+            resolvers.register(Box::new(CommandResolver));
+        }
+        resolvers
+    }
+
+    /// Adds a resolver; it replaces any existing one for the same scheme.
+    pub fn register(&mut self, resolver: Box<dyn SecretResolver>) {
+        self.resolvers.retain(|r| r.scheme() != resolver.scheme());
+        self.resolvers.push(resolver);
+    }
+
+    pub fn interpolate(&self, value: &str) -> Result<String> {
+        if let Some(path) = value.strip_prefix("file:") {
+            return self.resolve_reference(&format!("file:{}", path));
+        }
+        // Drop the one backslash `escape` added in front of a leading `file:`
+        let value = match value.strip_prefix('\\') {
+            Some(rest) if rest.trim_start_matches('\\').starts_with("file:") => rest,
+            _ => value,
+        };
+
+        let mut result = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(start) = rest.find('$') {
+            result.push_str(&rest[..start]);
+            let tail = &rest[start..];
+
+            if let Some(after) = tail.strip_prefix("$${") {
+                result.push_str("${");
+                rest = after;
+            } else if let Some(after) = tail.strip_prefix("${") {
+                let end = after
+                    .find('}')
+                    .context(format!("unterminated reference in '{}'", value))?;
+                result.push_str(&self.resolve_reference(&after[..end])?);
+                rest = &after[end + 1..];
+            } else {
+                result.push('$');
+                rest = &tail[1..];
+            }
+        }
+        result.push_str(rest);
+
+        Ok(result)
+    }
+
+    /// Escapes a literal value so `interpolate` gives it back unchanged.
+    pub fn escape(value: &str) -> String {
+        let escaped = value.replace("${", "$${");
+        if escaped.trim_start_matches('\\').starts_with("file:") {
+            format!("\\{}", escaped)
+        } else {
+            escaped
+        }
+    }
+
+    fn resolve_reference(&self, reference: &str) -> Result<String> {
+        let (scheme, target) = match reference.split_once(':') {
+            Some((scheme, target)) if self.is_scheme(scheme) || scheme == "cmd" => (scheme, target),
+            _ => ("env", reference),
+        };
+
+        let resolver = self
+            .resolvers
+            .iter()
+            .find(|r| r.scheme() == scheme)
+            .context(format!(
+                "${{{}}}: command references are disabled; allow them to run `{}`",
+                reference, target
+            ))?;
+
+        resolver
+            .resolve(target.trim())
+            .map_err(|e| anyhow::anyhow!("${{{}}}: {}", reference, e))
+    }
+
+    fn is_scheme(&self, scheme: &str) -> bool {
+        self.resolvers.iter().any(|r| r.scheme() == scheme)
+    }
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+        if let Some(home) = home {
+            return PathBuf::from(home).join(rest);
+        }
+    }
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaped_values_interpolate_to_themselves() {
+        let resolvers = SecretResolvers::new(false);
+        for value in [
+            "plain",
+            "pa${ss}word",
+            "$${already}",
+            "$$${x}",
+            "cost $5",
+            "file:/etc/passwd",
+            "\\file:x",
+            "\\\\file:x",
+            "\\not-a-file",
+            "${cmd:rm -rf /}",
+        ] {
+            let escaped = SecretResolvers::escape(value);
+            assert_eq!(
+                resolvers.interpolate(&escaped).unwrap(),
+                value,
+                "{}",
+                escaped
+            );
+        }
+    }
+
+    #[test]
+    fn references_still_resolve() {
+        std::env::set_var("SECRETS_TEST_VALUE", "resolved");
+        let resolvers = SecretResolvers::new(false);
+        assert_eq!(
+            resolvers
+                .interpolate("a-${env:SECRETS_TEST_VALUE}-b")
+                .unwrap(),
+            "a-resolved-b"
+        );
+        assert_eq!(
+            resolvers.interpolate("$${SECRETS_TEST_VALUE}").unwrap(),
+            "${SECRETS_TEST_VALUE}"
+        );
+        assert!(resolvers.interpolate("${cmd:echo hi}").is_err());
+    }
+}
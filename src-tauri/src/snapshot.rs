@@ -0,0 +1,187 @@
+use crate::cache::CacheManager;
+use crate::connection::DataSourceKind;
+use crate::db::{DataSource, Database, DbPool, MetadataCache, MetadataSnapshot};
+use crate::metadata::{KafkaTopicInfo, MetadataFetcher, SchemaChange, TableInfo};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub cache_type: String,
+    pub from_snapshot_id: i64,
+    pub from_taken_at: DateTime<Utc>,
+    pub to_snapshot_id: i64,
+    pub to_taken_at: DateTime<Utc>,
+    pub changes: Vec<SchemaChange>,
+}
+
+pub struct SnapshotManager;
+
+impl SnapshotManager {
+    // Only list-level entries are snapshotted; they are what the diff machinery understands
+    const SNAPSHOT_TYPES: [&'static str; 2] = ["tables", "topics"];
+
+    /// Stores a snapshot of a freshly cached entry unless it matches the latest one.
+    /// Takes an open connection so it runs inside the caller's database task.
+    pub fn record_if_changed(db: &Database, cache: &MetadataCache) -> Result<()> {
+        if !Self::SNAPSHOT_TYPES.contains(&cache.cache_type.as_str()) {
+            return Ok(());
+        }
+
+        let latest = db.find_metadata_snapshot(
+            cache.data_source_id,
+            &cache.cache_type,
+            &cache.cache_key,
+            None,
+        )?;
+        if latest.is_some_and(|s| s.snapshot_data == cache.cache_data) {
+            return Ok(());
+        }
+
+        db.save_metadata_snapshot(&MetadataSnapshot {
+            id: 0,
+            data_source_id: cache.data_source_id,
+            cache_type: cache.cache_type.clone(),
+            cache_key: cache.cache_key.clone(),
+            snapshot_data: cache.cache_data.clone(),
+            label: None,
+            taken_at: cache.cached_at,
+        })?;
+        Ok(())
+    }
+
+    /// Fetches fresh metadata and always stores it as a (optionally labelled) snapshot.
+    pub async fn take_snapshot(
+        db: &DbPool,
+        data_source: &DataSource,
+        label: Option<&str>,
+    ) -> Result<i64> {
+        let (cache_type, cache_key, data) = match data_source.data_type {
+            DataSourceKind::Kafka => {
+                let topics = MetadataFetcher::get_kafka_topics(data_source).await?;
+                (
+                    "topics",
+                    "topics".to_string(),
+                    serde_json::to_value(&topics)?,
+                )
+            }
+            _ => {
+                let tables = MetadataFetcher::get_tables(data_source).await?;
+                (
+                    "tables",
+                    CacheManager::tables_cache_key(data_source),
+                    serde_json::to_value(&tables)?,
+                )
+            }
+        };
+
+        let snapshot = MetadataSnapshot {
+            id: 0,
+            data_source_id: data_source.id,
+            cache_type: cache_type.to_string(),
+            cache_key: cache_key.clone(),
+            snapshot_data: data.clone(),
+            label: label.map(|l| l.to_string()),
+            taken_at: Utc::now(),
+        };
+        let id = db
+            .run(move |db| db.save_metadata_snapshot(&snapshot))
+            .await?;
+
+        // Keep the cache in step; the snapshot above already covers this data
+        CacheManager::store(db, data_source.id, cache_type, &cache_key, data).await?;
+        Ok(id)
+    }
+
+    pub async fn diff(
+        db: &DbPool,
+        from_snapshot_id: i64,
+        to_snapshot_id: i64,
+    ) -> Result<SnapshotDiff> {
+        let (from, to) = db
+            .run(move |db| {
+                Ok((
+                    db.get_metadata_snapshot(from_snapshot_id)
+                        .context(format!("Snapshot {} not found", from_snapshot_id))?,
+                    db.get_metadata_snapshot(to_snapshot_id)
+                        .context(format!("Snapshot {} not found", to_snapshot_id))?,
+                ))
+            })
+            .await?;
+
+        Self::diff_snapshots(&from, &to)
+    }
+
+    /// Diff between the last snapshot taken at or before `since` and the latest one.
+    pub async fn diff_since(
+        db: &DbPool,
+        data_source: &DataSource,
+        since: DateTime<Utc>,
+    ) -> Result<SnapshotDiff> {
+        let (cache_type, cache_key) = match data_source.data_type {
+            DataSourceKind::Kafka => ("topics", "topics".to_string()),
+            _ => ("tables", CacheManager::tables_cache_key(data_source)),
+        };
+
+        let data_source_id = data_source.id;
+        let (from, to) = db
+            .run(move |db| {
+                Ok((
+                    db.find_metadata_snapshot(data_source_id, cache_type, &cache_key, Some(since))?,
+                    db.find_metadata_snapshot(data_source_id, cache_type, &cache_key, None)?,
+                ))
+            })
+            .await?;
+
+        let from = from.ok_or_else(|| {
+            anyhow::anyhow!("No snapshot of {} taken before {}", data_source.name, since)
+        })?;
+        let to =
+            to.ok_or_else(|| anyhow::anyhow!("No snapshots recorded for {}", data_source.name))?;
+
+        Self::diff_snapshots(&from, &to)
+    }
+
+    fn diff_snapshots(from: &MetadataSnapshot, to: &MetadataSnapshot) -> Result<SnapshotDiff> {
+        if from.cache_type != to.cache_type {
+            return Err(anyhow::anyhow!(
+                "Cannot compare a {} snapshot with a {} snapshot",
+                from.cache_type,
+                to.cache_type
+            ));
+        }
+
+        let changes = match from.cache_type.as_str() {
+            "tables" => {
+                let old: Vec<TableInfo> = serde_json::from_value(from.snapshot_data.clone())
+                    .context("Invalid table snapshot")?;
+                let new: Vec<TableInfo> = serde_json::from_value(to.snapshot_data.clone())
+                    .context("Invalid table snapshot")?;
+                MetadataFetcher::diff_table_lists(&old, &new)
+            }
+            "topics" => {
+                let old: Vec<KafkaTopicInfo> = serde_json::from_value(from.snapshot_data.clone())
+                    .context("Invalid topic snapshot")?;
+                let new: Vec<KafkaTopicInfo> = serde_json::from_value(to.snapshot_data.clone())
+                    .context("Invalid topic snapshot")?;
+                MetadataFetcher::diff_topic_lists(&old, &new)
+            }
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Snapshots of type {} cannot be compared",
+                    other
+                ))
+            }
+        };
+
+        Ok(SnapshotDiff {
+            cache_type: from.cache_type.clone(),
+            from_snapshot_id: from.id,
+            from_taken_at: from.taken_at,
+            to_snapshot_id: to.id,
+            to_taken_at: to.taken_at,
+            changes,
+        })
+    }
+}
@@ -0,0 +1,241 @@
+use crate::db::{DataSource, Database, DbPool};
+use crate::secrets::SecretResolvers;
+use crate::yaml_import::{YamlDataSource, YamlImportData};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fs;
+
+// Keys inside proxy, SSH and schema registry configs that hold credentials
+const SECRET_KEYS: [&str; 4] = ["password", "token", "passphrase", "secret"];
+
+#[tauri::command]
+pub async fn export_context(
+    db: tauri::State<'_, DbPool>,
+    context_id: i64,
+    format: Option<String>,
+    include_secrets: bool,
+    file_path: Option<String>,
+) -> Result<String, String> {
+    let export_data = db
+        .run(move |db| export_from(db, context_id, include_secrets))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let content = match format.as_deref().unwrap_or("yaml") {
+        "yaml" | "yml" => serde_yaml::to_string(&export_data)
+            .map_err(|e| format!("Failed to serialize YAML: {}", e))?,
+        "json" => serde_json::to_string_pretty(&export_data)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?,
+        other => return Err(format!("Unsupported export format: {}", other)),
+    };
+
+    if let Some(path) = file_path {
+        fs::write(&path, &content).map_err(|e| format!("Failed to write file: {}", e))?;
+    }
+
+    Ok(content)
+}
+
+/// Builds the import document for a context. Context and data source ids are local to
+/// this store, so the context is referenced by name and imports recreate it as needed.
+fn export_from(db: &Database, context_id: i64, include_secrets: bool) -> Result<YamlImportData> {
+    let context = db
+        .list_contexts()?
+        .into_iter()
+        .find(|c| c.id == context_id)
+        .context(format!("Context {} not found", context_id))?;
+
+    let data_sources = db
+        .list_data_sources(Some(context_id))?
+        .into_iter()
+        .map(|ds| to_yaml_data_source(ds, include_secrets))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(YamlImportData {
+        context_id: None,
+        context_name: Some(context.name),
+        data_sources,
+    })
+}
+
+fn to_yaml_data_source(ds: DataSource, include_secrets: bool) -> Result<YamlDataSource> {
+    let prefix = placeholder_prefix(&ds.name);
+
+    // Imports interpolate these fields, so literal values are escaped to survive that
+    let password = if include_secrets || ds.password.is_empty() {
+        SecretResolvers::escape(&ds.password)
+    } else {
+        placeholder(&prefix, "PASSWORD")
+    };
+
+    let config = |value: Option<Value>, section: &str| -> Result<Option<serde_yaml::Value>> {
+        value
+            .map(|mut v| {
+                escape_strings(&mut v);
+                if !include_secrets {
+                    redact_secrets(&mut v, &format!("{}_{}", prefix, section));
+                }
+                serde_yaml::to_value(&v).context("Failed to convert config to YAML")
+            })
+            .transpose()
+    };
+
+    Ok(YamlDataSource {
+        proxy_config: config(
+            ds.proxy_config.map(serde_json::to_value).transpose()?,
+            "PROXY",
+        )?,
+        ssh_config: config(ds.ssh_config.map(serde_json::to_value).transpose()?, "SSH")?,
+        schema_registry_config: config(ds.schema_registry_config, "SCHEMA_REGISTRY")?,
+        name: ds.name,
+        data_type: ds.data_type.to_string(),
+        host: SecretResolvers::escape(&ds.host),
+        port: ds.port,
+        database: ds.database,
+        username: SecretResolvers::escape(&ds.username),
+        password,
+        proxy_type: ds.proxy_type.map(|p| p.to_string()),
+        schema_registry_url: ds
+            .schema_registry_url
+            .as_deref()
+            .map(SecretResolvers::escape),
+        options: ds
+            .options
+            .map(|o| serde_yaml::to_value(&o))
+            .transpose()
+            .context("Failed to convert options to YAML")?,
+    })
+}
+
+fn escape_strings(value: &mut Value) {
+    match value {
+        Value::String(s) => *s = SecretResolvers::escape(s),
+        Value::Object(map) => map.values_mut().for_each(escape_strings),
+        Value::Array(items) => items.iter_mut().for_each(escape_strings),
+        _ => {}
+    }
+}
+
+/// Replaces non-empty credential values with `${NAME}` placeholders, so whoever imports
+/// the file can see what to fill in.
+fn redact_secrets(value: &mut Value, prefix: &str) {
+    if let Value::Object(map) = value {
+        for (key, v) in map.iter_mut() {
+            let is_secret = SECRET_KEYS.contains(&key.to_lowercase().as_str());
+            match v {
+                Value::String(s) if is_secret && !s.is_empty() => {
+                    *s = placeholder(prefix, &key.to_uppercase());
+                }
+                Value::Object(_) => {
+                    redact_secrets(v, &format!("{}_{}", prefix, key.to_uppercase()))
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn placeholder(prefix: &str, name: &str) -> String {
+    format!("${{{}_{}}}", prefix, name)
+}
+
+/// Environment-variable style name derived from the data source name.
+fn placeholder_prefix(name: &str) -> String {
+    let prefix: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let prefix = prefix.trim_matches('_').to_string();
+    if prefix.is_empty() {
+        "DATA_SOURCE".to_string()
+    } else {
+        prefix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::{DataSourceKind, ProxyType, SshConfig};
+    use crate::yaml_import;
+
+    #[test]
+    fn literal_values_survive_export_and_import() {
+        let now = chrono::Utc::now();
+        let ds = DataSource {
+            id: 1,
+            context_id: 1,
+            name: "prod db".to_string(),
+            data_type: DataSourceKind::Postgresql,
+            host: "file:db.internal".to_string(),
+            port: 5432,
+            database: Some("app".to_string()),
+            username: "${USER}".to_string(),
+            password: "pa${ss}$${word}".to_string(),
+            proxy_type: Some(ProxyType::Ssh),
+            proxy_config: None,
+            ssh_config: Some(SshConfig {
+                host: "bastion".to_string(),
+                port: 22,
+                username: "ops".to_string(),
+                password: Some("file:not-a-path".to_string()),
+                private_key_path: Some("~/.ssh/${key}".to_string()),
+                local_port: 0,
+            }),
+            schema_registry_url: None,
+            schema_registry_config: None,
+            options: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let mut exported = YamlImportData {
+            context_id: None,
+            context_name: Some("prod".to_string()),
+            data_sources: vec![to_yaml_data_source(ds.clone(), true).unwrap()],
+        };
+        let errors = yaml_import::resolve_references(&mut exported, &SecretResolvers::new(false));
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let imported = &exported.data_sources[0];
+        assert_eq!(imported.host, ds.host);
+        assert_eq!(imported.username, ds.username);
+        assert_eq!(imported.password, ds.password);
+        let ssh_config: SshConfig =
+            serde_yaml::from_value(imported.ssh_config.clone().unwrap()).unwrap();
+        assert_eq!(Some(ssh_config), ds.ssh_config);
+    }
+
+    #[test]
+    fn redacted_secrets_become_placeholders() {
+        let now = chrono::Utc::now();
+        let ds = DataSource {
+            id: 1,
+            context_id: 1,
+            name: "prod-db".to_string(),
+            data_type: DataSourceKind::Mysql,
+            host: "db".to_string(),
+            port: 3306,
+            database: None,
+            username: "app".to_string(),
+            password: "secret".to_string(),
+            proxy_type: None,
+            proxy_config: None,
+            ssh_config: None,
+            schema_registry_url: None,
+            schema_registry_config: None,
+            options: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let exported = to_yaml_data_source(ds, false).unwrap();
+        assert_eq!(exported.password, "${PROD_DB_PASSWORD}");
+    }
+}
@@ -1,111 +1,504 @@
-use crate::commands::CreateDataSourceRequest;
-use crate::db::{get_db, Database};
-use anyhow::{Context, Result};
+use crate::connection::{DataSourceKind, DataSourceOptions, ProxyConfig, ProxyType, SshConfig};
+use crate::db::{DataSource, Database, DbPool};
+use crate::secrets::SecretResolvers;
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YamlDataSource {
     pub name: String,
-    pub data_type: String, // mysql, postgresql, sqlserver, kafka
-    pub host: String,
+    pub data_type: String, // mysql, postgresql, sqlserver, kafka, sqlite, duckdb
+    #[serde(default)]
+    pub host: String, // Empty for file databases
+    #[serde(default)]
     pub port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub database: Option<String>,
+    #[serde(default)]
     pub username: String,
+    #[serde(default)]
     pub password: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub proxy_type: Option<String>, // socks5, http, ssh
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub proxy_config: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub ssh_config: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub schema_registry_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_registry_config: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<serde_yaml::Value>, // Kind-specific settings; `kind` may be omitted
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YamlImportData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub context_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub context_name: Option<String>,
     pub data_sources: Vec<YamlDataSource>,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    Create, // Only add new data sources; an existing name is an error
+    #[default]
+    Update, // Add new data sources and overwrite existing ones with the same name
+    Skip,   // Add new data sources and leave existing ones untouched
+    Prune,  // Like update, and delete data sources of the context missing from the file
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportPlanEntry {
+    pub name: String,
+    pub action: String, // create, update, unchanged, skip, delete
+    pub data_source_id: Option<i64>,
+    pub changed_fields: Vec<String>,
+    pub password_command: Option<String>, // Run on every connect once imported
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub context_id: Option<i64>, // None when a dry run would create the context
+    pub context_name: Option<String>,
+    pub context_created: bool,
+    pub dry_run: bool,
+    pub entries: Vec<ImportPlanEntry>,
+    pub errors: Vec<String>,
+}
+
+/// How `import_into` applies a file.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ImportSettings {
+    pub mode: ImportMode,
+    pub dry_run: bool,
+    pub allow_commands: bool, // Whether a password_command may be imported
+}
+
+struct PlannedChange {
+    entry: ImportPlanEntry,
+    data_source: Option<DataSource>,
+}
+
+/// Imports data sources, matching existing ones in the target context by name.
+/// Everything is validated before anything is written, and the writes happen in a
+/// single transaction. With `dry_run` nothing is written and the report shows the plan.
 #[tauri::command]
 pub async fn import_data_sources_from_yaml(
+    db: tauri::State<'_, DbPool>,
     file_path: String,
     context_id: Option<i64>,
-) -> Result<Vec<i64>, String> {
-    let content = fs::read_to_string(&file_path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-    
-    let import_data: YamlImportData = serde_yaml::from_str(&content)
-        .map_err(|e| format!("Failed to parse YAML: {}", e))?;
-    
-    let db_guard = get_db().map_err(|e| e.to_string())?;
-    let db = db_guard.as_ref().ok_or("Database not initialized")?;
-    
-    // Determine context_id
-    let final_context_id = if let Some(cid) = context_id {
-        cid
-    } else if let Some(context_name) = &import_data.context_name {
-        // Create or get context
-        let contexts = db.list_contexts().map_err(|e| e.to_string())?;
-        if let Some(existing) = contexts.iter().find(|c| c.name == *context_name) {
-            existing.id
-        } else {
-            db.create_context(context_name, None).map_err(|e| e.to_string())?
+    allow_secret_commands: Option<bool>,
+    mode: Option<ImportMode>,
+    dry_run: Option<bool>,
+) -> Result<ImportReport, String> {
+    let content =
+        fs::read_to_string(&file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let import_data: YamlImportData =
+        serde_yaml::from_str(&content).map_err(|e| format!("Failed to parse YAML/JSON: {}", e))?;
+
+    // Resolving may read files or run commands, so keep it off the async runtime
+    let resolvers = SecretResolvers::new(allow_secret_commands.unwrap_or(false));
+    let (import_data, errors) = tokio::task::spawn_blocking(move || {
+        let mut import_data = import_data;
+        let errors = resolve_references(&mut import_data, &resolvers);
+        (import_data, errors)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let settings = ImportSettings {
+        mode: mode.unwrap_or_default(),
+        dry_run: dry_run.unwrap_or(false),
+        allow_commands: allow_secret_commands.unwrap_or(false),
+    };
+    db.run(move |db| import_into(db, import_data, context_id, settings, errors))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Replaces `${...}` and `file:` references in connection fields and returns every
+/// reference that could not be resolved rather than stopping at the first.
+pub(crate) fn resolve_references(
+    import_data: &mut YamlImportData,
+    resolvers: &SecretResolvers,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for ds in &mut import_data.data_sources {
+        let name = ds.name.clone();
+        let mut resolve = |field: &str, value: &mut String| match resolvers.interpolate(value) {
+            Ok(resolved) => *value = resolved,
+            Err(e) => errors.push(format!("{}.{}: unresolved reference {}", name, field, e)),
+        };
+
+        resolve("host", &mut ds.host);
+        resolve("username", &mut ds.username);
+        resolve("password", &mut ds.password);
+        if let Some(url) = ds.schema_registry_url.as_mut() {
+            resolve("schema_registry_url", url);
+        }
+        for (field, config) in [
+            ("proxy_config", ds.proxy_config.as_mut()),
+            ("ssh_config", ds.ssh_config.as_mut()),
+            ("schema_registry_config", ds.schema_registry_config.as_mut()),
+        ] {
+            if let Some(config) = config {
+                resolve_value(field, config, &mut resolve);
+            }
+        }
+    }
+
+    errors
+}
+
+fn resolve_value(
+    path: &str,
+    value: &mut serde_yaml::Value,
+    resolve: &mut impl FnMut(&str, &mut String),
+) {
+    match value {
+        serde_yaml::Value::String(s) => resolve(path, s),
+        serde_yaml::Value::Mapping(map) => {
+            for (key, v) in map.iter_mut() {
+                let key = key.as_str().unwrap_or("?");
+                resolve_value(&format!("{}.{}", path, key), v, resolve);
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for (i, v) in items.iter_mut().enumerate() {
+                resolve_value(&format!("{}[{}]", path, i), v, resolve);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn import_into(
+    db: &Database,
+    import_data: YamlImportData,
+    context_id: Option<i64>,
+    settings: ImportSettings,
+    mut errors: Vec<String>,
+) -> Result<ImportReport> {
+    // An explicit context wins over the one named in the file
+    let contexts = db.list_contexts()?;
+    let (context_id, context_name) = if let Some(cid) = context_id.or(import_data.context_id) {
+        match contexts.iter().find(|c| c.id == cid) {
+            Some(context) => (Some(context.id), context.name.clone()),
+            None => return Err(anyhow::anyhow!("Context {} not found", cid)),
         }
-    } else if let Some(cid) = import_data.context_id {
-        cid
+    } else if let Some(name) = import_data.context_name.clone() {
+        let existing = contexts.iter().find(|c| c.name == name).map(|c| c.id);
+        (existing, name)
     } else {
-        return Err("Context ID or context name must be provided".to_string());
+        return Err(anyhow::anyhow!(
+            "Context ID or context name must be provided"
+        ));
     };
-    
-    let mut created_ids = Vec::new();
-    
+
+    let mut incoming = Vec::new();
+    let mut seen = HashSet::new();
     for yaml_ds in import_data.data_sources {
-        // Convert YAML values to JSON values
-        let proxy_config = yaml_ds.proxy_config.map(|v| {
-            serde_json::to_value(&v).unwrap_or(serde_json::Value::Null)
-        });
-        
-        let ssh_config = yaml_ds.ssh_config.map(|v| {
-            serde_json::to_value(&v).unwrap_or(serde_json::Value::Null)
-        });
-        
-        let req = CreateDataSourceRequest {
-            context_id: final_context_id,
-            name: yaml_ds.name,
-            data_type: yaml_ds.data_type,
-            host: yaml_ds.host,
-            port: yaml_ds.port,
-            database: yaml_ds.database,
-            username: yaml_ds.username,
-            password: yaml_ds.password,
-            proxy_type: yaml_ds.proxy_type,
-            proxy_config,
-            ssh_config,
-            schema_registry_url: yaml_ds.schema_registry_url,
+        if !seen.insert(yaml_ds.name.clone()) {
+            errors.push(format!(
+                "{}: name appears more than once in the file",
+                yaml_ds.name
+            ));
+            continue;
+        }
+        match to_data_source(yaml_ds) {
+            Ok(ds) => {
+                // A password command runs on every connect, so it is held to the same
+                // opt-in as command secret references
+                let command = ds.options.as_ref().and_then(|o| o.password_command());
+                if command.is_some() && !settings.allow_commands {
+                    errors.push(format!(
+                        "{}: options.password_command runs a local command; allow secret commands to import it",
+                        ds.name
+                    ));
+                }
+                incoming.push(ds);
+            }
+            Err(problems) => errors.extend(problems),
+        }
+    }
+
+    let existing = match context_id {
+        Some(cid) => db.list_data_sources(Some(cid))?,
+        None => Vec::new(),
+    };
+    let mut plan = plan_changes(&existing, incoming, settings.mode, &mut errors);
+
+    let mut report = ImportReport {
+        context_id,
+        context_name: Some(context_name.clone()),
+        context_created: context_id.is_none(),
+        dry_run: settings.dry_run,
+        entries: Vec::new(),
+        errors,
+    };
+
+    if settings.dry_run {
+        report.entries = plan.into_iter().map(|change| change.entry).collect();
+        return Ok(report);
+    }
+    if !report.errors.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Import aborted, nothing was changed:\n{}",
+            report.errors.join("\n")
+        ));
+    }
+
+    let context_id = db.transaction(|db| {
+        let context_id = match context_id {
+            Some(cid) => cid,
+            None => db.create_context(&context_name, None)?,
         };
-        
-        let data_source = crate::db::DataSource {
-            id: 0,
-            context_id: req.context_id,
-            name: req.name,
-            data_type: req.data_type,
-            host: req.host,
-            port: req.port,
-            database: req.database,
-            username: req.username,
-            password: req.password,
-            proxy_type: req.proxy_type,
-            proxy_config: req.proxy_config,
-            ssh_config: req.ssh_config,
-            schema_registry_url: req.schema_registry_url,
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
+
+        for change in &mut plan {
+            match (change.entry.action.as_str(), change.data_source.as_mut()) {
+                ("create", Some(ds)) => {
+                    ds.context_id = context_id;
+                    change.entry.data_source_id = Some(db.create_data_source(ds)?);
+                }
+                ("update", Some(ds)) => db.update_data_source(ds)?,
+                ("delete", _) => {
+                    if let Some(id) = change.entry.data_source_id {
+                        db.delete_data_source(id)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(context_id)
+    })?;
+
+    report.context_id = Some(context_id);
+    report.entries = plan.into_iter().map(|change| change.entry).collect();
+    Ok(report)
+}
+
+fn plan_changes(
+    existing: &[DataSource],
+    incoming: Vec<DataSource>,
+    mode: ImportMode,
+    errors: &mut Vec<String>,
+) -> Vec<PlannedChange> {
+    let mut plan = Vec::new();
+    let incoming_names: HashSet<String> = incoming.iter().map(|ds| ds.name.clone()).collect();
+
+    for mut ds in incoming {
+        let current = existing.iter().find(|e| e.name == ds.name);
+        let (action, data_source_id, fields) = match (current, mode) {
+            (None, _) => ("create", None, Vec::new()),
+            (Some(current), ImportMode::Create) => {
+                errors.push(format!(
+                    "{}: a data source with this name already exists",
+                    ds.name
+                ));
+                ("skip", Some(current.id), Vec::new())
+            }
+            (Some(current), ImportMode::Skip) => ("skip", Some(current.id), Vec::new()),
+            (Some(current), ImportMode::Update | ImportMode::Prune) => {
+                let changed = changed_fields(current, &ds);
+                ds.id = current.id;
+                ds.context_id = current.context_id;
+                ds.created_at = current.created_at;
+                if changed.is_empty() {
+                    ("unchanged", Some(current.id), changed)
+                } else {
+                    ("update", Some(current.id), changed)
+                }
+            }
         };
-        
-        let id = db.create_data_source(&data_source).map_err(|e| e.to_string())?;
-        created_ids.push(id);
+
+        plan.push(PlannedChange {
+            entry: ImportPlanEntry {
+                name: ds.name.clone(),
+                action: action.to_string(),
+                data_source_id,
+                changed_fields: fields,
+                password_command: ds
+                    .options
+                    .as_ref()
+                    .and_then(|o| o.password_command())
+                    .map(|c| c.to_string()),
+            },
+            data_source: Some(ds),
+        });
+    }
+
+    if mode == ImportMode::Prune {
+        for current in existing
+            .iter()
+            .filter(|e| !incoming_names.contains(&e.name))
+        {
+            plan.push(PlannedChange {
+                entry: ImportPlanEntry {
+                    name: current.name.clone(),
+                    action: "delete".to_string(),
+                    data_source_id: Some(current.id),
+                    changed_fields: Vec::new(),
+                    password_command: None,
+                },
+                data_source: None,
+            });
+        }
     }
-    
-    Ok(created_ids)
+
+    plan
 }
 
+/// Names of the fields that differ; secrets are listed but their values never are.
+fn changed_fields(current: &DataSource, incoming: &DataSource) -> Vec<String> {
+    let checks = [
+        ("data_type", current.data_type != incoming.data_type),
+        ("host", current.host != incoming.host),
+        ("port", current.port != incoming.port),
+        ("database", current.database != incoming.database),
+        ("username", current.username != incoming.username),
+        ("password", current.password != incoming.password),
+        ("proxy_type", current.proxy_type != incoming.proxy_type),
+        (
+            "proxy_config",
+            current.proxy_config != incoming.proxy_config,
+        ),
+        ("ssh_config", current.ssh_config != incoming.ssh_config),
+        (
+            "schema_registry_url",
+            current.schema_registry_url != incoming.schema_registry_url,
+        ),
+        (
+            "schema_registry_config",
+            current.schema_registry_config != incoming.schema_registry_config,
+        ),
+        ("options", current.options != incoming.options),
+    ];
+
+    checks
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(field, _)| field.to_string())
+        .collect()
+}
+
+/// Converts and validates one entry, collecting every problem found.
+fn to_data_source(yaml_ds: YamlDataSource) -> std::result::Result<DataSource, Vec<String>> {
+    let name = yaml_ds.name.clone();
+    let mut problems = Vec::new();
+    let mut problem = |message: String| problems.push(format!("{}: {}", name, message));
+
+    let data_type = match yaml_ds.data_type.parse::<DataSourceKind>() {
+        Ok(kind) => Some(kind),
+        Err(e) => {
+            problem(e.to_string());
+            None
+        }
+    };
+    let proxy_type = match yaml_ds
+        .proxy_type
+        .as_deref()
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse::<ProxyType>())
+    {
+        Some(Ok(proxy_type)) => Some(proxy_type),
+        Some(Err(e)) => {
+            problem(e.to_string());
+            None
+        }
+        None => None,
+    };
+    let proxy_config = match yaml_ds
+        .proxy_config
+        .map(serde_yaml::from_value::<ProxyConfig>)
+    {
+        Some(Ok(config)) => Some(config),
+        Some(Err(e)) => {
+            problem(format!("proxy_config is invalid: {}", e));
+            None
+        }
+        None => None,
+    };
+    let ssh_config = match yaml_ds.ssh_config.map(serde_yaml::from_value::<SshConfig>) {
+        Some(Ok(config)) => Some(config),
+        Some(Err(e)) => {
+            problem(format!("ssh_config is invalid: {}", e));
+            None
+        }
+        None => None,
+    };
+
+    let mut to_json = |field: &str, value: Option<serde_yaml::Value>| {
+        value.and_then(|v| match serde_json::to_value(&v) {
+            Ok(json) => Some(json),
+            Err(e) => {
+                problem(format!("{} is not valid: {}", field, e));
+                None
+            }
+        })
+    };
+    let schema_registry_config = to_json("schema_registry_config", yaml_ds.schema_registry_config);
+    let options = to_json("options", yaml_ds.options);
+
+    // The kind of the options may be left out in YAML; it follows data_type
+    let options = match (options, data_type) {
+        (Some(mut options), Some(kind)) => {
+            if let Some(map) = options.as_object_mut() {
+                map.entry("kind").or_insert_with(|| kind.as_str().into());
+            }
+            match serde_json::from_value::<DataSourceOptions>(options) {
+                Ok(options) => Some(options),
+                Err(e) => {
+                    problem(format!("options are invalid: {}", e));
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    let Some(data_type) = data_type else {
+        return Err(problems);
+    };
+
+    let now = chrono::Utc::now();
+    let data_source = DataSource {
+        id: 0,
+        context_id: 0,
+        name: yaml_ds.name,
+        data_type,
+        host: yaml_ds.host,
+        port: yaml_ds.port,
+        database: yaml_ds.database,
+        username: yaml_ds.username,
+        password: yaml_ds.password,
+        proxy_type,
+        proxy_config,
+        ssh_config,
+        schema_registry_url: yaml_ds.schema_registry_url,
+        schema_registry_config,
+        options,
+        created_at: now,
+        updated_at: now,
+    };
+    problems.extend(
+        data_source
+            .problems()
+            .into_iter()
+            .map(|p| format!("{}: {}", name, p)),
+    );
+
+    if problems.is_empty() {
+        Ok(data_source)
+    } else {
+        Err(problems)
+    }
+}
@@ -1,12 +1,20 @@
 import { ref } from 'vue';
 import { invoke } from '@tauri-apps/api/core';
-import type { Context, DataSource, CreateDataSourceRequest, TableInfo, KafkaTopicInfo, SchemaInfo, TableComparison } from '../types';
+import type { Context, DataSource, DataSourceKind, CreateDataSourceRequest, Capability, ConnectionDiagnostics, QueryResult, TableInfo, KafkaTopicInfo, PostgresObjects, RedisKeyPage, RedisValue, RedisInfoSection, SchemaInfo, TableComparison, CachedMetadata } from '../types';
 
 export function useDataSources() {
   const contexts = ref<Context[]>([]);
   const dataSources = ref<DataSource[]>([]);
   const loading = ref(false);
   const error = ref<string | null>(null);
+  // Freshness of the last metadata response; stale data is served when a source is unreachable
+  const lastCacheStatus = ref<Omit<CachedMetadata<unknown>, 'data'> | null>(null);
+
+  const unwrapCached = <T>(result: CachedMetadata<T>): T => {
+    const { data, ...status } = result;
+    lastCacheStatus.value = status;
+    return data;
+  };
 
   const loadContexts = async () => {
     try {
@@ -90,12 +98,46 @@ export function useDataSources() {
     }
   };
 
-  const testConnection = async (dataSource: DataSource) => {
+  const exportContext = async (
+    contextId: number,
+    format: 'yaml' | 'json' = 'yaml',
+    includeSecrets = false,
+    filePath?: string
+  ): Promise<string> => {
+    try {
+      loading.value = true;
+      error.value = null;
+      return await invoke<string>('export_context', { contextId, format, includeSecrets, filePath });
+    } catch (e: any) {
+      error.value = e.toString();
+      throw e;
+    } finally {
+      loading.value = false;
+    }
+  };
+
+  const testConnection = async (dataSource: DataSource): Promise<ConnectionDiagnostics> => {
     try {
       loading.value = true;
       error.value = null;
-      await invoke('test_connection', { dataSource });
-      return true;
+      return await invoke<ConnectionDiagnostics>('test_connection', { dataSource });
+    } catch (e: any) {
+      error.value = e.toString();
+      throw e;
+    } finally {
+      loading.value = false;
+    }
+  };
+
+  const getConnectorCapabilities = async (dataType: DataSourceKind): Promise<Capability[]> => {
+    return await invoke<Capability[]>('get_connector_capabilities', { dataType });
+  };
+
+  const executeQuery = async (dataSourceId: number, sql: string, maxRows?: number): Promise<QueryResult> => {
+    try {
+      loading.value = true;
+      error.value = null;
+      return await invoke<QueryResult>('execute_query', { dataSourceId, sql, maxRows });
     } catch (e: any) {
       error.value = e.toString();
       throw e;
@@ -108,7 +150,7 @@ export function useDataSources() {
     try {
       loading.value = true;
       error.value = null;
-      return await invoke<TableInfo[]>('get_tables', { dataSourceId, forceRefresh });
+      return unwrapCached(await invoke<CachedMetadata<TableInfo[]>>('get_tables', { dataSourceId, forceRefresh }));
     } catch (e: any) {
       error.value = e.toString();
       throw e;
@@ -126,12 +168,90 @@ export function useDataSources() {
     try {
       loading.value = true;
       error.value = null;
-      return await invoke<TableInfo>('get_table_structure', {
+      return unwrapCached(await invoke<CachedMetadata<TableInfo>>('get_table_structure', {
         dataSourceId,
         schema,
         tableName,
         forceRefresh,
-      });
+      }));
+    } catch (e: any) {
+      error.value = e.toString();
+      throw e;
+    } finally {
+      loading.value = false;
+    }
+  };
+
+  const listDatabases = async (dataSourceId: number, forceRefresh = false): Promise<string[]> => {
+    try {
+      loading.value = true;
+      error.value = null;
+      return unwrapCached(await invoke<CachedMetadata<string[]>>('list_databases', { dataSourceId, forceRefresh }));
+    } catch (e: any) {
+      error.value = e.toString();
+      throw e;
+    } finally {
+      loading.value = false;
+    }
+  };
+
+  const listSchemas = async (dataSourceId: number, database?: string, forceRefresh = false): Promise<string[]> => {
+    try {
+      loading.value = true;
+      error.value = null;
+      return unwrapCached(await invoke<CachedMetadata<string[]>>('list_schemas', { dataSourceId, database, forceRefresh }));
+    } catch (e: any) {
+      error.value = e.toString();
+      throw e;
+    } finally {
+      loading.value = false;
+    }
+  };
+
+  const getPostgresObjects = async (dataSourceId: number, schema?: string, forceRefresh = false): Promise<PostgresObjects> => {
+    try {
+      loading.value = true;
+      error.value = null;
+      return unwrapCached(await invoke<CachedMetadata<PostgresObjects>>('get_postgres_objects', { dataSourceId, schema, forceRefresh }));
+    } catch (e: any) {
+      error.value = e.toString();
+      throw e;
+    } finally {
+      loading.value = false;
+    }
+  };
+
+  const scanRedisKeys = async (dataSourceId: number, pattern?: string, cursor?: number): Promise<RedisKeyPage> => {
+    try {
+      loading.value = true;
+      error.value = null;
+      return await invoke<RedisKeyPage>('scan_redis_keys', { dataSourceId, pattern, cursor });
+    } catch (e: any) {
+      error.value = e.toString();
+      throw e;
+    } finally {
+      loading.value = false;
+    }
+  };
+
+  const getRedisValue = async (dataSourceId: number, key: string, limit?: number): Promise<RedisValue> => {
+    try {
+      loading.value = true;
+      error.value = null;
+      return await invoke<RedisValue>('get_redis_value', { dataSourceId, key, limit });
+    } catch (e: any) {
+      error.value = e.toString();
+      throw e;
+    } finally {
+      loading.value = false;
+    }
+  };
+
+  const getRedisInfo = async (dataSourceId: number, forceRefresh = false): Promise<RedisInfoSection[]> => {
+    try {
+      loading.value = true;
+      error.value = null;
+      return unwrapCached(await invoke<CachedMetadata<RedisInfoSection[]>>('get_redis_info', { dataSourceId, forceRefresh }));
     } catch (e: any) {
       error.value = e.toString();
       throw e;
@@ -144,7 +264,7 @@ export function useDataSources() {
     try {
       loading.value = true;
       error.value = null;
-      return await invoke<KafkaTopicInfo[]>('get_kafka_topics', { dataSourceId, forceRefresh });
+      return unwrapCached(await invoke<CachedMetadata<KafkaTopicInfo[]>>('get_kafka_topics', { dataSourceId, forceRefresh }));
     } catch (e: any) {
       error.value = e.toString();
       throw e;
@@ -157,7 +277,7 @@ export function useDataSources() {
     try {
       loading.value = true;
       error.value = null;
-      return await invoke<SchemaInfo[]>('get_schema_registry_schemas', { dataSourceId, forceRefresh });
+      return unwrapCached(await invoke<CachedMetadata<SchemaInfo[]>>('get_schema_registry_schemas', { dataSourceId, forceRefresh }));
     } catch (e: any) {
       error.value = e.toString();
       throw e;
@@ -209,16 +329,26 @@ export function useDataSources() {
     dataSources,
     loading,
     error,
+    lastCacheStatus,
     loadContexts,
     createContext,
     updateContext,
     deleteContext,
     loadDataSources,
     createDataSource,
+    exportContext,
     testConnection,
+    getConnectorCapabilities,
+    executeQuery,
     getTables,
     getTableStructure,
     getKafkaTopics,
+    listDatabases,
+    listSchemas,
+    getPostgresObjects,
+    scanRedisKeys,
+    getRedisValue,
+    getRedisInfo,
     getSchemaRegistrySchemas,
     refreshMetadata,
     compareTables,
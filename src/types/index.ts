@@ -6,20 +6,98 @@ export interface Context {
   updated_at: string;
 }
 
+export type DataSourceKind = 'mysql' | 'postgresql' | 'sqlserver' | 'kafka' | 'sqlite' | 'duckdb' | 'clickhouse' | 'mongodb' | 'redis' | 'elasticsearch';
+
+export type TlsMode = 'disable' | 'prefer' | 'require' | 'verify-ca' | 'verify-full';
+
+export type DataSourceOptions =
+  | {
+      kind: 'mysql';
+      tls_mode?: TlsMode;
+      auth_method?: 'native' | 'caching_sha2' | 'cleartext';
+      charset?: string;
+      connect_timeout_secs?: number;
+      ca_cert_path?: string;
+      client_cert_path?: string;
+      client_key_path?: string;
+      password_command?: string;
+      token_lifetime_secs?: number;
+    }
+  | {
+      kind: 'postgresql';
+      tls_mode?: TlsMode;
+      auth_method?: 'password' | 'client_cert';
+      application_name?: string;
+      connect_timeout_secs?: number;
+      schema?: string;
+      ca_cert_path?: string;
+      client_cert_path?: string;
+      client_key_path?: string;
+      password_command?: string;
+      token_lifetime_secs?: number;
+    }
+  | {
+      kind: 'sqlserver';
+      tls_mode?: TlsMode;
+      auth_method?: 'sql_server' | 'integrated' | 'azure_ad_token';
+      instance_name?: string;
+      application_name?: string;
+      schema?: string;
+      ca_cert_path?: string;
+      password_command?: string;
+      token_lifetime_secs?: number;
+    }
+  | {
+      kind: 'kafka';
+      security_protocol?: 'PLAINTEXT' | 'SSL' | 'SASL_PLAINTEXT' | 'SASL_SSL';
+      sasl_mechanism?: 'PLAIN' | 'SCRAM-SHA-256' | 'SCRAM-SHA-512';
+      client_id?: string;
+    }
+  | { kind: 'sqlite' | 'duckdb'; read_only?: boolean }
+  | {
+      kind: 'clickhouse';
+      secure?: boolean;
+      insecure_skip_verify?: boolean;
+      max_execution_time_secs?: number;
+    }
+  | {
+      kind: 'mongodb';
+      srv?: boolean;
+      auth_source?: string;
+      replica_set?: string;
+      tls?: boolean;
+      direct_connection?: boolean;
+      sample_size?: number;
+    }
+  | {
+      kind: 'redis';
+      tls?: boolean;
+      insecure_skip_verify?: boolean;
+      scan_count?: number;
+    }
+  | {
+      kind: 'elasticsearch';
+      secure?: boolean;
+      insecure_skip_verify?: boolean;
+      include_hidden?: boolean;
+    };
+
 export interface DataSource {
   id: number;
   context_id: number;
   name: string;
-  data_type: 'mysql' | 'postgresql' | 'sqlserver' | 'kafka';
+  data_type: DataSourceKind;
   host: string;
   port: number;
   database?: string;
   username: string;
   password: string;
-  proxy_type?: 'socks5' | 'http' | 'ssh';
-  proxy_config?: any;
-  ssh_config?: any;
+  proxy_type?: 'socks5' | 'http' | 'ssh' | null;
+  proxy_config?: ProxyConfig | null;
+  ssh_config?: SshConfig | null;
   schema_registry_url?: string;
+  schema_registry_config?: SchemaRegistryConfig;
+  options?: DataSourceOptions;
   created_at: string;
   updated_at: string;
 }
@@ -27,16 +105,68 @@ export interface DataSource {
 export interface CreateDataSourceRequest {
   context_id: number;
   name: string;
-  data_type: 'mysql' | 'postgresql' | 'sqlserver' | 'kafka';
+  data_type: DataSourceKind;
   host: string;
   port: number;
   database?: string;
   username: string;
   password: string;
-  proxy_type?: 'socks5' | 'http' | 'ssh';
-  proxy_config?: any;
-  ssh_config?: any;
+  proxy_type?: 'socks5' | 'http' | 'ssh' | null;
+  proxy_config?: ProxyConfig | null;
+  ssh_config?: SshConfig | null;
   schema_registry_url?: string;
+  schema_registry_config?: SchemaRegistryConfig;
+  options?: DataSourceOptions;
+}
+
+export type Capability =
+  | 'test_connection'
+  | 'tables'
+  | 'table_structure'
+  | 'row_count'
+  | 'query'
+  | 'topics'
+  | 'keys'
+  | 'server_info'
+  | 'schema_objects'
+  | 'schemas'
+  | 'databases';
+
+export interface TlsInfo {
+  encrypted: boolean;
+  version?: string;
+  cipher?: string;
+  certificate_subject?: string;
+  certificate_issuer?: string;
+  certificate_error?: string;
+}
+
+export interface DiagnosticStage {
+  name: string;
+  status: 'passed' | 'failed' | 'skipped';
+  duration_ms?: number;
+  detail?: string;
+  hint?: string;
+}
+
+export interface ServerDetails {
+  version?: string;
+  current_user?: string;
+  current_database?: string;
+}
+
+export interface ConnectionDiagnostics {
+  success: boolean;
+  stages: DiagnosticStage[];
+  tls?: TlsInfo;
+  server?: ServerDetails;
+}
+
+export interface QueryResult {
+  columns: string[];
+  rows: unknown[][];
+  rows_affected?: number;
+  truncated: boolean;
 }
 
 export interface TableInfo {
@@ -44,6 +174,7 @@ export interface TableInfo {
   schema?: string;
   row_count?: number;
   columns: ColumnInfo[];
+  properties?: Record<string, string>;
 }
 
 export interface ColumnInfo {
@@ -52,6 +183,7 @@ export interface ColumnInfo {
   is_nullable: boolean;
   default_value?: string;
   constraints: string[];
+  properties?: Record<string, string>;
 }
 
 export interface TableComparison {
@@ -82,10 +214,217 @@ export interface PartitionInfo {
   isr: number[];
 }
 
+export interface RedisKeyInfo {
+  key: string;
+  key_type: string;
+  ttl_ms?: number;
+  memory_bytes?: number;
+}
+
+export interface RedisKeyPage {
+  cursor: number;
+  keys: RedisKeyInfo[];
+}
+
+export interface RedisValue {
+  key: RedisKeyInfo;
+  length?: number;
+  value: unknown;
+  truncated: boolean;
+}
+
+export interface RedisInfoSection {
+  name: string;
+  entries: Record<string, string>;
+}
+
+export interface PostgresObjects {
+  enums: { schema: string; name: string; labels: string[] }[];
+  domains: {
+    schema: string;
+    name: string;
+    base_type: string;
+    is_nullable: boolean;
+    default_value?: string;
+    check?: string;
+  }[];
+  composite_types: { schema: string; name: string; attributes: ColumnInfo[] }[];
+  extensions: { name: string; version: string; schema: string }[];
+  sequences: {
+    schema: string;
+    name: string;
+    data_type: string;
+    start_value: number;
+    min_value: number;
+    max_value: number;
+    increment: number;
+    cycle: boolean;
+    last_value?: number;
+  }[];
+}
+
+export interface ProxyConfig {
+  proxy_type: string;
+  host: string;
+  port: number;
+  username?: string | null;
+  password?: string | null;
+}
+
+export interface SshConfig {
+  host: string;
+  port: number;
+  username: string;
+  password?: string | null;
+  private_key_path?: string | null;
+  local_port: number;
+}
+
+export interface SchemaRegistryConfig {
+  auth_type?: 'basic' | 'bearer';
+  username?: string;
+  password?: string;
+  token?: string;
+  ca_cert_path?: string;
+  client_cert_path?: string;
+  client_key_path?: string;
+  insecure_skip_verify?: boolean;
+  max_concurrency?: number;
+}
+
 export interface SchemaInfo {
   subject: string;
   version: number;
+  id?: number;
   schema_type: string;
   schema: string;
 }
 
+export interface SchemaDiff {
+  subject: string;
+  version1: number;
+  version2: number;
+  schema_type: string;
+  diffs: StructureDiff[];
+}
+
+export interface CompatibilityLevel {
+  subject?: string;
+  level: string;
+  is_global_default: boolean;
+}
+
+export interface CompatibilityCheckResult {
+  subject: string;
+  version: string;
+  is_compatible: boolean;
+  messages: string[];
+}
+
+
+export interface CachePolicy {
+  id: number;
+  data_source_id?: number;
+  cache_type: string;
+  ttl_seconds?: number;
+  updated_at: string;
+}
+
+export interface SchemaChange {
+  object_type: 'table' | 'column' | 'topic';
+  object_name: string;
+  change_type: 'added' | 'removed' | 'modified';
+  old_value?: string;
+  new_value?: string;
+}
+
+export interface MetadataChangedEvent {
+  data_source_id: number;
+  data_source_name: string;
+  cache_type: string;
+  cache_key: string;
+  changes: SchemaChange[];
+  detected_at: string;
+}
+
+export interface MetadataChangeRecord {
+  id: number;
+  data_source_id: number;
+  cache_type: string;
+  cache_key: string;
+  object_type: string;
+  object_name: string;
+  change_type: 'added' | 'removed' | 'modified';
+  old_value?: string;
+  new_value?: string;
+  detected_at: string;
+}
+
+export interface MetadataSnapshotInfo {
+  id: number;
+  data_source_id: number;
+  cache_type: string;
+  cache_key: string;
+  object_count: number;
+  label?: string;
+  taken_at: string;
+}
+
+export interface SnapshotDiff {
+  cache_type: string;
+  from_snapshot_id: number;
+  from_taken_at: string;
+  to_snapshot_id: number;
+  to_taken_at: string;
+  changes: SchemaChange[];
+}
+
+export interface CachedMetadata<T> {
+  data: T;
+  stale: boolean;
+  cached_at: string;
+  revalidating: boolean;
+  error?: string;
+}
+
+export interface MetadataRevalidatedEvent {
+  data_source_id: number;
+  cache_type: string;
+  cache_key: string;
+  error?: string;
+}
+
+export interface DatabaseLocation {
+  path: string;
+  profile?: string;
+  source: 'default' | 'profile' | 'cli' | 'env';
+  migrated_from?: string;
+}
+
+export type ImportMode = 'create' | 'update' | 'skip' | 'prune';
+
+export interface ImportPlanEntry {
+  name: string;
+  action: 'create' | 'update' | 'unchanged' | 'skip' | 'delete';
+  data_source_id?: number;
+  changed_fields: string[];
+  password_command?: string;
+}
+
+export interface ImportReport {
+  context_id?: number;
+  context_name?: string;
+  context_created: boolean;
+  dry_run: boolean;
+  entries: ImportPlanEntry[];
+  errors: string[];
+}
+
+export type ExternalFormat = 'dbeaver' | 'datagrip' | 'pgpass' | 'my_cnf' | 'kafka_properties';
+
+export interface ExternalImportPreview {
+  format: ExternalFormat;
+  data_sources: Omit<CreateDataSourceRequest, 'context_id'>[];
+  warnings: string[];
+  report: ImportReport;
+}